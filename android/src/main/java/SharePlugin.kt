@@ -1,17 +1,31 @@
 package plugin.vnidrop.share
 
 import android.app.Activity
+import android.app.PendingIntent
+import android.content.ActivityNotFoundException
+import android.content.BroadcastReceiver
 import android.content.ClipData
+import android.content.ComponentName
+import android.content.Context
 import android.content.Intent
+import android.content.IntentFilter
 import android.net.Uri
+import android.os.Build
+import android.provider.OpenableColumns
 import android.util.Base64
+import android.util.Log
+import android.webkit.WebView
 import java.io.File
 import java.io.FileOutputStream
+import androidx.activity.result.ActivityResult
+import androidx.core.content.ContextCompat
 import androidx.core.content.FileProvider
+import app.tauri.annotation.ActivityCallback
 import app.tauri.annotation.Command
 import app.tauri.annotation.InvokeArg
 import app.tauri.annotation.TauriPlugin
 import app.tauri.plugin.Invoke
+import app.tauri.plugin.JSArray
 import app.tauri.plugin.JSObject
 import app.tauri.plugin.Plugin
 import java.io.IOException
@@ -20,6 +34,8 @@ import java.util.UUID
 @InvokeArg
 class SharedFile {
     lateinit var data: String
+    // The path of a handed-off file, which is moved into the share directory instead of `data`.
+    var path: String? = null
     lateinit var name: String
     lateinit var mimeType: String
 }
@@ -30,13 +46,62 @@ class ShareOptions {
     var title: String? = null
     var url: String? = null
     var files: List<SharedFile>? = null
+    var waitForDismissal: Boolean? = null
+    var returnUrl: String? = null
+    var expiresAt: Long? = null
+    var cc: List<String>? = null
+    var replyTo: String? = null
 }
 
+private const val ACTION_TARGET_CHOSEN = "plugin.vnidrop.share.TARGET_CHOSEN"
+private const val EXTRA_EXPIRES_AT = "plugin.vnidrop.share.EXPIRES_AT"
+// Android has no standard reply-to extra, and mail apps ignore this one.
+private const val EXTRA_REPLY_TO = "plugin.vnidrop.share.REPLY_TO"
+private const val EVENT_SHARE_RECEIVED = "shareReceived"
+
 @TauriPlugin
 class SharePlugin(private val activity: Activity): Plugin(activity) {
-    private var pendingShareInvoke: Invoke? = null
     private var shareInProgress = false
-    private var awaitingShareResume = false
+    private var chosenTarget: String? = null
+    private var returnUrl: String? = null
+    private var targetReceiverRegistered = false
+    // A share received before anyone listened for it, e.g. the one the app was launched with.
+    private var pendingReceivedShare: JSObject? = null
+
+    // Receives the component picked in the chooser (API 22+).
+    private val targetChosenReceiver = object : BroadcastReceiver() {
+        override fun onReceive(context: Context, intent: Intent) {
+            val component: ComponentName? = if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.TIRAMISU) {
+                intent.getParcelableExtra(Intent.EXTRA_CHOSEN_COMPONENT, ComponentName::class.java)
+            } else {
+                @Suppress("DEPRECATION")
+                intent.getParcelableExtra(Intent.EXTRA_CHOSEN_COMPONENT)
+            }
+            chosenTarget = component?.packageName
+        }
+    }
+
+    override fun load(webView: WebView) {
+        super.load(webView)
+        receiveShare(activity.intent)
+    }
+
+    override fun onNewIntent(intent: Intent) {
+        receiveShare(intent)
+    }
+
+    /**
+     * Delivers the share received before the first listener was registered.
+     */
+    @Command
+    override fun registerListener(invoke: Invoke) {
+        super.registerListener(invoke)
+        val share = pendingReceivedShare ?: return
+        if (hasListener(EVENT_SHARE_RECEIVED)) {
+            pendingReceivedShare = null
+            trigger(EVENT_SHARE_RECEIVED, share)
+        }
+    }
 
     @Command
     fun canShare(invoke: Invoke) {
@@ -62,11 +127,7 @@ class SharePlugin(private val activity: Activity): Plugin(activity) {
             args.files?.let {
                 if (it.isNotEmpty()) {
                     for (file in it) {
-                        val decodedBytes = Base64.decode(file.data, Base64.DEFAULT)
-                        val tempFile = createSafeFile(file.name)
-                        FileOutputStream(tempFile).use { outputStream ->
-                            outputStream.write(decodedBytes)
-                        }
+                        val tempFile = writeSharedFile(file)
 
                         val authority = "${activity.packageName}.fileprovider"
                         fileUris.add(
@@ -101,56 +162,241 @@ class SharePlugin(private val activity: Activity): Plugin(activity) {
             if (args.title != null) {
                 shareIntent.putExtra(Intent.EXTRA_TITLE, args.title)
             }
+            args.expiresAt?.let { shareIntent.putExtra(EXTRA_EXPIRES_AT, it) }
+            args.cc?.let { shareIntent.putExtra(Intent.EXTRA_CC, it.toTypedArray()) }
+            args.replyTo?.let { shareIntent.putExtra(EXTRA_REPLY_TO, it) }
 
             shareIntent.addFlags(Intent.FLAG_GRANT_READ_URI_PERMISSION)
-            val chooser = Intent.createChooser(shareIntent, args.title)
+            val chooser = createChooser(shareIntent, args.title)
+
+            if (args.waitForDismissal == false) {
+                // Fire-and-forget: the outcome is never reported, so assume it completed.
+                activity.startActivity(chooser)
+                val result = JSObject()
+                result.put("status", "completed")
+                invoke.resolve(result)
+                return
+            }
 
-            pendingShareInvoke = invoke
             shareInProgress = true
-            awaitingShareResume = false
-            activity.startActivity(chooser)
+            chosenTarget = null
+            returnUrl = args.returnUrl
+            // The chooser forwards the result of the picked activity, so the callback
+            // runs once the user is back from the share target.
+            startActivityForResult(invoke, chooser, "shareDismissed")
         } catch (e: Exception) {
             resetPendingShare()
             invoke.reject("Failed to share content: ${e.message}", e)
         }
     }
 
-    override fun onPause() {
-        super.onPause()
-        if (shareInProgress) {
-            awaitingShareResume = true
+    @ActivityCallback
+    private fun shareDismissed(invoke: Invoke, result: ActivityResult) {
+        // The result code of share targets is not meaningful; the chosen target is.
+        val shareResult = shareResult()
+        val url = returnUrl
+        resetPendingShare()
+        invoke.resolve(shareResult)
+        url?.let { openReturnUrl(it) }
+    }
+
+    /**
+     * Opens the return URL of a dismissed share, preferring this app so its own deep links
+     * come straight back to it.
+     */
+    private fun openReturnUrl(url: String) {
+        val intent = Intent(Intent.ACTION_VIEW, Uri.parse(url))
+        try {
+            activity.startActivity(Intent(intent).setPackage(activity.packageName))
+        } catch (e: ActivityNotFoundException) {
+            try {
+                activity.startActivity(intent)
+            } catch (e: ActivityNotFoundException) {
+                Log.w("SharePlugin", "No activity handles the return URL $url")
+            }
         }
     }
 
-    override fun onResume() {
-        super.onResume()
-        if (shareInProgress && awaitingShareResume) {
-            val invoke = pendingShareInvoke
-            resetPendingShare()
-            invoke?.resolve()
+    /**
+     * Builds the chooser intent, asking to be told which component was picked where supported.
+     */
+    private fun createChooser(shareIntent: Intent, title: String?): Intent {
+        if (Build.VERSION.SDK_INT < Build.VERSION_CODES.LOLLIPOP_MR1) {
+            return Intent.createChooser(shareIntent, title)
+        }
+
+        if (!targetReceiverRegistered) {
+            ContextCompat.registerReceiver(
+                activity,
+                targetChosenReceiver,
+                IntentFilter(ACTION_TARGET_CHOSEN),
+                ContextCompat.RECEIVER_NOT_EXPORTED
+            )
+            targetReceiverRegistered = true
+        }
+
+        var flags = PendingIntent.FLAG_UPDATE_CURRENT
+        if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.S) {
+            // The chooser fills in EXTRA_CHOSEN_COMPONENT, so the intent must stay mutable.
+            flags = flags or PendingIntent.FLAG_MUTABLE
         }
+        val callback = PendingIntent.getBroadcast(
+            activity,
+            0,
+            Intent(ACTION_TARGET_CHOSEN).setPackage(activity.packageName),
+            flags
+        )
+        return Intent.createChooser(shareIntent, title, callback.intentSender)
+    }
+
+    /**
+     * Maps the chooser outcome to the `ShareResult` shape expected by the Rust side.
+     */
+    private fun shareResult(): JSObject {
+        val result = JSObject()
+        val target = chosenTarget
+        if (target == null && Build.VERSION.SDK_INT >= Build.VERSION_CODES.LOLLIPOP_MR1) {
+            result.put("status", "cancelled")
+        } else {
+            // Before API 22 the chosen target cannot be known, so the share is assumed to be completed.
+            result.put("status", "completed")
+            result.put("target", target)
+        }
+        return result
     }
 
     /**
      * Deletes all temporary files created by this plugin in its dedicated share directory.
      * This should be called by the developer when the files are no longer needed.
+     * Resolves with the number of deleted files and the paths of those that could not be deleted.
      */
     @Command
     fun cleanup(invoke: Invoke) {
         try {
             val shareDir = getSafeShareDir()
-            if (shareDir.exists() && shareDir.isDirectory) {
-                if (!shareDir.deleteRecursively()) {
-                    invoke.reject("Failed to delete all temporary share files.")
-                    return
+            var removed = 0
+            val failed = JSArray()
+            shareDir.walkBottomUp().filter { it != shareDir }.forEach { file ->
+                val isFile = file.isFile
+                if (file.delete()) {
+                    if (isFile) removed++
+                } else if (isFile) {
+                    failed.put(file.absolutePath)
                 }
             }
-            invoke.resolve()
+            val result = JSObject()
+            result.put("removed", removed)
+            result.put("failed", failed)
+            invoke.resolve(result)
         } catch (e: Exception) {
             invoke.reject("Error during cleanup: ${e.message}", e)
         }
     }
 
+    /**
+     * Returns the dedicated directory where temporary share files are written.
+     */
+    @Command
+    fun tempDir(invoke: Invoke) {
+        val result = JSObject()
+        result.put("path", getSafeShareDir().absolutePath)
+        invoke.resolve(result)
+    }
+
+    /**
+     * The Android share sheet has no print target. The Rust side reports this as
+     * unsupported without invoking it, so both mobile plugins expose the same methods.
+     */
+    @Command
+    fun printDocument(invoke: Invoke) {
+        invoke.reject("Printing is not supported on Android")
+    }
+
+    /**
+     * Forwards the content of an incoming ACTION_SEND or ACTION_SEND_MULTIPLE intent.
+     */
+    private fun receiveShare(intent: Intent?) {
+        if (intent == null) return
+        if (intent.action != Intent.ACTION_SEND && intent.action != Intent.ACTION_SEND_MULTIPLE) return
+
+        val share = try {
+            readReceivedShare(intent)
+        } catch (e: Exception) {
+            Log.e("SharePlugin", "Failed to read the received share", e)
+            return
+        }
+        // The intent stays attached to the activity, so it must not be delivered again.
+        intent.action = null
+
+        if (hasListener(EVENT_SHARE_RECEIVED)) {
+            trigger(EVENT_SHARE_RECEIVED, share)
+        } else {
+            pendingReceivedShare = share
+        }
+    }
+
+    /**
+     * Maps an incoming share intent to the `ReceivedShare` shape expected by the Rust side.
+     * Shared files are copied into the share directory, since their content URIs are only
+     * readable while the intent is being handled.
+     */
+    private fun readReceivedShare(intent: Intent): JSObject {
+        val uris: List<Uri> = if (intent.action == Intent.ACTION_SEND_MULTIPLE) {
+            if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.TIRAMISU) {
+                intent.getParcelableArrayListExtra(Intent.EXTRA_STREAM, Uri::class.java)
+            } else {
+                @Suppress("DEPRECATION")
+                intent.getParcelableArrayListExtra(Intent.EXTRA_STREAM)
+            } ?: emptyList()
+        } else {
+            listOfNotNull(
+                if (Build.VERSION.SDK_INT >= Build.VERSION_CODES.TIRAMISU) {
+                    intent.getParcelableExtra(Intent.EXTRA_STREAM, Uri::class.java)
+                } else {
+                    @Suppress("DEPRECATION")
+                    intent.getParcelableExtra(Intent.EXTRA_STREAM)
+                }
+            )
+        }
+
+        val files = JSArray()
+        for (uri in uris) {
+            files.put(copyReceivedFile(uri))
+        }
+
+        val share = JSObject()
+        share.put("title", intent.getStringExtra(Intent.EXTRA_SUBJECT))
+        share.put("text", intent.getCharSequenceExtra(Intent.EXTRA_TEXT)?.toString())
+        share.put("files", files)
+        return share
+    }
+
+    @Throws(IOException::class, SecurityException::class)
+    private fun copyReceivedFile(uri: Uri): JSObject {
+        val resolver = activity.contentResolver
+        var name: String? = null
+        resolver.query(uri, arrayOf(OpenableColumns.DISPLAY_NAME), null, null, null)?.use { cursor ->
+            if (cursor.moveToFirst()) {
+                name = cursor.getString(0)
+            }
+        }
+        val fileName = name ?: uri.lastPathSegment ?: "shared"
+
+        val target = createSafeFile(fileName)
+        val input = resolver.openInputStream(uri) ?: throw IOException("Cannot read $uri")
+        input.use { inputStream ->
+            FileOutputStream(target).use { outputStream ->
+                inputStream.copyTo(outputStream)
+            }
+        }
+
+        val file = JSObject()
+        file.put("path", target.absolutePath)
+        file.put("name", fileName)
+        file.put("mimeType", resolver.getType(uri))
+        return file
+    }
+
     private fun determineMimeType(files: List<SharedFile>): String {
         if (files.isEmpty()) return "*/*"
         val firstMimeType = files.first().mimeType
@@ -166,9 +412,9 @@ class SharePlugin(private val activity: Activity): Plugin(activity) {
     }
 
     private fun resetPendingShare() {
-        pendingShareInvoke = null
         shareInProgress = false
-        awaitingShareResume = false
+        chosenTarget = null
+        returnUrl = null
     }
 
     /**
@@ -183,6 +429,37 @@ class SharePlugin(private val activity: Activity): Plugin(activity) {
         return shareDir
     }
 
+    /**
+     * Writes a shared file into the share directory. A handed-off file is moved there
+     * from the handoff directory instead, so it is never read into memory.
+     */
+    @Throws(IOException::class, SecurityException::class)
+    private fun writeSharedFile(file: SharedFile): File {
+        val tempFile = createSafeFile(file.name)
+        val path = file.path
+        if (path == null) {
+            val decodedBytes = Base64.decode(file.data, Base64.DEFAULT)
+            FileOutputStream(tempFile).use { outputStream ->
+                outputStream.write(decodedBytes)
+            }
+            return tempFile
+        }
+
+        val source = File(path).canonicalFile
+        val handoffDir = File(getSafeShareDir(), "handoff").canonicalPath
+        if (!source.path.startsWith(handoffDir + File.separator)) {
+            throw SecurityException("The file is not in the handoff directory: '$path'")
+        }
+        // Both directories are in the cache dir, so the move is a rename unless it fails.
+        if (!source.renameTo(tempFile)) {
+            source.inputStream().use { input ->
+                FileOutputStream(tempFile).use { output -> input.copyTo(output) }
+            }
+            source.delete()
+        }
+        return tempFile
+    }
+
     /**
      * Creates a safe File object within the dedicated share directory.
      * It sanitizes the filename and performs path traversal checks.
@@ -1 +1,282 @@
-if("__TAURI__"in window){var __TAURI_PLUGIN_SHARE__=function(e){"use strict";async function n(e,n={},a){return window.__TAURI_INTERNALS__.invoke(e,n,a)}async function a(e){return new Promise((n,a)=>{const t=new FileReader;t.readAsDataURL(e),t.onload=()=>{const e=t.result.split(",")[1];n(e)},t.onerror=e=>a(e)})}return"function"==typeof SuppressedError&&SuppressedError,e.canShare=async function(e){const a=await n("plugin:vnidrop-share|can_share");return!0===a.value||"true"===a.value},e.cleanup=async function(){await n("plugin:vnidrop-share|cleanup")},e.share=async function(e){const t={text:e.text,title:e.title,url:e.url};e.files&&e.files.length>0&&(t.files=await Promise.all(e.files.map(async e=>({data:await a(e),name:e.name,mimeType:e.type||"application/octet-stream"})))),await n("plugin:vnidrop-share|share",{options:t})},e}({});Object.defineProperty(window.__TAURI__,"share",{value:__TAURI_PLUGIN_SHARE__})}
+if ('__TAURI__' in window) {
+var __TAURI_PLUGIN_SHARE__ = (function (exports) {
+    'use strict';
+
+    async function invoke(cmd, args = {}, options) {
+        return window.__TAURI_INTERNALS__.invoke(cmd, args, options);
+    }
+
+    function transformCallback(callback, once = false) {
+        return window.__TAURI_INTERNALS__.transformCallback(callback, once);
+    }
+
+    async function _unlisten(event, eventId) {
+        window.__TAURI_EVENT_PLUGIN_INTERNALS__.unregisterListener(event, eventId);
+        await invoke('plugin:event|unlisten', { event, eventId });
+    }
+
+    async function listen(event, handler, options) {
+        const target = typeof options?.target === 'string'
+            ? { kind: 'AnyLabel', label: options.target }
+            : (options?.target ?? { kind: 'Any' });
+        return invoke('plugin:event|listen', {
+            event,
+            target,
+            handler: transformCallback(handler)
+        }).then((eventId) => {
+            return async () => _unlisten(event, eventId);
+        });
+    }
+
+    function getCurrentWindow() {
+        return { label: window.__TAURI_INTERNALS__.metadata.currentWindow.label };
+    }
+
+    async function canShare(data) {
+        const result = await invoke("plugin:vnidrop-share|can_share");
+        if (!result.value) {
+            return false;
+        }
+        const files = data?.files ?? [];
+        if (result.maxFiles != null && files.length > result.maxFiles) {
+            return false;
+        }
+        const totalBytes = files.reduce((total, file) => total + file.size, 0);
+        if (result.maxTotalBytes != null && totalBytes > result.maxTotalBytes) {
+            return false;
+        }
+        return true;
+    }
+    async function shareLimits() {
+        const { maxFiles, maxTotalBytes } = await invoke("plugin:vnidrop-share|can_share");
+        return { maxFiles, maxTotalBytes };
+    }
+    async function recentTargets() {
+        return await invoke("plugin:vnidrop-share|recent_targets");
+    }
+    async function cleanup(paths) {
+        return await invoke("plugin:vnidrop-share|cleanup", { paths });
+    }
+    async function cleanupOrphans() {
+        return await invoke("plugin:vnidrop-share|cleanup_orphans");
+    }
+    async function cancelAllShares() {
+        return await invoke("plugin:vnidrop-share|cancel_all_shares");
+    }
+    async function lastShareResult(label = getCurrentWindow().label) {
+        return await invoke("plugin:vnidrop-share|last_share_result", { label });
+    }
+    async function resumeLastShare() {
+        return await invoke("plugin:vnidrop-share|resume_last_share");
+    }
+    async function discardLastShare() {
+        return await invoke("plugin:vnidrop-share|discard_last_share");
+    }
+    async function tempDir() {
+        return await invoke("plugin:vnidrop-share|temp_dir");
+    }
+    async function handoffDir() {
+        return await invoke("plugin:vnidrop-share|handoff_dir");
+    }
+    async function fileToBase64(file) {
+        return new Promise((resolve, reject) => {
+            const reader = new FileReader();
+            reader.readAsDataURL(file);
+            reader.onload = () => {
+                const base64String = reader.result.split(",")[1];
+                resolve(base64String);
+            };
+            reader.onerror = (error) => reject(error);
+        });
+    }
+    async function share(data) {
+        const payload = await toSharePayload(data);
+        return await invoke("plugin:vnidrop-share|share", { options: payload });
+    }
+    async function shareNowait(data) {
+        const payload = await toSharePayload(data);
+        return await invoke("plugin:vnidrop-share|share_nowait", { options: payload });
+    }
+    async function shareBatch(payloads) {
+        const serialized = await Promise.all(payloads.map(toSharePayload));
+        return await invoke("plugin:vnidrop-share|share_batch", {
+            payloads: serialized,
+        });
+    }
+    async function shareTo(service, data, serviceOptions) {
+        const payload = await toSharePayload(data);
+        return await invoke("plugin:vnidrop-share|share_to", {
+            options: { service, serviceOptions, options: payload },
+        });
+    }
+    async function shareAsFile(content, name) {
+        return await invoke("plugin:vnidrop-share|share_as_file", {
+            options: { content, name },
+        });
+    }
+    async function shareRawData(data, options) {
+        const headers = {
+            "x-share-name": encodeURIComponent(options.name),
+        };
+        if (options.mimeType) {
+            headers["x-share-mime-type"] = encodeURIComponent(options.mimeType);
+        }
+        if (options.text !== undefined) {
+            headers["x-share-text"] = encodeURIComponent(options.text);
+        }
+        if (options.title !== undefined) {
+            headers["x-share-title"] = encodeURIComponent(options.title);
+        }
+        return await invoke("plugin:vnidrop-share|share_raw_data", data, { headers });
+    }
+    async function shareScreenshot(data) {
+        const payload = data ? await toSharePayload(data) : undefined;
+        return await invoke("plugin:vnidrop-share|share_screenshot", {
+            options: payload,
+        });
+    }
+    async function shareClipboard() {
+        return await invoke("plugin:vnidrop-share|share_clipboard");
+    }
+    async function sharePrint(file) {
+        return await invoke("plugin:vnidrop-share|share_print", {
+            file: {
+                data: await fileToBase64(file),
+                name: file.name,
+                mimeType: file.type || "application/octet-stream",
+            },
+        });
+    }
+    async function validate(data) {
+        const payload = await toSharePayload(data);
+        return await invoke("plugin:vnidrop-share|validate", { options: payload });
+    }
+    async function estimateDiskUsage(data) {
+        const payload = await toSharePayload(data);
+        return await invoke("plugin:vnidrop-share|estimate_disk_usage", {
+            options: payload,
+        });
+    }
+    async function toSharePayload(data) {
+        const payload = {
+            text: data.text,
+            title: data.title,
+            url: data.url,
+            applicationName: data.applicationName,
+            applicationLink: data.applicationLink,
+            waitForDismissal: data.waitForDismissal,
+            urlProviderId: data.urlProviderId,
+            zeroizeData: data.zeroizeData,
+            textIsPlain: data.textIsPlain,
+            textUrlSeparator: data.textUrlSeparator,
+            urlFirst: data.urlFirst,
+            textItems: data.textItems,
+            textItemsSeparator: data.textItemsSeparator,
+            fallback: data.fallback,
+            preferredEdge: data.preferredEdge,
+            presentation: data.presentation,
+            screen: data.screen,
+            nameCollision: data.nameCollision,
+            dedupeFiles: data.dedupeFiles,
+            shareMode: data.shareMode,
+            returnUrl: data.returnUrl,
+            expiresAt: data.expiresAt,
+            customFormats: data.customFormats,
+            cc: data.cc,
+            replyTo: data.replyTo,
+        };
+        if (data.previewImage) {
+            payload.previewImage = {
+                data: await fileToBase64(data.previewImage),
+                name: data.previewImage.name,
+                mimeType: data.previewImage.type || "application/octet-stream",
+            };
+        }
+        if (data.files && data.files.length > 0) {
+            payload.files = await Promise.all(data.files.map(async (file) => ({
+                data: await fileToBase64(file),
+                name: file.name,
+                mimeType: file.type || "application/octet-stream",
+                uti: data.utis?.[file.name],
+                ...data.imageSizes?.[file.name],
+                displayName: data.displayNames?.[file.name],
+            })));
+        }
+        if (data.paths && data.paths.length > 0) {
+            payload.files = (payload.files ?? []).concat(data.paths.map((path) => {
+                const name = path.split(/[\\/]/).pop() ?? path;
+                return {
+                    path,
+                    name,
+                    uti: data.utis?.[name],
+                    ...data.imageSizes?.[name],
+                    displayName: data.displayNames?.[name],
+                };
+            }));
+        }
+        if (data.handoffPaths && data.handoffPaths.length > 0) {
+            payload.files = (payload.files ?? []).concat(data.handoffPaths.map((path) => {
+                const name = path.split(/[\\/]/).pop() ?? path;
+                return {
+                    path,
+                    name,
+                    handoff: true,
+                    uti: data.utis?.[name],
+                    ...data.imageSizes?.[name],
+                    displayName: data.displayNames?.[name],
+                };
+            }));
+        }
+        return payload;
+    }
+    async function onShareError(handler) {
+        return await listen("vnidrop-share:error", (event) => handler(event.payload));
+    }
+    async function onShareCompleted(handler) {
+        return await listen("vnidrop-share:completed", (event) => handler(event.payload));
+    }
+    async function onUploadProgress(handler) {
+        return await listen("vnidrop-share:upload-progress", (event) => handler(event.payload));
+    }
+    async function onCleaned(handler) {
+        return await listen("vnidrop-share:cleaned", (event) => handler(event.payload));
+    }
+    async function onShareReceived(handler) {
+        const unlisten = await listen("vnidrop-share:received", (event) => handler(event.payload));
+        const pending = await invoke("plugin:vnidrop-share|take_received_shares");
+        pending.forEach(handler);
+        return unlisten;
+    }
+
+    exports.canShare = canShare;
+    exports.cancelAllShares = cancelAllShares;
+    exports.cleanup = cleanup;
+    exports.cleanupOrphans = cleanupOrphans;
+    exports.discardLastShare = discardLastShare;
+    exports.estimateDiskUsage = estimateDiskUsage;
+    exports.handoffDir = handoffDir;
+    exports.lastShareResult = lastShareResult;
+    exports.onCleaned = onCleaned;
+    exports.onShareCompleted = onShareCompleted;
+    exports.onShareError = onShareError;
+    exports.onShareReceived = onShareReceived;
+    exports.onUploadProgress = onUploadProgress;
+    exports.recentTargets = recentTargets;
+    exports.resumeLastShare = resumeLastShare;
+    exports.share = share;
+    exports.shareAsFile = shareAsFile;
+    exports.shareBatch = shareBatch;
+    exports.shareClipboard = shareClipboard;
+    exports.shareLimits = shareLimits;
+    exports.shareNowait = shareNowait;
+    exports.sharePrint = sharePrint;
+    exports.shareRawData = shareRawData;
+    exports.shareScreenshot = shareScreenshot;
+    exports.shareTo = shareTo;
+    exports.tempDir = tempDir;
+    exports.validate = validate;
+
+    return exports;
+
+})({});
+Object.defineProperty(window.__TAURI__, 'share', { value: __TAURI_PLUGIN_SHARE__ }) }
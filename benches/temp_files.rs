@@ -0,0 +1,50 @@
+//! Measures how fast the files of a share are written to disk, with the Base64
+//! decoding and the disk write also measured on their own.
+//!
+//! Run with `cargo bench --features bench`.
+
+use std::hint::black_box;
+use std::io::Write;
+
+use base64::{engine::general_purpose, Engine as _};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use tauri_plugin_vnidrop_share::{bench, SharedFile};
+
+/// The payload sizes measured, from a small text file to a large video.
+const SIZES: &[(&str, usize)] = &[("1KiB", 1 << 10), ("1MiB", 1 << 20), ("16MiB", 16 << 20)];
+
+fn shared_file(bytes: &[u8]) -> SharedFile {
+    SharedFile::new(
+        "payload.bin",
+        "application/octet-stream",
+        general_purpose::STANDARD.encode(bytes),
+    )
+}
+
+fn temp_files(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut group = c.benchmark_group("temp_files");
+    for &(label, len) in SIZES {
+        let bytes: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+        let file = shared_file(&bytes);
+        group.throughput(Throughput::Bytes(len as u64));
+
+        group.bench_with_input(BenchmarkId::new("decode", label), &file, |b, file| {
+            b.iter(|| bench::decode_file_data(black_box(file)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("write", label), &bytes, |b, bytes| {
+            b.iter(|| {
+                let mut temp_file = tempfile::NamedTempFile::new_in(dir.path()).unwrap();
+                temp_file.write_all(black_box(bytes)).unwrap();
+                temp_file
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("create", label), &file, |b, file| {
+            b.iter(|| bench::create_temp_file_for_data(black_box(file), dir.path()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, temp_files);
+criterion_main!(benches);
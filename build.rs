@@ -1,4 +1,31 @@
-const COMMANDS: &[&str] = &["share", "can_share", "cleanup"];
+/// Every command registered with `generate_handler!` in `src/lib.rs`.
+///
+/// An `allow-`/`deny-` permission is generated for each entry, so a command that is
+/// missing here cannot be granted to a capability and is denied at runtime.
+const COMMANDS: &[&str] = &[
+    "share",
+    "share_nowait",
+    "share_batch",
+    "share_to",
+    "share_as_file",
+    "share_raw_data",
+    "share_screenshot",
+    "share_clipboard",
+    "share_print",
+    "can_share",
+    "recent_targets",
+    "cleanup",
+    "cleanup_orphans",
+    "cancel_all_shares",
+    "temp_dir",
+    "handoff_dir",
+    "validate",
+    "estimate_disk_usage",
+    "take_received_shares",
+    "last_share_result",
+    "resume_last_share",
+    "discard_last_share",
+];
 
 fn main() {
     tauri_plugin::Builder::new(COMMANDS)
@@ -0,0 +1,24 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The result type for the `can_share` command.
+ *
+ * A `true` value indicates that the current platform supports native sharing.
+ * The [`crate::commands::can_share`] command will return `true` on Windows, macOS, and mobile platforms,
+ * and `false` on Linux since there is no native sharing dialog available.
+ *
+ * `max_files` and `max_total_bytes` report the limits the platform itself puts on
+ * a share, so the frontend can check files before calling `share`. They are
+ * `None` when the platform has no known limit. None of the supported platforms
+ * currently documents one: limits such as MMS size caps are set by individual
+ * share targets, which cannot be known before the user picks one.
+ */
+export type CanShareResult = { value: boolean, 
+/**
+ * The maximum number of files in a single share, if limited.
+ */
+maxFiles: number | null, 
+/**
+ * The maximum combined size of the files in a single share, if limited.
+ */
+maxTotalBytes: number | null, };
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How files of a single share that end up with the same name are written.
+ *
+ * Names are compared after sanitization and ignoring case, as the default file
+ * systems of Windows and macOS do. Files shared by path are never renamed.
+ */
+export type NameCollision = "suffix" | "error" | "overwrite";
@@ -0,0 +1,11 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The edge of its anchor the macOS share picker appears on, one of the four
+ * `NSRectEdge` values.
+ *
+ * The picker is anchored at the center of the window's content view. Edges are
+ * in AppKit coordinates, where the y axis points up unless a view is flipped, so
+ * `MinY` is the bottom edge and `MaxY` the top edge.
+ */
+export type PreferredEdge = "minX" | "minY" | "maxX" | "maxY";
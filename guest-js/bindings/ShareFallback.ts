@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The behavior used in place of a share when native sharing is unavailable.
+ */
+export type ShareFallback = "openUrl" | "openFile";
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How the receiver of a share is expected to use its temporary files.
+ *
+ * The desktop platforms hand receivers a URL to the temporary file rather than
+ * its content, so a receiver that reads it after the file was deleted loses it.
+ * Files shared by path are never deleted by the plugin, whatever the mode.
+ */
+export type ShareMode = "copy" | "reference";
@@ -0,0 +1,204 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NameCollision } from "./NameCollision";
+import type { PreferredEdge } from "./PreferredEdge";
+import type { ShareFallback } from "./ShareFallback";
+import type { ShareMode } from "./ShareMode";
+import type { SharePresentation } from "./SharePresentation";
+import type { SharedFile } from "./SharedFile";
+
+/**
+ * Defines the content and options for a native sharing dialog.
+ *
+ * This struct can be used to share text, a title, a URL, and a list of files.
+ * All fields are optional, allowing for flexible sharing payloads.
+ *
+ * ## Examples
+ *
+ * To share a simple message and URL:
+ *
+ * ```json
+ * {
+ *   "title": "My Tauri App",
+ *   "text": "Check out this great app built with Tauri!",
+ *   "url": "[https://tauri.app](https://tauri.app)"
+ * }
+ * ```
+ *
+ * To share a file (e.g., an image in Base64 format):
+ *
+ * ```json
+ * {
+ *   "files": [
+ *     {
+ *       "data": "data:image/png;base64,iVBORw0KGgo...",
+ *       "name": "my-image.png",
+ *       "mimeType": "image/png"
+ *     }
+ *   ]
+ * }
+ * ```
+ */
+export type ShareOptions = { 
+/**
+ * Optional text content to include in the share dialog.
+ */
+text?: string, 
+/**
+ * Optional title for the share dialog. (This is mainly used on Android)
+ *
+ * `{fileCount}` is replaced with the number of shared files and `{fileName}`
+ * with the name of the first one. On Windows, a share without a title is
+ * titled after the name or count of its files, or the host of its URL.
+ */
+title?: string, 
+/**
+ * Optional URL to include in the share dialog.
+ */
+url?: string, 
+/**
+ * A list of files to share, each represented by a `SharedFile` struct.
+ */
+files?: Array<SharedFile>, 
+/**
+ * Overrides the name of the app the content is shared from. Defaults to the
+ * product name from the Tauri config. (This is only used on Windows)
+ */
+applicationName?: string, 
+/**
+ * Overrides the link back to the app the content is shared from. Defaults to
+ * the `applicationLink` plugin config. (This is only used on Windows)
+ */
+applicationLink?: string, 
+/**
+ * Whether the share resolves only once the share sheet is dismissed. Defaults
+ * to `true`. When `false`, the share resolves as soon as the sheet is shown,
+ * with a `Completed` result without target. (This is only used on mobile)
+ */
+waitForDismissal?: boolean, 
+/**
+ * The id of a URL provider registered with `Share::register_url_provider`.
+ * When set, `url` is replaced by the link the provider mints right before
+ * the share is performed.
+ */
+urlProviderId?: string, 
+/**
+ * Whether the Base64 `data` of each file is wiped from memory as soon as its
+ * temporary file is written, instead of when the share ends. Defaults to
+ * `false`. Copies held by the webview or by the IPC layer are out of the
+ * plugin's control. (This is only used on Windows and macOS)
+ */
+zeroizeData?: boolean, 
+/**
+ * Shares `text` and `url` as plain text only, so receivers do not turn text
+ * that looks like a URL into a link. Defaults to `false`. (This is only used
+ * on Windows and macOS)
+ */
+textIsPlain?: boolean, 
+/**
+ * What to do instead when native sharing is unavailable, e.g. on Linux.
+ * Defaults to nothing, in which case the share is reported as cancelled.
+ */
+fallback?: ShareFallback, 
+/**
+ * The side of its anchor the share picker appears on. Defaults to `minY`.
+ * (This is only used on macOS)
+ */
+preferredEdge?: PreferredEdge, 
+/**
+ * How the share picker is presented. Defaults to `popover`.
+ * (This is only used on macOS)
+ */
+presentation?: SharePresentation, 
+/**
+ * What happens when several files share the same name. Defaults to `suffix`.
+ * (This is only used on Windows and macOS)
+ */
+nameCollision?: NameCollision, 
+/**
+ * The separator placed between the text and the URL when both are shared as
+ * one text, e.g. `" "` or `""`. Defaults to a newline. (This is only used on
+ * Windows and macOS)
+ */
+textUrlSeparator?: string, 
+/**
+ * Whether the URL comes before the text when both are shared as one text.
+ * Defaults to `false`. (This is only used on Windows and macOS)
+ */
+urlFirst?: boolean, 
+/**
+ * Further texts shared after `text` and `url`, e.g. the title and the body of
+ * a note. On macOS each one is a separate item, which receivers can handle
+ * apart. Windows has a single text slot, so there they are appended to the
+ * shared text, joined by `text_items_separator`. (This is only used on
+ * Windows and macOS)
+ */
+textItems?: Array<string>, 
+/**
+ * The separator placed between the text and each of the `text_items` when
+ * they are joined into one text. Defaults to a blank line. (This is only used
+ * on Windows)
+ */
+textItemsSeparator?: string, 
+/**
+ * An image previewing the shared link, e.g. for a link card in the receiver.
+ * It is not shared as an attachment, and must be given as `data` or a reader
+ * rather than by path. (This is only used on Windows and macOS)
+ */
+previewImage?: SharedFile, 
+/**
+ * How long the temporary files of the share are kept once it completed.
+ * Defaults to `copy`. (This is only used on Windows and macOS)
+ */
+shareMode?: ShareMode, 
+/**
+ * A URL opened once the share sheet is dismissed, e.g. a deep link of the app
+ * itself to resume a workflow. The app must handle its scheme. On Android, it
+ * is only opened when `wait_for_dismissal` is not `false`. (This is only used
+ * on Android and iOS)
+ */
+returnUrl?: string, 
+/**
+ * A hint, as a Unix timestamp in seconds, of when the shared content stops
+ * being valid, for receivers that honor ephemeral content. It is attached as
+ * the `ExpiresAt` custom property of the data package on Windows and as the
+ * `plugin.vnidrop.share.EXPIRES_AT` long extra on Android, and ignored
+ * elsewhere. Nothing is enforced by the plugin itself.
+ */
+expiresAt?: number, 
+/**
+ * The index of the monitor to show the share UI on, in the order of the
+ * window's `available_monitors`. Falls back to the screen of the window when
+ * out of range. (This is only used on Windows and macOS)
+ */
+screen?: number, 
+/**
+ * Whether files whose content is identical to an earlier file of the share
+ * are left out, keeping the first one. Files are compared by the hash of their
+ * decoded data, or of the content they point to, before any temporary file is
+ * written. Streamed files and folders are never left out. Defaults to `false`.
+ */
+dedupeFiles?: boolean, 
+/**
+ * Payloads in custom data formats, as Base64 keyed by format id, for receivers
+ * that understand them. Each is set on the data package with `SetData`. Ids
+ * should be unique to the app, e.g. `com.example.app.board`, and cannot be one
+ * of the standard formats such as `Text`. Each payload decodes to at most
+ * 4 MiB, since the data package holds it in memory. (This is only used on
+ * Windows)
+ */
+customFormats?: { [key in string]?: string }, 
+/**
+ * The addresses a mail target copies the share to. Each must be a non-empty
+ * string, malformed addresses are left to the mail app. It is set as the
+ * `EXTRA_CC` of the share intent on Android, and ignored by targets that are
+ * not mail apps. (This is only used on Android)
+ */
+cc?: Array<string>, 
+/**
+ * The address replies to a shared mail go to, which must be non-empty. Android
+ * has no standard extra for it, so it is attached as the plugin's own
+ * `plugin.vnidrop.share.REPLY_TO` string extra, which mail apps do not read:
+ * it has no effect unless the receiving app is written to look for it.
+ * (This is only used on Android)
+ */
+replyTo?: string, };
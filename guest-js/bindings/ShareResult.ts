@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The outcome of a share operation, returned by the `share` command.
+ *
+ * Serialized with a `status` tag, e.g. `{ "status": "completed", "target": "Mail" }`,
+ * `{ "status": "cancelled" }`, `{ "status": "fallback", "method": "openUrl" }` or
+ * `{ "status": "notShown" }`.
+ */
+export type ShareResult = { "status": "completed", target: string | null, failedFiles?: Array<string>, acceptedFiles?: Array<string>, duplicatesRemoved?: number, } | { "status": "cancelled" } | { "status": "fallback", method: string, } | { "status": "notShown" };
@@ -0,0 +1,75 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Represents a file to be shared, including its content, name, and MIME type.
+ *
+ * The `data` field holds the Base64 encoded content of the file. This approach
+ * allows files to be easily passed from the frontend to the Rust backend
+ * without needing to manage local file paths directly.
+ *
+ * Rust callers can instead provide the content as a stream with
+ * [`SharedFile::from_reader`], which avoids holding the whole file in memory.
+ *
+ * An existing file can also be shared by `path`. Paths coming from the frontend
+ * must be allowed by the file system scope, see [`crate::Config`].
+ *
+ * Rust callers create it with [`SharedFile::new`], [`SharedFile::from_reader`] or
+ * [`SharedFile::from_path`], and set the optional fields afterwards.
+ */
+export type SharedFile = { data: string, name: string, mimeType: string, 
+/**
+ * The absolute path of an existing file or directory to share instead of `data`.
+ *
+ * On desktop the file is shared in place and never deleted by the plugin.
+ * On mobile it is read into memory first, like a streamed file, unless it is
+ * handed off: the native side then moves it into its share directory.
+ *
+ * Directories are shared as folder items, without zipping them, on macOS and
+ * Windows only. Whether a receiver accepts them is up to the receiver: AirDrop
+ * and Nearby Share take folders, while most mail and messaging targets only
+ * take files. Sharing a directory on mobile fails.
+ */
+path?: string, 
+/**
+ * Hands the file at `path` off to the plugin, which deletes it along with the
+ * temporary files of the share.
+ *
+ * This lets the frontend write a large file itself, e.g. with `tauri-plugin-fs`
+ * into the directory returned by `temp_dir`, and share it without sending its
+ * content over IPC or copying it. A handed-off file must be in the plugin temp
+ * dir or allowed by the file system scope, even with `allowUnscopedPaths`, and
+ * cannot be a directory.
+ */
+handoff?: boolean, 
+/**
+ * The Uniform Type Identifier of the file on Apple platforms, e.g.
+ * `com.example.project` for a proprietary document type.
+ *
+ * When absent, receivers infer the type from the file extension, or from
+ * `mime_type` for files without one. Ignored on other platforms.
+ */
+uti?: string, 
+/**
+ * The width in pixels of an image file, so receivers can lay out its preview
+ * before loading it.
+ *
+ * When `width` or `height` is absent, the dimensions are read from the header
+ * of the image with the `image-dimensions` cargo feature, without decoding the
+ * whole image. macOS passes them as the preferred presentation size of the
+ * item. Windows ignores them.
+ */
+width?: number, 
+/**
+ * The height in pixels of an image file, see `width`.
+ */
+height?: number, 
+/**
+ * The name receivers show for the file, e.g. `Invoice.pdf`, when it differs
+ * from `name`.
+ *
+ * macOS passes it as the suggested name of the shared item, leaving the name
+ * of the temporary file as is. Windows and mobile show the name of the file
+ * itself, so the temporary file is written under the display name instead.
+ * Windows shares files given by `path` in place, under their own name.
+ */
+displayName?: string, };
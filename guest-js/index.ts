@@ -1,4 +1,23 @@
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import { getCurrentWindow } from "@tauri-apps/api/window";
+
+import type { CanShareResult } from "./bindings/CanShareResult";
+import type { NameCollision } from "./bindings/NameCollision";
+import type { PreferredEdge } from "./bindings/PreferredEdge";
+import type { ShareFallback } from "./bindings/ShareFallback";
+import type { ShareMode } from "./bindings/ShareMode";
+import type { SharePresentation } from "./bindings/SharePresentation";
+import type { ShareResult } from "./bindings/ShareResult";
+
+export type {
+  NameCollision,
+  PreferredEdge,
+  ShareFallback,
+  ShareMode,
+  SharePresentation,
+  ShareResult,
+};
 
 /**
  * Represents the content to be shared, similar to the Web Share API's ShareData dictionary.
@@ -16,12 +35,192 @@ import { invoke } from "@tauri-apps/api/core";
 export interface ShareData {
   /** Optional array of File objects to share (e.g., images, PDFs). */
   files?: File[];
+  /**
+   * Optional absolute paths of existing files to share without reading them in the webview.
+   * Each path must be allowed by the file system scope, or the plugin must be
+   * configured with `allowUnscopedPaths`.
+   */
+  paths?: string[];
+  /**
+   * Optional absolute paths of files written for this share, e.g. with the fs
+   * plugin, into the directory returned by `handoffDir()`. They are shared in
+   * place like `paths`, and deleted along with the temporary files of the share.
+   * Each path must be in `handoffDir()`.
+   */
+  handoffPaths?: string[];
   /** Optional text content to be shared. */
   text?: string;
-  /** Optional title describing the shared content. */
+  /**
+   * Optional title describing the shared content. `{fileCount}` is replaced with
+   * the number of shared files and `{fileName}` with the name of the first one.
+   * On Windows, a share without a title is titled after its files or the host of
+   * its URL.
+   */
   title?: string;
-  /** Optional URL to be shared. */
+  /**
+   * Optional URL to be shared. Without `text`, it is shared as a link with a rich preview
+   * on Windows and macOS, and must be a well-formed absolute URL.
+   */
   url?: string;
+  /**
+   * Overrides the name of the app the content is shared from.
+   * Defaults to the product name. (Windows only)
+   */
+  applicationName?: string;
+  /**
+   * Overrides the link back to the app the content is shared from.
+   * Defaults to the `applicationLink` plugin config. (Windows only)
+   */
+  applicationLink?: string;
+  /**
+   * Whether `share()` resolves only once the share sheet is dismissed. Defaults to `true`.
+   * When `false`, it resolves as soon as the sheet is shown, as `{ status: "completed" }`
+   * without a target. (Android and iOS only)
+   */
+  waitForDismissal?: boolean;
+  /**
+   * Id of a URL provider registered in Rust with `register_url_provider`. The provider
+   * mints the shared URL right before sharing, replacing `url`.
+   */
+  urlProviderId?: string;
+  /**
+   * Uniform Type Identifiers of the shared files, keyed by file name, e.g.
+   * `{ "plan.proj": "com.example.project" }`. Lets receivers recognize custom
+   * document types. (macOS and iOS only)
+   */
+  utis?: Record<string, string>;
+  /**
+   * Pixel dimensions of the shared images, keyed by file name, e.g.
+   * `{ "photo.jpg": { width: 4032, height: 3024 } }`, so receivers can lay out
+   * their preview up front. Without them, the dimensions are read from the image
+   * header when the `image-dimensions` cargo feature is enabled.
+   * (Windows and macOS only)
+   */
+  imageSizes?: Record<string, { width: number; height: number }>;
+  /**
+   * Names receivers show for the shared files instead of their own, keyed by file
+   * name, e.g. `{ "a1b2c3.pdf": "Invoice.pdf" }`. On macOS it is the suggested
+   * name of the item; elsewhere the file is shared under it. Windows keeps the
+   * name of files shared by path.
+   */
+  displayNames?: Record<string, string>;
+  /**
+   * Wipes the Base64 content of each file from the plugin's memory as soon as its
+   * temporary file is written. The copies held by the webview are out of the plugin's
+   * control. (Windows and macOS only)
+   */
+  zeroizeData?: boolean;
+  /**
+   * Shares `text` and `url` as plain text only, so receivers do not turn text that
+   * looks like a URL into a link. (Windows and macOS only)
+   */
+  textIsPlain?: boolean;
+  /**
+   * The separator placed between `text` and `url` when they are shared as one text.
+   * Defaults to a newline. (Windows and macOS only)
+   */
+  textUrlSeparator?: string;
+  /**
+   * Puts `url` before `text` when they are shared as one text. (Windows and macOS only)
+   */
+  urlFirst?: boolean;
+  /**
+   * Further texts shared after `text` and `url`. On macOS each one is a separate
+   * item of the share; on Windows they are appended to the shared text, joined by
+   * `textItemsSeparator`. (Windows and macOS only)
+   */
+  textItems?: string[];
+  /**
+   * The separator placed between the text and each of the `textItems` when they
+   * are joined into one text. Defaults to a blank line. (Windows only)
+   */
+  textItemsSeparator?: string;
+  /**
+   * What to do when native sharing is unavailable (Linux). With `"openUrl"`, an
+   * `http` or `https` `url` is opened in the default browser and the share resolves
+   * as `{ status: "fallback", method: "openUrl" }`. With `"openFile"`, the first
+   * file is opened in its default application and the share resolves as
+   * `{ status: "fallback", method: "openFile" }`.
+   */
+  fallback?: ShareFallback;
+  /**
+   * The side of the window's center the share picker appears on, mapping to the
+   * `NSRectEdge` values. In AppKit coordinates the y axis points up, so `"minY"`
+   * is the bottom edge and `"maxY"` the top edge. Defaults to `"minY"`. (macOS only)
+   */
+  preferredEdge?: PreferredEdge;
+  /**
+   * How the share picker is presented. `"popover"` leaves the rest of the window
+   * usable, while `"sheet"` attaches a window-modal sheet that blocks the window
+   * until a service is chosen or the picker is dismissed. Defaults to
+   * `"popover"`. (macOS only)
+   */
+  presentation?: SharePresentation;
+  /**
+   * The index of the monitor to show the share UI on, in the order of
+   * `availableMonitors()` from `@tauri-apps/api/window`. Falls back to the
+   * screen of the window when out of range. (Windows and macOS only)
+   */
+  screen?: number;
+  /**
+   * What happens when several files have the same name, ignoring case. `"suffix"`
+   * renames later files to `name (1).ext`, `"error"` rejects the share and
+   * `"overwrite"` keeps only the last file. Defaults to `"suffix"`. (Windows and
+   * macOS only)
+   */
+  nameCollision?: NameCollision;
+  /**
+   * Leaves out the files whose content is identical to an earlier file, keeping
+   * the first one. Their number is reported as `duplicatesRemoved` in the result.
+   * Streamed files and folders are always kept. Defaults to `false`.
+   */
+  dedupeFiles?: boolean;
+  /**
+   * How receivers use the temporary copies of the shared files. With `"copy"`
+   * they are kept for the `retainAfterShareMs` grace period after the share, with
+   * `"reference"` they are deleted as soon as they are released. Defaults to
+   * `"copy"`. (Windows and macOS only)
+   */
+  shareMode?: ShareMode;
+  /**
+   * A URL opened once the share sheet is dismissed, e.g. a deep link of the app
+   * itself to resume a workflow. On Android, it is only opened when
+   * `waitForDismissal` is not `false`. (Android and iOS only)
+   */
+  returnUrl?: string;
+  /**
+   * A hint of when the shared content stops being valid, as a Unix timestamp in
+   * seconds, for receivers that honor ephemeral content. Attached as the
+   * `ExpiresAt` data package property on Windows and the
+   * `plugin.vnidrop.share.EXPIRES_AT` extra on Android; ignored elsewhere.
+   */
+  expiresAt?: number;
+  /**
+   * Payloads in custom data formats, as Base64 keyed by format id, for receivers
+   * that understand them, e.g. another app of the same vendor. Ids should be
+   * unique to the app, like `com.example.app.board`, and cannot be a standard
+   * format such as `Text`. Each payload decodes to at most 4 MiB. (Windows only)
+   */
+  customFormats?: Record<string, string>;
+  /**
+   * The addresses a mail target copies the share to, set as the `EXTRA_CC` of
+   * the share intent. Addresses cannot be empty, but are otherwise passed to the
+   * mail app as is. Ignored by other targets. (Android only)
+   */
+  cc?: string[];
+  /**
+   * The address replies to a shared mail go to, attached as the plugin's own
+   * `plugin.vnidrop.share.REPLY_TO` extra. Mail apps do not read it, so it has no
+   * effect unless the receiving app is written to look for it. It cannot be
+   * empty. (Android only)
+   */
+  replyTo?: string;
+  /**
+   * An image previewing the shared `url`, shown by receivers on the link's card
+   * instead of being shared as an attachment. On macOS it is only used when the
+   * `url` is shared without text. (Windows and macOS only)
+   */
+  previewImage?: File;
 }
 
 /**
@@ -39,14 +238,108 @@ export interface ShareData {
  * }
  * ```
  *
+ * When `data` is given, its files are also checked against the platform limits
+ * reported by `shareLimits()`.
+ *
  * @param data Optional ShareData to check shareability for.
  * @returns Promise resolving to `true` if sharing is possible.
  */
 export async function canShare(data?: ShareData): Promise<boolean> {
-  const result = (await invoke("plugin:vnidrop-share|can_share")) as {
-    value: any;
-  };
-  return result.value === true || result.value === "true";
+  const result = await invoke<CanShareResult>("plugin:vnidrop-share|can_share");
+  if (!result.value) {
+    return false;
+  }
+
+  const files = data?.files ?? [];
+  if (result.maxFiles != null && files.length > result.maxFiles) {
+    return false;
+  }
+  const totalBytes = files.reduce((total, file) => total + file.size, 0);
+  if (result.maxTotalBytes != null && totalBytes > result.maxTotalBytes) {
+    return false;
+  }
+  return true;
+}
+
+/**
+ * Limits the platform puts on the files of a single share.
+ *
+ * A `null` limit means the platform has no known limit. Individual share targets
+ * (e.g. MMS) may still apply their own.
+ */
+export interface ShareLimits {
+  /** The maximum number of files, if limited. */
+  maxFiles: number | null;
+  /** The maximum combined size of the files in bytes, if limited. */
+  maxTotalBytes: number | null;
+}
+
+/**
+ * Returns the limits the current platform puts on shared files.
+ *
+ * Example:
+ * ```ts
+ * const { maxFiles } = await shareLimits();
+ * if (maxFiles !== null && files.length > maxFiles) {
+ *   console.warn(`Only ${maxFiles} files can be shared at once.`);
+ * }
+ * ```
+ *
+ * @returns Promise resolving to the platform limits.
+ */
+export async function shareLimits(): Promise<ShareLimits> {
+  const { maxFiles, maxTotalBytes } = await invoke<CanShareResult>(
+    "plugin:vnidrop-share|can_share"
+  );
+  return { maxFiles, maxTotalBytes };
+}
+
+/**
+ * A share target the user recently shared to.
+ */
+export interface RecentTarget {
+  /** The display name of the target, e.g. an app or a contact. */
+  name: string;
+  /** The name `shareTo()` accepts for the target, if it can be shared to directly. */
+  service: string | null;
+}
+
+/**
+ * Lists the share targets the user recently shared to, where the platform
+ * exposes them.
+ *
+ * None of the supported platforms currently lets apps read its share history,
+ * so this resolves with an empty list everywhere. Treat a non-empty list as a
+ * bonus rather than something to rely on.
+ *
+ * Example:
+ * ```ts
+ * for (const target of await recentTargets()) {
+ *   console.log(`Recently shared to ${target.name}`);
+ * }
+ * ```
+ *
+ * @returns Promise resolving with the recent targets, most recent first.
+ */
+export async function recentTargets(): Promise<RecentTarget[]> {
+  return await invoke("plugin:vnidrop-share|recent_targets");
+}
+
+/**
+ * The outcome of a manual cleanup.
+ */
+export interface CleanupResult {
+  /** The number of temporary files that were deleted. */
+  removed: number;
+  /** The paths of the files that could not be deleted. */
+  failed: string[];
+  /** The given `paths` that were deleted. */
+  removedPaths?: string[];
+  /**
+   * The given `paths` that were left alone, as they are not temporary files
+   * managed by the plugin.
+   */
+  skipped?: string[];
 }
 
 /**
@@ -55,16 +348,142 @@ export async function canShare(data?: ShareData): Promise<boolean> {
  * Useful when files are generated during sharing but you want to remove them
  * immediately after to save storage space.
  *
+ * With `paths`, exactly those files are deleted, even while a share still uses
+ * them. Paths outside the plugin's temp directory or not managed by the plugin
+ * are skipped, so no other file can be deleted. On mobile, the native side
+ * manages the share files, so every path is skipped.
+ *
  * Example:
  * ```ts
- * await cleanup();
- * console.log("Temporary share files removed.");
+ * const { removed, failed } = await cleanup();
+ * console.log(`Removed ${removed} temporary share files.`);
+ * if (failed.length > 0) console.warn("Could not delete:", failed);
  * ```
  *
- * @returns Promise resolving when cleanup is complete.
+ * @param paths The temporary files to delete, if not all of them.
+ * @returns Promise resolving with the number of deleted files and the paths of the files that could not be deleted.
  */
-export async function cleanup(): Promise<void> {
-  await invoke("plugin:vnidrop-share|cleanup");
+export async function cleanup(paths?: string[]): Promise<CleanupResult> {
+  return await invoke<CleanupResult>("plugin:vnidrop-share|cleanup", { paths });
+}
+
+/**
+ * Deletes temporary files left behind by earlier runs, e.g. after a crash.
+ *
+ * Files tied to an active share, and files modified within the last hour, are
+ * kept. Does nothing on mobile.
+ *
+ * Example:
+ * ```ts
+ * const removed = await cleanupOrphans();
+ * ```
+ *
+ * @returns Promise resolving with the absolute paths of the removed files.
+ */
+export async function cleanupOrphans(): Promise<string[]> {
+  return await invoke("plugin:vnidrop-share|cleanup_orphans");
+}
+
+/**
+ * Cancels every share in progress and deletes the temporary files of all shares.
+ *
+ * The pending shares resolve as cancelled and the share UI is dismissed where
+ * possible. A share UI owned by the OS, like the macOS picker popover, the
+ * Windows share flyout or the mobile share sheets, may stay open until the user
+ * closes it. Shares still queued under `maxConcurrentShares` reject as
+ * cancelled. On mobile, this only deletes the files of the native share directory.
+ *
+ * Example:
+ * ```ts
+ * await cancelAllShares();
+ * ```
+ *
+ * @returns Promise resolving with the number of deleted files and the paths of the files that could not be deleted.
+ */
+export async function cancelAllShares(): Promise<CleanupResult> {
+  return await invoke<CleanupResult>("plugin:vnidrop-share|cancel_all_shares");
+}
+
+/**
+ * Returns the outcome of the last share performed from a window.
+ *
+ * Useful when a share is not awaited but the UI later wants to reflect its
+ * outcome. Resolves with `null` while a share is pending, after a failed share,
+ * or when the window has not shared anything yet.
+ *
+ * Example:
+ * ```ts
+ * void share(shareData);
+ * // later
+ * const result = await lastShareResult();
+ * ```
+ *
+ * @param label The label of the window. Defaults to the current window.
+ * @returns Promise resolving with the last `ShareResult`, or `null`.
+ */
+export async function lastShareResult(
+  label: string = getCurrentWindow().label
+): Promise<ShareResult | null> {
+  return await invoke("plugin:vnidrop-share|last_share_result", { label });
+}
+
+/**
+ * Presents the last share of the current window again, e.g. after the user
+ * cancelled it.
+ *
+ * Requires the `cacheLastShare` config, which keeps the options of the last
+ * `share` or `shareNowait` of each window until it completes with a target.
+ * Rejects when the window has no cached share.
+ *
+ * Example:
+ * ```ts
+ * const result = await share(shareData);
+ * if (result.status === "cancelled") await resumeLastShare();
+ * ```
+ *
+ * @returns Promise resolving with the `ShareResult` of the resumed share.
+ */
+export async function resumeLastShare(): Promise<ShareResult> {
+  return await invoke("plugin:vnidrop-share|resume_last_share");
+}
+
+/**
+ * Forgets the cached last share of the current window, so it can no longer be
+ * resumed. Its cached files are released.
+ *
+ * @returns Promise resolving with whether a cached share was discarded.
+ */
+export async function discardLastShare(): Promise<boolean> {
+  return await invoke("plugin:vnidrop-share|discard_last_share");
+}
+
+/**
+ * Returns the directory where the plugin writes its temporary files.
+ *
+ * Example:
+ * ```ts
+ * console.log(`Share files are written to ${await tempDir()}`);
+ * ```
+ *
+ * @returns Promise resolving to the absolute path of the directory.
+ */
+export async function tempDir(): Promise<string> {
+  return await invoke("plugin:vnidrop-share|temp_dir");
+}
+
+/**
+ * Returns the directory where files are written before being handed off with
+ * `handoffPaths`. It is created on first use.
+ *
+ * Example:
+ * ```ts
+ * const path = await join(await handoffDir(), "export.zip");
+ * ```
+ *
+ * @returns Promise resolving to the absolute path of the directory.
+ */
+export async function handoffDir(): Promise<string> {
+  return await invoke("plugin:vnidrop-share|handoff_dir");
 }
 
 /**
@@ -105,24 +524,570 @@ async function fileToBase64(file: File): Promise<string> {
  * ```
  *
  * @param data Content to share.
- * @returns Promise resolving when the share dialog is closed.
+ * @returns Promise resolving with the share outcome when the share dialog is closed.
+ */
+export async function share(data: ShareData): Promise<ShareResult> {
+  const payload = await toSharePayload(data);
+  return await invoke("plugin:vnidrop-share|share", { options: payload });
+}
+
+/**
+ * Starts a share without waiting for it, for fire-and-forget use cases.
+ *
+ * The share runs in the background and the promise resolves right away with the
+ * id of the share. Its outcome is reported by `onShareCompleted` with the same id.
+ * The temporary files of the share are released once it completes, then deleted
+ * after the `retainAfterShareMs` grace period.
+ *
+ * Example:
+ * ```ts
+ * const shareId = await shareNowait({ text: "Check this out!" });
+ * const unlisten = await onShareCompleted((event) => {
+ *   if (event.shareId === shareId) console.log(event.result ?? event.error);
+ * });
+ * ```
+ *
+ * @param data Content to share.
+ * @returns Promise resolving with the id of the share once it was dispatched.
  */
-export async function share(data: ShareData): Promise<void> {
+export async function shareNowait(data: ShareData): Promise<string> {
+  const payload = await toSharePayload(data);
+  return await invoke("plugin:vnidrop-share|share_nowait", { options: payload });
+}
+
+/**
+ * Shares several independent payloads one after another.
+ *
+ * Each share dialog is shown once the previous one was dismissed, so dialogs never
+ * overlap and `waitForDismissal` is ignored. Cancelling a dialog stops the batch:
+ * the remaining payloads are not shared, and the results end with
+ * `{ status: "cancelled" }`. If a share fails, the promise rejects and the
+ * remaining payloads are not shared either.
+ *
+ * Example:
+ * ```ts
+ * const results = await shareBatch([{ url: first }, { url: second }]);
+ * console.log(`${results.length} share dialogs were shown.`);
+ * ```
+ *
+ * @param payloads The content of each share, in order.
+ * @returns Promise resolving with the outcome of each share that was performed.
+ */
+export async function shareBatch(payloads: ShareData[]): Promise<ShareResult[]> {
+  const serialized = await Promise.all(payloads.map(toSharePayload));
+  return await invoke("plugin:vnidrop-share|share_batch", {
+    payloads: serialized,
+  });
+}
+
+/**
+ * Shares content straight to a specific sharing service, skipping the picker.
+ *
+ * Supported services on macOS are `mail`, `messages`, `airdrop`, `notes` and
+ * `reminders`, or any `NSSharingService` name. `serviceOptions` accepts `subject`
+ * and `recipients` (comma-separated) for `mail`, and `recipients` for `messages`.
+ * Unsupported services and options are ignored, and other platforms show their
+ * regular share dialog.
+ *
+ * Example:
+ * ```ts
+ * await shareTo("notes", { text: "Meeting notes" });
+ * ```
+ *
+ * @param service Name of the sharing service.
+ * @param data Content to share.
+ * @param serviceOptions Optional service-specific settings.
+ * @returns Promise resolving with the share outcome when the share is done.
+ */
+export async function shareTo(
+  service: string,
+  data: ShareData,
+  serviceOptions?: Record<string, string>
+): Promise<ShareResult> {
+  const payload = await toSharePayload(data);
+  return await invoke("plugin:vnidrop-share|share_to", {
+    options: { service, serviceOptions, options: payload },
+  });
+}
+
+/**
+ * Shares a string as a file attachment, without Base64-encoding it first.
+ *
+ * The MIME type is inferred from the file name and defaults to `text/plain`.
+ *
+ * Example:
+ * ```ts
+ * await shareAsFile("Hello from Tauri!", "greeting.txt");
+ * ```
+ *
+ * @param content Text content of the file.
+ * @param name Name of the file, including its extension.
+ * @returns Promise resolving with the share outcome when the share dialog is closed.
+ */
+export async function shareAsFile(
+  content: string,
+  name: string
+): Promise<ShareResult> {
+  return await invoke("plugin:vnidrop-share|share_as_file", {
+    options: { content, name },
+  });
+}
+
+/**
+ * Metadata of a file shared with {@link shareRawData}.
+ */
+export interface RawDataOptions {
+  /** Name of the file, including its extension. */
+  name: string;
+  /** MIME type of the file. Inferred from the name when omitted. */
+  mimeType?: string;
+  /** Text shared along with the file. */
+  text?: string;
+  /** Title of the share. */
+  title?: string;
+}
+
+/**
+ * Shares a single binary file without Base64-encoding it first.
+ *
+ * The bytes are sent as the raw body of the invoke, so large files do not pay
+ * for the encoding. Files larger than 256 MiB are rejected.
+ *
+ * Example:
+ * ```ts
+ * const file = fileInput.files[0];
+ * await shareRawData(await file.arrayBuffer(), { name: file.name, mimeType: file.type });
+ * ```
+ *
+ * @param data Content of the file.
+ * @param options Name and MIME type of the file, and the accompanying text.
+ * @returns Promise resolving with the share outcome when the share is done.
+ */
+export async function shareRawData(
+  data: ArrayBuffer | Uint8Array,
+  options: RawDataOptions
+): Promise<ShareResult> {
+  const headers: Record<string, string> = {
+    "x-share-name": encodeURIComponent(options.name),
+  };
+  if (options.mimeType) {
+    headers["x-share-mime-type"] = encodeURIComponent(options.mimeType);
+  }
+  if (options.text !== undefined) {
+    headers["x-share-text"] = encodeURIComponent(options.text);
+  }
+  if (options.title !== undefined) {
+    headers["x-share-title"] = encodeURIComponent(options.title);
+  }
+  return await invoke("plugin:vnidrop-share|share_raw_data", data, { headers });
+}
+
+/**
+ * Captures the current webview as a PNG image and shares it.
+ *
+ * The screenshot is shared as `screenshot.png`, along with any content in `data`.
+ * Supported on Windows and macOS; rejects with an "unsupported" error elsewhere.
+ *
+ * Example:
+ * ```ts
+ * await shareScreenshot({ text: "Look at this!" });
+ * ```
+ *
+ * @param data Optional content to share alongside the screenshot.
+ * @returns Promise resolving with the share outcome when the share is done.
+ */
+export async function shareScreenshot(data?: ShareData): Promise<ShareResult> {
+  const payload = data ? await toSharePayload(data) : undefined;
+  return await invoke("plugin:vnidrop-share|share_screenshot", {
+    options: payload,
+  });
+}
+
+/**
+ * Shares the current content of the system clipboard.
+ *
+ * Text is shared as text, and otherwise an image is shared as `clipboard.png`.
+ * Requires the `clipboard` cargo feature and the clipboard-manager plugin.
+ *
+ * Example:
+ * ```ts
+ * await shareClipboard();
+ * ```
+ *
+ * @returns Promise resolving with the share outcome when the share is done.
+ * Rejects when the clipboard holds neither text nor an image.
+ */
+export async function shareClipboard(): Promise<ShareResult> {
+  return await invoke("plugin:vnidrop-share|share_clipboard");
+}
+
+/**
+ * Sends a PDF document or an image to the print service of the platform.
+ *
+ * Printing is supported on macOS and iOS; elsewhere the promise rejects with an
+ * "unsupported" error.
+ *
+ * Example:
+ * ```ts
+ * await sharePrint(invoicePdf);
+ * ```
+ *
+ * @param file The PDF document or image to print.
+ * @returns Promise resolving with the print outcome once the print UI is dismissed.
+ * Rejects when the file is neither a PDF document nor an image.
+ */
+export async function sharePrint(file: File): Promise<ShareResult> {
+  return await invoke("plugin:vnidrop-share|share_print", {
+    file: {
+      data: await fileToBase64(file),
+      name: file.name,
+      mimeType: file.type || "application/octet-stream",
+    },
+  });
+}
+
+/**
+ * Describes a single file as it would be shared.
+ */
+export interface ValidatedFile {
+  /** The file name as provided. */
+  name: string;
+  /** The sanitized file name, before any platform-specific rename. */
+  resolvedName?: string;
+  /** The MIME type that would be attached to the shared item. */
+  resolvedMime?: string;
+  /** The size of the file content in bytes, unknown for directories. */
+  byteLength?: number;
+  /** The reason this file would be rejected, if any. */
+  error?: string;
+}
+
+/**
+ * A preview of what a call to `share()` would send.
+ */
+export interface ValidatedShare {
+  /** The title that would be passed to the share dialog. */
+  title?: string;
+  /** The text and URL merged as they would be shared. */
+  text?: string;
+  /** One entry per file, in the order they were provided. */
+  files: ValidatedFile[];
+}
+
+/**
+ * Validates share data without writing temporary files or showing any UI.
+ *
+ * Useful to render an accurate preview of what will be shared. Problems with
+ * individual files are reported on their entry instead of rejecting the promise.
+ *
+ * Example:
+ * ```ts
+ * const preview = await validate({ files: [myFile] });
+ * for (const file of preview.files) {
+ *   console.log(file.resolvedName, file.byteLength, file.error);
+ * }
+ * ```
+ *
+ * @param data Content to validate.
+ * @returns Promise resolving to the validated share description.
+ */
+export async function validate(data: ShareData): Promise<ValidatedShare> {
+  const payload = await toSharePayload(data);
+  return await invoke("plugin:vnidrop-share|validate", { options: payload });
+}
+
+/**
+ * Estimates the disk space the temporary files of a share would take.
+ *
+ * On Windows and macOS, the promise rejects when they do not fit in the free
+ * space of the temp dir, so the app can warn before sharing.
+ *
+ * Example:
+ * ```ts
+ * const bytes = await estimateDiskUsage({ files: [video] });
+ * ```
+ *
+ * @param data Content to estimate.
+ * @returns Promise resolving with the estimated number of bytes.
+ */
+export async function estimateDiskUsage(data: ShareData): Promise<number> {
+  const payload = await toSharePayload(data);
+  return await invoke("plugin:vnidrop-share|estimate_disk_usage", {
+    options: payload,
+  });
+}
+
+/**
+ * Converts `ShareData` into the payload expected by the Rust commands.
+ *
+ * @param data Content to convert.
+ * @returns Promise resolving to the serialized payload.
+ */
+async function toSharePayload(data: ShareData): Promise<any> {
   const payload: any = {
     text: data.text,
     title: data.title,
     url: data.url,
+    applicationName: data.applicationName,
+    applicationLink: data.applicationLink,
+    waitForDismissal: data.waitForDismissal,
+    urlProviderId: data.urlProviderId,
+    zeroizeData: data.zeroizeData,
+    textIsPlain: data.textIsPlain,
+    textUrlSeparator: data.textUrlSeparator,
+    urlFirst: data.urlFirst,
+    textItems: data.textItems,
+    textItemsSeparator: data.textItemsSeparator,
+    fallback: data.fallback,
+    preferredEdge: data.preferredEdge,
+    presentation: data.presentation,
+    screen: data.screen,
+    nameCollision: data.nameCollision,
+    dedupeFiles: data.dedupeFiles,
+    shareMode: data.shareMode,
+    returnUrl: data.returnUrl,
+    expiresAt: data.expiresAt,
+    customFormats: data.customFormats,
+    cc: data.cc,
+    replyTo: data.replyTo,
   };
 
+  if (data.previewImage) {
+    payload.previewImage = {
+      data: await fileToBase64(data.previewImage),
+      name: data.previewImage.name,
+      mimeType: data.previewImage.type || "application/octet-stream",
+    };
+  }
+
   if (data.files && data.files.length > 0) {
     payload.files = await Promise.all(
       data.files.map(async (file) => ({
         data: await fileToBase64(file),
         name: file.name,
         mimeType: file.type || "application/octet-stream",
+        uti: data.utis?.[file.name],
+        ...data.imageSizes?.[file.name],
+        displayName: data.displayNames?.[file.name],
       }))
     );
   }
 
-  await invoke("plugin:vnidrop-share|share", { options: payload });
+  if (data.paths && data.paths.length > 0) {
+    payload.files = (payload.files ?? []).concat(
+      data.paths.map((path) => {
+        const name = path.split(/[\\/]/).pop() ?? path;
+        return {
+          path,
+          name,
+          uti: data.utis?.[name],
+          ...data.imageSizes?.[name],
+          displayName: data.displayNames?.[name],
+        };
+      })
+    );
+  }
+
+  if (data.handoffPaths && data.handoffPaths.length > 0) {
+    payload.files = (payload.files ?? []).concat(
+      data.handoffPaths.map((path) => {
+        const name = path.split(/[\\/]/).pop() ?? path;
+        return {
+          path,
+          name,
+          handoff: true,
+          uti: data.utis?.[name],
+          ...data.imageSizes?.[name],
+          displayName: data.displayNames?.[name],
+        };
+      })
+    );
+  }
+
+  return payload;
+}
+
+/**
+ * Payload of the event emitted when part of a share could not be completed.
+ */
+export interface ShareErrorEvent {
+  /** A human-readable description of the problem. */
+  message: string;
+  /** The files affected by the problem, with the reason for each. */
+  files: string[];
+}
+
+/**
+ * Listens for errors that happen after the share dialog was opened, such as
+ * files that could not be attached in time.
+ *
+ * Example:
+ * ```ts
+ * const unlisten = await onShareError((event) => {
+ *   console.warn(event.message, event.files);
+ * });
+ * ```
+ *
+ * @param handler Called with the error payload.
+ * @returns Promise resolving to a function that removes the listener.
+ */
+export async function onShareError(
+  handler: (event: ShareErrorEvent) => void
+): Promise<UnlistenFn> {
+  return await listen<ShareErrorEvent>("vnidrop-share:error", (event) =>
+    handler(event.payload)
+  );
+}
+
+/**
+ * Payload of the event emitted when a share started by `shareNowait` finishes.
+ */
+export interface ShareCompletedEvent {
+  /** The id `shareNowait` resolved with. */
+  shareId: string;
+  /** The outcome of the share, unless it failed. */
+  result: ShareResult | null;
+  /** Why the share failed, if it did. */
+  error: string | null;
+}
+
+/**
+ * Listens for the outcome of the shares started by `shareNowait`.
+ *
+ * Example:
+ * ```ts
+ * const unlisten = await onShareCompleted((event) => {
+ *   console.log(event.shareId, event.result ?? event.error);
+ * });
+ * ```
+ *
+ * @param handler Called with the completion payload.
+ * @returns Promise resolving to a function that removes the listener.
+ */
+export async function onShareCompleted(
+  handler: (event: ShareCompletedEvent) => void
+): Promise<UnlistenFn> {
+  return await listen<ShareCompletedEvent>("vnidrop-share:completed", (event) =>
+    handler(event.payload)
+  );
+}
+
+/**
+ * Payload of the event emitted while an uploader registered from Rust runs.
+ */
+export interface UploadProgressEvent {
+  /** The id the uploader was registered with, as set in `urlProviderId`. */
+  uploaderId: string;
+  /** The number of bytes uploaded so far. */
+  sent: number;
+  /** The total number of bytes to upload, if known. */
+  total: number | null;
+}
+
+/**
+ * Listens for the progress of the uploaders run before a share, e.g. to show a
+ * progress bar until the share dialog opens.
+ *
+ * Example:
+ * ```ts
+ * const unlisten = await onUploadProgress((event) => {
+ *   if (event.total) console.log(`${(100 * event.sent) / event.total}%`);
+ * });
+ * ```
+ *
+ * @param handler Called with the progress payload.
+ * @returns Promise resolving to a function that removes the listener.
+ */
+export async function onUploadProgress(
+  handler: (event: UploadProgressEvent) => void
+): Promise<UnlistenFn> {
+  return await listen<UploadProgressEvent>(
+    "vnidrop-share:upload-progress",
+    (event) => handler(event.payload)
+  );
+}
+
+/**
+ * Payload of the event emitted when temporary files were deleted to stay under
+ * the `tempHighWaterMarkBytes` config.
+ */
+export interface CleanedEvent {
+  /** The paths of the deleted files, oldest first. */
+  files: string[];
+}
+
+/**
+ * Listens for the temporary files the plugin deleted on its own to stay under
+ * the `tempHighWaterMarkBytes` config.
+ *
+ * Example:
+ * ```ts
+ * const unlisten = await onCleaned(({ files }) => {
+ *   console.log(`Evicted ${files.length} temporary files`);
+ * });
+ * ```
+ *
+ * @param handler Called with the cleaned payload.
+ * @returns Promise resolving to a function that removes the listener.
+ */
+export async function onCleaned(
+  handler: (event: CleanedEvent) => void
+): Promise<UnlistenFn> {
+  return await listen<CleanedEvent>("vnidrop-share:cleaned", (event) =>
+    handler(event.payload)
+  );
+}
+
+/**
+ * A file received from another app.
+ */
+export interface ReceivedFile {
+  /** The absolute path of the file, inside the plugin's temp directory on Android. */
+  path: string;
+  /** The name of the file. */
+  name: string;
+  /** The MIME type reported by the sharing app, if any. */
+  mimeType?: string | null;
+}
+
+/**
+ * Content another app shared with this app.
+ */
+export interface ReceivedShare {
+  /** The subject the content was shared with, if any. */
+  title?: string | null;
+  /** The shared text, which may be a URL. */
+  text?: string | null;
+  /** The shared files. */
+  files: ReceivedFile[];
+}
+
+/**
+ * Listens for content other apps share with this app. (Android and iOS only)
+ *
+ * The share the app was launched with, if any, is delivered right after the
+ * listener is registered.
+ *
+ * Example:
+ * ```ts
+ * const unlisten = await onShareReceived((share) => {
+ *   console.log(share.text, share.files);
+ * });
+ * ```
+ *
+ * @param handler Called with each received share.
+ * @returns Promise resolving to a function that removes the listener.
+ */
+export async function onShareReceived(
+  handler: (share: ReceivedShare) => void
+): Promise<UnlistenFn> {
+  const unlisten = await listen<ReceivedShare>(
+    "vnidrop-share:received",
+    (event) => handler(event.payload)
+  );
+  const pending = await invoke<ReceivedShare[]>(
+    "plugin:vnidrop-share|take_received_shares"
+  );
+  pending.forEach(handler);
+  return unlisten;
 }
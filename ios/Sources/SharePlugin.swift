@@ -1,11 +1,15 @@
 import Tauri
 import UIKit
+import UniformTypeIdentifiers
 import WebKit
 
 struct SharedFile: Decodable {
     let data: String
+    /// The path of a handed-off file, which is moved into the share directory instead of `data`.
+    let path: String?
     let name: String
     let mimeType: String
+    let uti: String?
 }
 
 struct ShareOptions: Decodable {
@@ -13,6 +17,26 @@ struct ShareOptions: Decodable {
     var title: String?
     var url: String?
     var files: [SharedFile]?
+    var waitForDismissal: Bool?
+    var returnUrl: String?
+}
+
+struct PrintOptions: Decodable {
+    let file: SharedFile
+}
+
+enum SharedFileError: LocalizedError {
+    case invalidData(String)
+    case notHandedOff(String)
+
+    var errorDescription: String? {
+        switch self {
+        case .invalidData(let name):
+            return "Invalid Base64 data for file: \(name)"
+        case .notHandedOff(let path):
+            return "The file is not in the handoff directory: \(path)"
+        }
+    }
 }
 
 @_cdecl("init_plugin_share") 
@@ -32,19 +56,47 @@ public class SharePlugin: Plugin {
     /**
      * Deletes all temporary files created by this plugin.
      * This is a manual cleanup utility for the developer.
+     * Resolves with the number of deleted files and the paths of those that could not be deleted.
      */
     @objc func cleanup(_ invoke: Invoke) {
         do {
             let shareDir = try getSafeShareDir()
-            if FileManager.default.fileExists(atPath: shareDir.path) {
+            var removed = 0
+            var failed: [String] = []
+            let files = FileManager.default.enumerator(
+                at: shareDir,
+                includingPropertiesForKeys: [.isRegularFileKey]
+            )
+            while let file = files?.nextObject() as? URL {
+                let values = try? file.resourceValues(forKeys: [.isRegularFileKey])
+                guard values?.isRegularFile == true else { continue }
+                do {
+                    try FileManager.default.removeItem(at: file)
+                    removed += 1
+                } catch {
+                    failed.append(file.path)
+                }
+            }
+            if failed.isEmpty {
                 try FileManager.default.removeItem(at: shareDir)
             }
-            invoke.resolve()
+            invoke.resolve(["removed": removed, "failed": failed])
         } catch {
             invoke.reject("Error during cleanup: \(error.localizedDescription)")
         }
     }
 
+    /**
+     * Returns the dedicated directory where temporary share files are written.
+     */
+    @objc func tempDir(_ invoke: Invoke) {
+        do {
+            invoke.resolve(["path": try getSafeShareDir().path])
+        } catch {
+            invoke.reject("Failed to resolve the share directory: \(error.localizedDescription)")
+        }
+    }
+
     @objc func share(_ invoke: Invoke) throws {
         let args = try invoke.parseArgs(ShareOptions.self)
         var activityItems: [Any] = []
@@ -58,15 +110,9 @@ public class SharePlugin: Plugin {
 
         if let files = args.files {
             for file in files {
-                guard let decodedData = Data(base64Encoded: file.data) else {
-                    invoke.reject("Invalid Base64 data for file: \(file.name)")
-                    return
-                }
-                
                 do {
-                    let tempFileURL = try createSafeTempFile(for: file.name)
-                    try decodedData.write(to: tempFileURL, options:.atomic)
-                    activityItems.append(tempFileURL)
+                    let tempFileURL = try writeSharedFile(file)
+                    activityItems.append(shareItem(for: tempFileURL, file: file))
                     temporaryFileURLs.append(tempFileURL)
                 } catch {
                     invoke.reject("Failed to create temporary file: \(error.localizedDescription)")
@@ -80,9 +126,61 @@ public class SharePlugin: Plugin {
             return
         }
 
-        presentShareSheet(invoke: invoke, activityItems: activityItems)
+        presentShareSheet(
+            invoke: invoke,
+            activityItems: activityItems,
+            waitForDismissal: args.waitForDismissal ?? true,
+            returnUrl: args.returnUrl.flatMap { URL(string: $0) }
+        )
     }
     
+    /**
+     * Sends a PDF document or an image to the print service. The file is written to
+     * the share directory like a shared file, and resolves with the print activity as
+     * its target once printed.
+     */
+    @objc func printDocument(_ invoke: Invoke) throws {
+        let args = try invoke.parseArgs(PrintOptions.self)
+
+        let tempFileURL: URL
+        do {
+            tempFileURL = try writeSharedFile(args.file)
+            temporaryFileURLs.append(tempFileURL)
+        } catch {
+            invoke.reject("Failed to create temporary file: \(error.localizedDescription)")
+            return
+        }
+
+        guard UIPrintInteractionController.canPrint(tempFileURL) else {
+            cleanupTemporaryFiles()
+            invoke.reject("The file cannot be printed: \(args.file.name)")
+            return
+        }
+
+        DispatchQueue.main.async {
+            let printInfo = UIPrintInfo(dictionary: nil)
+            printInfo.jobName = args.file.name
+            printInfo.outputType = args.file.mimeType.hasPrefix("image/") ? .photo : .general
+
+            let controller = UIPrintInteractionController.shared
+            controller.printInfo = printInfo
+            controller.printingItem = tempFileURL
+            controller.present(animated: true) { _, completed, error in
+                self.cleanupTemporaryFiles()
+                if let anError = error {
+                    invoke.reject("Printing failed: \(anError.localizedDescription)")
+                } else if completed {
+                    invoke.resolve([
+                        "status": "completed",
+                        "target": UIActivity.ActivityType.print.rawValue,
+                    ])
+                } else {
+                    invoke.resolve(["status": "cancelled"])
+                }
+            }
+        }
+    }
+
     /**
      * Returns the URL for a dedicated, secure directory for storing temporary share files.
      * Creates it if it doesn't exist.
@@ -115,7 +213,57 @@ public class SharePlugin: Plugin {
         return safeDir.appendingPathComponent(finalFileName)
     }
 
-    private func presentShareSheet(invoke: Invoke, activityItems: [Any]) {
+    /**
+     * Writes a shared file into the share directory. A handed-off file is moved there
+     * from the handoff directory instead, so it is never read into memory.
+     */
+    private func writeSharedFile(_ file: SharedFile) throws -> URL {
+        let tempFileURL = try createSafeTempFile(for: file.name)
+        guard let path = file.path else {
+            guard let decodedData = Data(base64Encoded: file.data) else {
+                throw SharedFileError.invalidData(file.name)
+            }
+            try decodedData.write(to: tempFileURL, options: .atomic)
+            return tempFileURL
+        }
+
+        let source = URL(fileURLWithPath: path).resolvingSymlinksInPath()
+        let handoffDir = try getSafeShareDir()
+            .appendingPathComponent("handoff")
+            .resolvingSymlinksInPath()
+        guard source.path.hasPrefix(handoffDir.path + "/") else {
+            throw SharedFileError.notHandedOff(path)
+        }
+        try FileManager.default.moveItem(at: source, to: tempFileURL)
+        return tempFileURL
+    }
+
+    /**
+     * Wraps a file URL in an NSItemProvider declaring its UTType when the caller set one,
+     * or when the file has no extension but its MIME type is known. Otherwise the
+     * receiver infers the type from the extension.
+     */
+    private func shareItem(for url: URL, file: SharedFile) -> Any {
+        var typeIdentifier = file.uti
+        if #available(iOS 14.0, *), typeIdentifier == nil, url.pathExtension.isEmpty {
+            typeIdentifier = UTType(mimeType: file.mimeType)?.identifier
+        }
+        guard let typeIdentifier = typeIdentifier else {
+            return url
+        }
+        return NSItemProvider(item: url as NSURL, typeIdentifier: typeIdentifier)
+    }
+
+    /**
+     * Presents the share sheet. When `waitForDismissal` is false the invoke is resolved
+     * as soon as the sheet is shown, and the completion handler only cleans up.
+     */
+    private func presentShareSheet(
+        invoke: Invoke,
+        activityItems: [Any],
+        waitForDismissal: Bool,
+        returnUrl: URL?
+    ) {
         DispatchQueue.main.async {
             guard let viewController = self.manager.viewController else {
                 invoke.reject("Could not find root view controller.")
@@ -124,13 +272,27 @@ public class SharePlugin: Plugin {
 
             let activityViewController = UIActivityViewController(activityItems: activityItems, applicationActivities: nil)
             
-            activityViewController.completionWithItemsHandler = { _, _, _, error in
+            activityViewController.completionWithItemsHandler = { activityType, completed, _, error in
                 self.cleanupTemporaryFiles()
-                
+                // Bridges back into the app, e.g. through one of its own deep links.
+                if let returnUrl = returnUrl {
+                    UIApplication.shared.open(returnUrl)
+                }
+
+                guard waitForDismissal else {
+                    return
+                }
+
                 if let anError = error {
                     invoke.reject("Sharing failed: \(anError.localizedDescription)")
+                } else if completed {
+                    var result: [String: Any] = ["status": "completed"]
+                    if let activityType = activityType {
+                        result["target"] = activityType.rawValue
+                    }
+                    invoke.resolve(result)
                 } else {
-                    invoke.resolve()
+                    invoke.resolve(["status": "cancelled"])
                 }
             }
 
@@ -141,7 +303,12 @@ public class SharePlugin: Plugin {
                 popoverController.permittedArrowDirections = []
             }
 
-            viewController.present(activityViewController, animated: true, completion: nil)
+            viewController.present(activityViewController, animated: true) {
+                if !waitForDismissal {
+                    // Fire-and-forget: the outcome is never reported, so assume it completed.
+                    invoke.resolve(["status": "completed"])
+                }
+            }
         }
     }
 
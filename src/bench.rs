@@ -0,0 +1,23 @@
+//! Entry points for the benchmarks in `benches/`, which cannot reach the
+//! crate-private functions they measure. Not part of the public API.
+
+use std::path::Path;
+
+use tempfile::{Builder, NamedTempFile};
+
+use crate::{Error, Result, SharedFile};
+
+/// Decodes the Base64 `data` of `file`, as done before its temp file is written.
+pub fn decode_file_data(file: &SharedFile) -> Result<Vec<u8>> {
+    crate::validation::decode_file_data(file, None)
+}
+
+/// Writes `file` to a new temporary file in `dir`, the way the platforms write
+/// the files of a share.
+pub fn create_temp_file_for_data(file: &SharedFile, dir: &Path) -> Result<NamedTempFile> {
+    let mut temp_file = Builder::new()
+        .tempfile_in(dir)
+        .map_err(|e| Error::TempFile(format!("Failed to create temp file: {}", e)))?;
+    crate::platform::write_file_data(file, 0, &mut temp_file, None)?;
+    Ok(temp_file)
+}
@@ -0,0 +1,498 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::state::ShareCancelToken;
+use crate::validation::{is_within_dir, SharedPath};
+use crate::{CanShareResult, CleanupResult, Error, Result, ShareOptions, ShareResult, SharedFile};
+
+/// Invokes a method of the native mobile plugin.
+///
+/// Implemented by the `PluginHandle` of the mobile plugin. The payloads sent to
+/// the native side and the mapping of its responses are written against this
+/// trait, so they can be tested with a mock instead of a device.
+pub(crate) trait MobileBridge {
+    fn run<T: DeserializeOwned, P: Serialize>(&self, method: &str, payload: P) -> Result<T>;
+}
+
+#[cfg(mobile)]
+impl<R: tauri::Runtime> MobileBridge for tauri::plugin::PluginHandle<R> {
+    fn run<T: DeserializeOwned, P: Serialize>(&self, method: &str, payload: P) -> Result<T> {
+        self.run_mobile_plugin(method, payload).map_err(Into::into)
+    }
+}
+
+/// The response of the native `tempDir` method.
+#[derive(Deserialize)]
+struct TempDirResponse {
+    path: String,
+}
+
+/// Shares `options` through the native share sheet.
+///
+/// The types of the files are resolved with the app's MIME `overrides` first.
+/// Fails with [`Error::Cancelled`] when `cancel` is cancelled before the options
+/// are handed to the native side, which is checked before and after the streamed
+/// files are read. The handed-off files the native side did not take over are
+/// deleted once it returns.
+pub(crate) fn share(
+    bridge: &impl MobileBridge,
+    options: ShareOptions,
+    overrides: &HashMap<String, String>,
+    cancel: Option<&ShareCancelToken>,
+) -> Result<ShareResult> {
+    let handoff_dir = share_handoff_dir(bridge, options.files.iter().flatten())?;
+    let handoffs = handed_off_paths(options.files.iter().flatten(), handoff_dir.as_deref());
+    let result = send_share(bridge, options, overrides, cancel, handoff_dir.as_deref());
+    delete_handed_off_files(&handoffs);
+    result
+}
+
+/// Sends the share to the native side, see [`share`].
+fn send_share(
+    bridge: &impl MobileBridge,
+    options: ShareOptions,
+    overrides: &HashMap<String, String>,
+    cancel: Option<&ShareCancelToken>,
+    handoff_dir: Option<&Path>,
+) -> Result<ShareResult> {
+    let check_cancelled = || match cancel {
+        Some(cancel) if cancel.is_cancelled() => Err(Error::Cancelled),
+        _ => Ok(()),
+    };
+    check_cancelled()?;
+    let (payload, duplicates) = share_payload(options, overrides, handoff_dir)?;
+    check_cancelled()?;
+    let result: ShareResult = bridge.run("share", payload)?;
+    Ok(result.with_duplicates_removed(duplicates))
+}
+
+/// The payload of the native `printDocument` method.
+#[cfg(not(target_os = "android"))]
+#[derive(Serialize)]
+struct PrintPayload {
+    file: SharedFile,
+}
+
+/// Sends a PDF document or an image to the native print service.
+///
+/// The file is inlined like the files of a share, and its MIME type resolved so
+/// the native side knows how to render it.
+#[cfg(not(target_os = "android"))]
+pub(crate) fn print(
+    bridge: &impl MobileBridge,
+    mut file: SharedFile,
+    overrides: &HashMap<String, String>,
+) -> Result<ShareResult> {
+    let handoff_dir = share_handoff_dir(bridge, [&file])?;
+    let handoffs = handed_off_paths([&file], handoff_dir.as_deref());
+    let result = crate::validation::print_mime_type(&file, overrides).and_then(|mime_type| {
+        file.mime_type = mime_type;
+        inline_file(&mut file, handoff_dir.as_deref())?;
+        bridge.run("printDocument", PrintPayload { file })
+    });
+    delete_handed_off_files(&handoffs);
+    result
+}
+
+pub(crate) fn can_share(bridge: &impl MobileBridge) -> Result<CanShareResult> {
+    bridge.run("canShare", ())
+}
+
+pub(crate) fn cleanup(bridge: &impl MobileBridge) -> Result<CleanupResult> {
+    bridge.run("cleanup", ())
+}
+
+pub(crate) fn temp_dir(bridge: &impl MobileBridge) -> Result<PathBuf> {
+    let response: TempDirResponse = bridge.run("tempDir", ())?;
+    Ok(PathBuf::from(response.path))
+}
+
+/// Returns the handoff dir of the native share directory when one of `files` is
+/// handed off, without asking the native side otherwise.
+fn share_handoff_dir<'a>(
+    bridge: &impl MobileBridge,
+    files: impl IntoIterator<Item = &'a SharedFile>,
+) -> Result<Option<PathBuf>> {
+    if !files.into_iter().any(|file| file.handoff == Some(true)) {
+        return Ok(None);
+    }
+    crate::scope::handoff_dir(&temp_dir(bridge)?).map(Some)
+}
+
+/// The paths of the handed-off `files` that are in `handoff_dir`, the only ones
+/// the plugin may delete.
+fn handed_off_paths<'a>(
+    files: impl IntoIterator<Item = &'a SharedFile>,
+    handoff_dir: Option<&Path>,
+) -> Vec<PathBuf> {
+    let Some(handoff_dir) = handoff_dir else {
+        return Vec::new();
+    };
+    files
+        .into_iter()
+        .filter(|file| file.handoff == Some(true))
+        .filter_map(|file| file.path.as_deref())
+        .map(PathBuf::from)
+        .filter(|path| path.is_file() && is_within_dir(path, handoff_dir))
+        .collect()
+}
+
+/// Deletes the handed-off files still in the handoff dir.
+///
+/// The native side moves the files it shares into its own share directory, so
+/// these are the duplicates left out and the files of a share that failed.
+fn delete_handed_off_files(paths: &[PathBuf]) {
+    for path in paths {
+        match std::fs::remove_file(path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => eprintln!(
+                "Failed to delete the handed-off file '{}': {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+}
+
+/// Prepares the options of a share for the native side.
+///
+/// Streamed and path-based files are read into their Base64 `data`, since readers
+/// cannot cross the mobile bridge. Handed-off files are sent by path instead, see
+/// [`inline_file`]. The preview image and the custom data formats are not used on
+/// mobile, so they are dropped instead of being sent. The return URL must be
+/// absolute, as the native side opens it as is. Duplicate files are left out
+/// before any file is read, and their number is returned with the payload.
+fn share_payload(
+    mut options: ShareOptions,
+    overrides: &HashMap<String, String>,
+    handoff_dir: Option<&Path>,
+) -> Result<(ShareOptions, u32)> {
+    // The handed-off duplicates are deleted by the caller once the share returns.
+    let (duplicates, _) = crate::validation::dedupe_files(&mut options)?;
+    let mut options = crate::validation::normalize_share_options(options, overrides)?;
+    options.preview_image = None;
+    options.custom_formats = None;
+    if let Some(url) = options.return_url.as_deref() {
+        tauri::Url::parse(url)
+            .map_err(|e| Error::InvalidArgs(format!("Invalid return URL '{}': {}", url, e)))?;
+    }
+    for file in options.files.iter_mut().flatten() {
+        inline_file(file, handoff_dir)?;
+    }
+    Ok((options, duplicates))
+}
+
+/// Reads a streamed or path-based file into its Base64 `data`.
+///
+/// A handed-off file keeps its `path` instead: the native side moves it into its
+/// share directory, so a large file is neither read into memory nor copied. It
+/// must be in `handoff_dir`, since the native side takes it over.
+/// The native side writes the file under its name, so the display name replaces it.
+fn inline_file(file: &mut SharedFile, handoff_dir: Option<&Path>) -> Result<()> {
+    if let Some(display_name) = file.display_name.take() {
+        file.name = display_name;
+    }
+    let handoff = file.handoff.take() == Some(true);
+    if let Some(path) = file.path.take() {
+        if crate::validation::check_shared_path(&path)? == SharedPath::Directory {
+            return Err(Error::InvalidArgs(format!(
+                "Cannot share directory '{}': folders can only be shared on desktop",
+                path
+            )));
+        }
+        if !handoff {
+            file.data = general_purpose::STANDARD.encode(std::fs::read(&path)?);
+        } else if handoff_dir.is_some_and(|dir| is_within_dir(Path::new(&path), dir)) {
+            file.path = Some(path);
+        } else {
+            return Err(Error::InvalidArgs(format!(
+                "Cannot hand off '{}': handed-off files must be in the handoff dir",
+                path
+            )));
+        }
+    } else if let Some(mut reader) = file.reader.take().and_then(|reader| reader.take()) {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        file.data = general_purpose::STANDARD.encode(bytes);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    /// Records the invoked methods and answers with canned responses.
+    #[derive(Default)]
+    struct MockBridge {
+        calls: RefCell<Vec<(String, Value)>>,
+        responses: HashMap<&'static str, Value>,
+    }
+
+    impl MockBridge {
+        fn responding(method: &'static str, response: Value) -> Self {
+            Self {
+                responses: HashMap::from([(method, response)]),
+                ..Default::default()
+            }
+        }
+    }
+
+    impl MobileBridge for MockBridge {
+        fn run<T: DeserializeOwned, P: Serialize>(&self, method: &str, payload: P) -> Result<T> {
+            let payload = serde_json::to_value(payload).unwrap();
+            self.calls.borrow_mut().push((method.to_string(), payload));
+            let response = self.responses.get(method).cloned().unwrap_or(Value::Null);
+            serde_json::from_value(response).map_err(|e| Error::NativeApi(e.to_string()))
+        }
+    }
+
+    #[test]
+    fn share_sends_camel_case_options_with_inlined_files() {
+        let bridge = MockBridge::responding("share", json!({ "status": "cancelled" }));
+        let options = ShareOptions {
+            text: Some("Look".to_string()),
+            wait_for_dismissal: Some(false),
+            url_provider_id: Some("upload".to_string()),
+            files: Some(vec![SharedFile::from_reader("notes.txt", "", &b"hi"[..])]),
+            preview_image: Some(SharedFile::from_reader("card.png", "", &b"png"[..])),
+            ..Default::default()
+        };
+
+        let result = share(&bridge, options, &HashMap::new(), None).unwrap();
+
+        assert_eq!(result, ShareResult::Cancelled);
+        let calls = bridge.calls.borrow();
+        let (method, payload) = &calls[0];
+        assert_eq!(method, "share");
+        assert_eq!(payload["text"], "Look");
+        assert_eq!(payload["waitForDismissal"], false);
+        assert_eq!(payload["urlProviderId"], "upload");
+        assert_eq!(
+            payload["files"][0],
+            json!({ "data": "aGk=", "name": "notes.txt", "mimeType": "text/plain" })
+        );
+        assert!(payload.get("previewImage").is_none());
+    }
+
+    #[test]
+    fn return_url_must_be_absolute() {
+        let bridge = MockBridge::responding("share", json!({ "status": "cancelled" }));
+        let options = |url: &str| ShareOptions {
+            text: Some("Look".to_string()),
+            return_url: Some(url.to_string()),
+            ..Default::default()
+        };
+
+        share(
+            &bridge,
+            options("myapp://shared?id=1"),
+            &HashMap::new(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            bridge.calls.borrow()[0].1["returnUrl"],
+            "myapp://shared?id=1"
+        );
+
+        let result = share(&bridge, options("/shared"), &HashMap::new(), None);
+        assert!(matches!(result, Err(Error::InvalidArgs(_))));
+        assert_eq!(bridge.calls.borrow().len(), 1);
+    }
+
+    #[test]
+    fn cancelled_share_never_reaches_the_native_side() {
+        let bridge = MockBridge::default();
+        let cancel = ShareCancelToken::new();
+        cancel.cancel();
+
+        let result = share(
+            &bridge,
+            ShareOptions::default(),
+            &HashMap::new(),
+            Some(&cancel),
+        );
+
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert!(bridge.calls.borrow().is_empty());
+    }
+
+    /// A bridge whose native share directory is `dir`, answering shares with `share`.
+    fn bridge_with_temp_dir(dir: &Path, share: Value) -> MockBridge {
+        MockBridge {
+            responses: HashMap::from([("tempDir", json!({ "path": dir })), ("share", share)]),
+            ..Default::default()
+        }
+    }
+
+    fn handed_off(path: &Path) -> SharedFile {
+        let mut file = SharedFile::from_path(path, "");
+        file.handoff = Some(true);
+        file
+    }
+
+    #[test]
+    fn handed_off_file_is_sent_by_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let bridge = bridge_with_temp_dir(dir.path(), json!({ "status": "cancelled" }));
+        let path = crate::scope::handoff_dir(dir.path())
+            .unwrap()
+            .join("large.bin");
+        std::fs::write(&path, b"hi").unwrap();
+        let options = ShareOptions {
+            files: Some(vec![handed_off(&path)]),
+            ..Default::default()
+        };
+
+        share(&bridge, options, &HashMap::new(), None).unwrap();
+
+        let calls = bridge.calls.borrow();
+        let (method, payload) = &calls[1];
+        assert_eq!(method, "share");
+        assert_eq!(payload["files"][0]["path"], path.to_string_lossy().as_ref());
+        assert_eq!(payload["files"][0]["data"], "");
+        assert!(payload["files"][0].get("handoff").is_none());
+    }
+
+    #[test]
+    fn handed_off_file_left_behind_by_a_failed_share_is_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let bridge = bridge_with_temp_dir(dir.path(), json!("not a share result"));
+        let path = crate::scope::handoff_dir(dir.path())
+            .unwrap()
+            .join("large.bin");
+        std::fs::write(&path, b"hi").unwrap();
+        let options = ShareOptions {
+            files: Some(vec![handed_off(&path)]),
+            ..Default::default()
+        };
+
+        assert!(share(&bridge, options, &HashMap::new(), None).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn handed_off_file_outside_the_handoff_dir_is_rejected_and_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let bridge = bridge_with_temp_dir(dir.path(), json!({ "status": "cancelled" }));
+        let path = dir.path().join("document.pdf");
+        std::fs::write(&path, b"%PDF").unwrap();
+        let options = ShareOptions {
+            files: Some(vec![handed_off(&path)]),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            share(&bridge, options, &HashMap::new(), None),
+            Err(Error::InvalidArgs(_))
+        ));
+        assert!(path.exists());
+        assert!(bridge
+            .calls
+            .borrow()
+            .iter()
+            .all(|(method, _)| method != "share"));
+    }
+
+    #[test]
+    fn display_name_replaces_the_file_name() {
+        let bridge = MockBridge::responding("share", json!({ "status": "cancelled" }));
+        let mut file = SharedFile::from_reader("a1b2c3.tmp", "application/pdf", &b"hi"[..]);
+        file.display_name = Some("Invoice.pdf".to_string());
+        let options = ShareOptions {
+            files: Some(vec![file]),
+            ..Default::default()
+        };
+
+        share(&bridge, options, &HashMap::new(), None).unwrap();
+
+        let calls = bridge.calls.borrow();
+        assert_eq!(calls[0].1["files"][0]["name"], "Invoice.pdf");
+        assert!(calls[0].1["files"][0].get("displayName").is_none());
+    }
+
+    #[test]
+    fn removed_duplicates_are_reported_in_the_result() {
+        let bridge = MockBridge::responding("share", json!({ "status": "completed" }));
+        let file = |name: &str| SharedFile {
+            data: "aGk=".to_string(),
+            reader: None,
+            ..SharedFile::from_reader(name, "", &b""[..])
+        };
+        let options = ShareOptions {
+            files: Some(vec![file("a.txt"), file("b.txt")]),
+            dedupe_files: Some(true),
+            ..Default::default()
+        };
+
+        let result = share(&bridge, options, &HashMap::new(), None).unwrap();
+
+        assert!(matches!(
+            result,
+            ShareResult::Completed {
+                duplicates_removed: 1,
+                ..
+            }
+        ));
+        let calls = bridge.calls.borrow();
+        assert_eq!(calls[0].1["files"].as_array().unwrap().len(), 1);
+        assert_eq!(calls[0].1["files"][0]["name"], "a.txt");
+    }
+
+    #[test]
+    fn print_sends_the_inlined_file() {
+        let bridge = MockBridge::responding(
+            "printDocument",
+            json!({ "status": "completed", "target": "com.apple.UIKit.activity.Print" }),
+        );
+        let file = SharedFile::from_reader("invoice.pdf", "", &b"%PDF"[..]);
+
+        let result = print(&bridge, file, &HashMap::new()).unwrap();
+
+        assert!(matches!(result, ShareResult::Completed { .. }));
+        let calls = bridge.calls.borrow();
+        let (method, payload) = &calls[0];
+        assert_eq!(method, "printDocument");
+        assert_eq!(
+            payload["file"],
+            json!({ "data": "JVBERg==", "name": "invoice.pdf", "mimeType": "application/pdf" })
+        );
+    }
+
+    #[test]
+    fn print_rejects_files_that_cannot_be_printed() {
+        let bridge = MockBridge::default();
+        let file = SharedFile::from_reader("notes.txt", "", &b"hi"[..]);
+
+        assert!(matches!(
+            print(&bridge, file, &HashMap::new()),
+            Err(Error::InvalidArgs(_))
+        ));
+        assert!(bridge.calls.borrow().is_empty());
+    }
+
+    #[test]
+    fn responses_are_mapped_to_results() {
+        let bridge = MockBridge::responding("canShare", json!({ "value": true, "maxFiles": 5 }));
+        let result = can_share(&bridge).unwrap();
+        assert!(result.value);
+        assert_eq!(result.max_files, Some(5));
+        assert_eq!(result.max_total_bytes, None);
+
+        let bridge = MockBridge::responding("tempDir", json!({ "path": "/cache/share" }));
+        assert_eq!(temp_dir(&bridge).unwrap(), PathBuf::from("/cache/share"));
+
+        let bridge = MockBridge::responding("cleanup", json!({ "removed": 2, "failed": [] }));
+        assert_eq!(cleanup(&bridge).unwrap().removed, 2);
+        assert!(can_share(&bridge).is_err());
+    }
+}
@@ -0,0 +1,57 @@
+use tauri::{AppHandle, Runtime};
+
+use crate::{Error, ShareOptions};
+
+/// Builds the options sharing the current content of the clipboard.
+///
+/// Text is shared as text, and otherwise an image is shared as a PNG file. The
+/// clipboard is read through `tauri-plugin-clipboard-manager`, which the app must
+/// register. Fails with `InvalidArgs` when the clipboard holds neither.
+#[cfg(feature = "clipboard")]
+pub(crate) fn clipboard_options<R: Runtime>(app: &AppHandle<R>) -> Result<ShareOptions, Error> {
+    use std::io::Cursor;
+
+    use image::{ImageFormat, RgbaImage};
+    use tauri::Manager;
+    use tauri_plugin_clipboard_manager::Clipboard;
+
+    use crate::SharedFile;
+
+    let clipboard = app.try_state::<Clipboard<R>>().ok_or_else(|| {
+        Error::Unsupported(
+            "sharing the clipboard requires the clipboard-manager plugin to be registered"
+                .to_string(),
+        )
+    })?;
+
+    if let Some(text) = clipboard.read_text().ok().filter(|text| !text.is_empty()) {
+        return Ok(ShareOptions {
+            text: Some(text),
+            ..Default::default()
+        });
+    }
+
+    let image = clipboard
+        .read_image()
+        .map_err(|_| Error::InvalidArgs("The clipboard is empty".to_string()))?;
+    let rgba = RgbaImage::from_raw(image.width(), image.height(), image.rgba().to_vec())
+        .ok_or_else(|| Error::NativeApi("The clipboard image is malformed".to_string()))?;
+    let mut png = Vec::new();
+    rgba.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| Error::NativeApi(format!("Failed to encode the clipboard image: {}", e)))?;
+    Ok(ShareOptions {
+        files: Some(vec![SharedFile::from_reader(
+            "clipboard.png",
+            "image/png",
+            Cursor::new(png),
+        )]),
+        ..Default::default()
+    })
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub(crate) fn clipboard_options<R: Runtime>(_app: &AppHandle<R>) -> Result<ShareOptions, Error> {
+    Err(Error::Unsupported(
+        "sharing the clipboard requires the `clipboard` feature".to_string(),
+    ))
+}
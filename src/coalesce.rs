@@ -0,0 +1,263 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::{Config, Error, Result, ShareOptions, ShareResult};
+
+/// Computes the key identifying identical shares.
+///
+/// Shares with a streamed file are never coalesced, since a reader can only be
+/// consumed once and its content cannot be compared. `zeroize_data`,
+/// `preferred_edge`, `presentation`, `screen` and `share_mode` do not change what
+/// is shared, so they are not part of the key. `wait_for_dismissal` is, since it
+/// changes when the share resolves, and so the result the coalesced shares receive.
+pub(crate) fn share_key(options: &ShareOptions, service: Option<&str>) -> Option<u64> {
+    let mut hasher = DefaultHasher::new();
+    service.hash(&mut hasher);
+    options.text.hash(&mut hasher);
+    options.title.hash(&mut hasher);
+    options.url.hash(&mut hasher);
+    options.url_provider_id.hash(&mut hasher);
+    options.text_is_plain.hash(&mut hasher);
+    options.text_url_separator.hash(&mut hasher);
+    options.url_first.hash(&mut hasher);
+    options.text_items.hash(&mut hasher);
+    options.text_items_separator.hash(&mut hasher);
+    options.application_name.hash(&mut hasher);
+    options.application_link.hash(&mut hasher);
+    options.fallback.hash(&mut hasher);
+    options.name_collision.hash(&mut hasher);
+    options.return_url.hash(&mut hasher);
+    options.expires_at.hash(&mut hasher);
+    options.dedupe_files.hash(&mut hasher);
+    options.wait_for_dismissal.hash(&mut hasher);
+    options.custom_formats.hash(&mut hasher);
+    options.cc.hash(&mut hasher);
+    options.reply_to.hash(&mut hasher);
+    for file in options
+        .files
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .chain(&options.preview_image)
+    {
+        if file.reader.is_some() {
+            return None;
+        }
+        file.name.hash(&mut hasher);
+        file.mime_type.hash(&mut hasher);
+        file.data.hash(&mut hasher);
+        file.path.hash(&mut hasher);
+        file.uti.hash(&mut hasher);
+        file.width.hash(&mut hasher);
+        file.height.hash(&mut hasher);
+        file.display_name.hash(&mut hasher);
+    }
+    Some(hasher.finish())
+}
+
+/// Runs `share`, unless an identical share is pending or finished within the
+/// configured `coalesceWindowMs`, in which case its outcome is reused.
+pub(crate) async fn run<R: Runtime>(
+    app: &AppHandle<R>,
+    key: Option<u64>,
+    share: impl Future<Output = Result<ShareResult>>,
+) -> Result<ShareResult> {
+    let (Some(key), Some(window)) = (key, app.state::<Config>().coalesce_window()) else {
+        return share.await;
+    };
+
+    let turn = app.state::<ShareCoalescer>().begin(key, window);
+    match turn {
+        Turn::Lead(lead) => {
+            let result = share.await;
+            lead.finish(&result);
+            result
+        }
+        Turn::Follow(outcome) => {
+            tauri::async_runtime::spawn_blocking(move || outcome.wait()).await?
+        }
+    }
+}
+
+/// The outcome of a share, shared with the identical shares coalesced into it.
+#[derive(Default)]
+pub(crate) struct Outcome {
+    result: Mutex<Option<std::result::Result<ShareResult, String>>>,
+    done: Condvar,
+}
+
+impl Outcome {
+    fn set(&self, result: std::result::Result<ShareResult, String>) {
+        let mut slot = lock(&self.result);
+        if slot.is_none() {
+            *slot = Some(result);
+            self.done.notify_all();
+        }
+    }
+
+    fn get(&self) -> Option<std::result::Result<ShareResult, String>> {
+        lock(&self.result).clone()
+    }
+
+    /// Blocks until the share this outcome belongs to has finished.
+    pub(crate) fn wait(&self) -> Result<ShareResult> {
+        let mut slot = lock(&self.result);
+        while slot.is_none() {
+            slot = match self.done.wait(slot) {
+                Ok(slot) => slot,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+        }
+        let result = slot.clone().expect("the outcome is set once the loop ends");
+        result.map_err(Error::NativeApi)
+    }
+}
+
+/// Whether a share performs the native invocation or reuses an identical one.
+pub(crate) enum Turn {
+    Lead(Lead),
+    Follow(Arc<Outcome>),
+}
+
+/// Held by the share that performs the native invocation.
+///
+/// Dropping it without calling [`Lead::finish`] fails the coalesced shares, so
+/// they are never left waiting.
+pub(crate) struct Lead {
+    outcome: Arc<Outcome>,
+}
+
+impl Lead {
+    pub(crate) fn finish(self, result: &Result<ShareResult>) {
+        let result = match result {
+            Ok(result) => Ok(result.clone()),
+            Err(err) => Err(err.to_string()),
+        };
+        self.outcome.set(result);
+    }
+}
+
+impl Drop for Lead {
+    fn drop(&mut self) {
+        self.outcome
+            .set(Err("The coalesced share was aborted".to_string()));
+    }
+}
+
+struct Entry {
+    started: Instant,
+    outcome: Arc<Outcome>,
+}
+
+/// Tracks recent shares so identical requests can be coalesced.
+///
+/// Managed as a Tauri state. Only used when `coalesceWindowMs` is configured.
+#[derive(Default)]
+pub(crate) struct ShareCoalescer {
+    recent: Mutex<HashMap<u64, Entry>>,
+}
+
+impl ShareCoalescer {
+    /// Decides whether the share identified by `key` runs or follows an identical one.
+    ///
+    /// A share is followed while it is pending, and for `window` after it started.
+    pub(crate) fn begin(&self, key: u64, window: Duration) -> Turn {
+        let mut recent = lock(&self.recent);
+        let now = Instant::now();
+        recent.retain(|_, entry| {
+            now.duration_since(entry.started) < window || entry.outcome.get().is_none()
+        });
+
+        if let Some(entry) = recent.get(&key) {
+            return Turn::Follow(entry.outcome.clone());
+        }
+
+        let outcome = Arc::new(Outcome::default());
+        recent.insert(
+            key,
+            Entry {
+                started: now,
+                outcome: outcome.clone(),
+            },
+        );
+        Turn::Lead(Lead { outcome })
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(text: &str) -> ShareOptions {
+        ShareOptions {
+            text: Some(text.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn identical_options_share_a_key() {
+        assert_eq!(
+            share_key(&options("a"), None),
+            share_key(&options("a"), None)
+        );
+        assert_ne!(
+            share_key(&options("a"), None),
+            share_key(&options("b"), None)
+        );
+        assert_ne!(
+            share_key(&options("a"), None),
+            share_key(&options("a"), Some("mail"))
+        );
+    }
+
+    #[test]
+    fn waiting_for_dismissal_changes_the_key() {
+        let waiting = ShareOptions {
+            wait_for_dismissal: Some(true),
+            ..options("a")
+        };
+        assert_ne!(share_key(&options("a"), None), share_key(&waiting, None));
+    }
+
+    #[test]
+    fn identical_share_follows_the_pending_one() {
+        let coalescer = ShareCoalescer::default();
+        let window = Duration::from_secs(60);
+
+        let Turn::Lead(lead) = coalescer.begin(1, window) else {
+            panic!("the first share should lead");
+        };
+        let Turn::Follow(outcome) = coalescer.begin(1, window) else {
+            panic!("an identical share should follow");
+        };
+        assert!(matches!(coalescer.begin(2, window), Turn::Lead(_)));
+
+        lead.finish(&Ok(ShareResult::Cancelled));
+        assert_eq!(outcome.wait().unwrap(), ShareResult::Cancelled);
+    }
+
+    #[test]
+    fn finished_share_expires_after_the_window() {
+        let coalescer = ShareCoalescer::default();
+
+        let Turn::Lead(lead) = coalescer.begin(1, Duration::ZERO) else {
+            panic!("the first share should lead");
+        };
+        drop(lead);
+        assert!(matches!(coalescer.begin(1, Duration::ZERO), Turn::Lead(_)));
+    }
+}
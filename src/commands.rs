@@ -1,6 +1,45 @@
-use tauri::{command, AppHandle, Runtime, State, Window};
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-use crate::{error, models, state::PluginTempFileManager, ShareExt};
+use tauri::{
+    command, ipc::InvokeBody, AppHandle, Emitter, Manager, Runtime, State, Webview, Window,
+};
+
+use crate::{
+    clipboard, coalesce, error, last_result::LastShareResults, models, received,
+    resume::LastShareOptions, scope, state::PluginTempFileManager, validation, Config, ShareExt,
+};
+
+/// Runs `share` and records its outcome as the last share result of the window `label`.
+async fn track<R: Runtime>(
+    app: &AppHandle<R>,
+    label: String,
+    share: impl Future<Output = Result<models::ShareResult, error::Error>>,
+) -> Result<models::ShareResult, error::Error> {
+    let results = app.state::<LastShareResults>();
+    results.begin(&label);
+    let result = share.await;
+    results.finish(&label, &result);
+    result
+}
+
+/// Caches `options` as the last share of the window `label`, when the
+/// `cacheLastShare` config is set.
+fn cache_options<R: Runtime>(app: &AppHandle<R>, label: &str, options: &models::ShareOptions) {
+    if !app.state::<Config>().cache_last_share {
+        return;
+    }
+    let manager = app.state::<PluginTempFileManager>();
+    let cache = app.state::<LastShareOptions>();
+    match app.share().temp_dir() {
+        Ok(dir) => cache.store(label, options, &manager, &dir),
+        Err(e) => {
+            eprintln!("Failed to cache the last share of '{}': {}", label, e);
+            cache.discard(label, &manager);
+        }
+    }
+}
 
 /// The main command to share content.
 ///
@@ -8,7 +47,18 @@ use crate::{error, models, state::PluginTempFileManager, ShareExt};
 /// It creates and displays the native sharing dialog for the current platform.
 ///
 /// The temporary files created for sharing will be automatically managed and
-/// cleaned up.
+/// cleaned up. Files shared by path must be allowed by the file system scope.
+///
+/// If `options.url_provider_id` is set, the matching URL provider is awaited
+/// first and its link becomes the shared URL.
+///
+/// When `coalesceWindowMs` is configured, identical shares within the window are
+/// coalesced into a single native share.
+///
+/// The returned `ShareResult` tells whether a target was chosen and, where the
+/// platform reports it, which one. When `cacheLastShare` is configured, the
+/// options are kept until the share completes with a target, see
+/// `resume_last_share`.
 ///
 /// ## Arguments
 ///
@@ -26,8 +76,434 @@ pub async fn share<R: Runtime>(
     window: Window<R>,
     options: models::ShareOptions,
     state: State<'_, PluginTempFileManager>,
-) -> Result<(), error::Error> {
-    app.share().share(window, options, state)
+) -> Result<models::ShareResult, error::Error> {
+    scope::check_paths(&app, &options)?;
+    cache_options(&app, window.label(), &options);
+    share_cached(app, window, options, state).await
+}
+
+/// Shares `options`, already cached as the last share of the window, and discards
+/// the cached entry once the share completes with a target.
+async fn share_cached<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    options: models::ShareOptions,
+    state: State<'_, PluginTempFileManager>,
+) -> Result<models::ShareResult, error::Error> {
+    let key = coalesce::share_key(&options, None);
+    let label = window.label().to_string();
+    let share = coalesce::run(&app, key, async {
+        let options = app.share().resolve_url(options).await?;
+        app.share()
+            .share_async(window, options, state.clone())
+            .await
+    });
+    let result = track(&app, label.clone(), share).await;
+    app.state::<LastShareOptions>()
+        .finish(&label, &result, &state);
+    result
+}
+
+/// Starts a share without waiting for it, for fire-and-forget use cases.
+///
+/// The share is dispatched in the background and the command returns right away,
+/// never waiting for the focus heuristics or the share to complete. Its outcome is
+/// emitted as the `vnidrop-share:completed` event, with the returned share id, and
+/// recorded as the last share result of the window. Only the check of the paths
+/// against the file system scope fails the command itself.
+///
+/// The temporary files of the share are released once it completes, and deleted
+/// after the `retainAfterShareMs` grace period, so their lifetime depends on when
+/// the completion is reported rather than on the caller.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `window`: The Tauri window from which the sharing dialog will be shown.
+/// * `options`: A `ShareOptions` struct defining the content to share.
+///
+/// ## Returns
+///
+/// The id of the share, found again in its completion event.
+#[command]
+pub async fn share_nowait<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    options: models::ShareOptions,
+) -> Result<String, error::Error> {
+    scope::check_paths(&app, &options)?;
+    let share_id = uuid::Uuid::new_v4().to_string();
+    let id = share_id.clone();
+    tauri::async_runtime::spawn(async move {
+        let label = window.label().to_string();
+        cache_options(&app, &label, &options);
+        let share = async {
+            let options = app.share().resolve_url(options).await?;
+            let state = app.state::<PluginTempFileManager>();
+            app.share().share_async(window, options, state).await
+        };
+        let result = track(&app, label.clone(), share).await;
+        app.state::<LastShareOptions>().finish(
+            &label,
+            &result,
+            &app.state::<PluginTempFileManager>(),
+        );
+        let event = models::ShareCompletedEvent {
+            share_id: id,
+            error: result.as_ref().err().map(ToString::to_string),
+            result: result.ok(),
+        };
+        if let Err(e) = app.emit(models::SHARE_COMPLETED_EVENT, event) {
+            eprintln!("Failed to emit share completed event: {}", e);
+        }
+    });
+    Ok(share_id)
+}
+
+/// Shares several independent payloads one after another.
+///
+/// Each share dialog is shown only once the previous one was dismissed, so the
+/// dialogs never overlap: every payload waits for its dialog to close, whatever
+/// its `wait_for_dismissal`. All paths are checked against the file system scope
+/// before the first dialog is shown.
+///
+/// When the user cancels a dialog, the batch stops there: the remaining payloads
+/// are not shared and the results end with `Cancelled`. The same goes for a
+/// dialog that was not shown, ending the results with `NotShown`. When a share
+/// fails, the command fails with its error and the remaining payloads are not
+/// shared either.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `window`: The Tauri window from which the sharing dialogs will be shown.
+/// * `payloads`: The `ShareOptions` of each share, in order.
+/// * `state`: The `PluginTempFileManager` state, used internally to manage file cleanup.
+///
+/// ## Returns
+///
+/// The `ShareResult` of each share that was performed, in order.
+#[command]
+pub async fn share_batch<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    payloads: Vec<models::ShareOptions>,
+    state: State<'_, PluginTempFileManager>,
+) -> Result<Vec<models::ShareResult>, error::Error> {
+    for options in &payloads {
+        scope::check_paths(&app, options)?;
+    }
+    let mut results = Vec::with_capacity(payloads.len());
+    for mut options in payloads {
+        options.wait_for_dismissal = Some(true);
+        let share = async {
+            let options = app.share().resolve_url(options).await?;
+            app.share()
+                .share_async(window.clone(), options, state.clone())
+                .await
+        };
+        let result = track(&app, window.label().to_string(), share).await?;
+        let stopped = matches!(
+            result,
+            models::ShareResult::Cancelled | models::ShareResult::NotShown
+        );
+        results.push(result);
+        if stopped {
+            break;
+        }
+    }
+    Ok(results)
+}
+
+/// Shares content straight to a specific sharing service, skipping the picker.
+///
+/// The service is selected by name, with optional service-specific settings. When
+/// the service or one of its options is not supported on the current platform,
+/// it is ignored and the regular share dialog is shown instead. See
+/// `ShareToOptions` for the supported service names.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `window`: The Tauri window from which the sharing dialog will be shown.
+/// * `options`: A `ShareToOptions` struct with the service and the content to share.
+/// * `state`: The `PluginTempFileManager` state, used internally to manage file cleanup.
+#[command]
+pub async fn share_to<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    options: models::ShareToOptions,
+    state: State<'_, PluginTempFileManager>,
+) -> Result<models::ShareResult, error::Error> {
+    scope::check_paths(&app, &options.options)?;
+    let key = coalesce::share_key(&options.options, Some(&options.service));
+    let label = window.label().to_string();
+    let share = coalesce::run(&app, key, async {
+        let mut options = options;
+        options.options = app.share().resolve_url(options.options).await?;
+        app.share().share_to_async(window, options, state).await
+    });
+    track(&app, label, share).await
+}
+
+/// Shares a string as a file attachment.
+///
+/// The content is written to a temporary file named after `options.name` and
+/// shared exactly like a file passed to [`share`]. The MIME type is inferred
+/// from the file name, and the file is registered for cleanup.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `window`: The Tauri window from which the sharing dialog will be shown.
+/// * `options`: A `ShareAsFileOptions` struct with the file content and name.
+/// * `state`: The `PluginTempFileManager` state, used internally to manage file cleanup.
+#[command]
+pub async fn share_as_file<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    options: models::ShareAsFileOptions,
+    state: State<'_, PluginTempFileManager>,
+) -> Result<models::ShareResult, error::Error> {
+    let label = window.label().to_string();
+    track(&app, label, async {
+        app.share().share_as_file(window, options, state).await
+    })
+    .await
+}
+
+/// The largest raw body accepted by [`share_raw_data`], 256 MiB.
+///
+/// Tauri holds the body in memory until the invoke is handled, so larger files
+/// should be shared by path instead.
+const MAX_RAW_DATA_BYTES: usize = 256 * 1024 * 1024;
+
+/// Shares a single binary file sent as the raw request body.
+///
+/// This avoids the Base64 encoding of `ShareOptions` files: the frontend sends the
+/// bytes as the body of the invoke, and the file metadata as percent-encoded
+/// headers. `x-share-name` is required, `x-share-mime-type`, `x-share-text` and
+/// `x-share-title` are optional. The file is complete once the invoke is
+/// received. The body is written straight to a file in the handoff dir, which is
+/// handed off to the share and deleted with its other temporary files, or right
+/// away when the share fails before taking it.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `window`: The Tauri window from which the sharing dialog will be shown.
+/// * `request`: The invoke request, whose raw body holds the file content.
+/// * `state`: The `PluginTempFileManager` state, used internally to manage file cleanup.
+///
+/// ## Returns
+///
+/// The `ShareResult` of the share. Fails with `InvalidArgs` when the body is not
+/// raw, is larger than 256 MiB, or the name header is missing.
+#[command]
+pub async fn share_raw_data<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    request: tauri::ipc::Request<'_>,
+    state: State<'_, PluginTempFileManager>,
+) -> Result<models::ShareResult, error::Error> {
+    let (options, path) = raw_data_options(&app, &request)?;
+    let manager = state.inner();
+    let label = window.label().to_string();
+    let result = track(&app, label, async {
+        app.share().share_async(window, options, state).await
+    })
+    .await;
+    if result.is_err() {
+        discard_raw_body(&path, manager);
+    }
+    result
+}
+
+/// Builds the options of a [`share_raw_data`] request from its body and headers,
+/// and returns them with the path the body was written to.
+///
+/// Tauri only lends the body to the command, so it is written to disk from there
+/// rather than copied into a [`models::SharedFile`] reader.
+fn raw_data_options<R: Runtime>(
+    app: &AppHandle<R>,
+    request: &tauri::ipc::Request<'_>,
+) -> Result<(models::ShareOptions, PathBuf), error::Error> {
+    let InvokeBody::Raw(bytes) = request.body() else {
+        return Err(error::Error::InvalidArgs(
+            "The file must be sent as the raw request body".to_string(),
+        ));
+    };
+    if bytes.len() > MAX_RAW_DATA_BYTES {
+        return Err(error::Error::InvalidArgs(format!(
+            "The file is {} bytes, more than the {} bytes allowed",
+            bytes.len(),
+            MAX_RAW_DATA_BYTES
+        )));
+    }
+    let header = |name: &str| -> Result<Option<String>, error::Error> {
+        let Some(value) = request.headers().get(name) else {
+            return Ok(None);
+        };
+        let value = value.to_str().map_err(|_| {
+            error::Error::InvalidArgs(format!("Header '{}' is not visible ASCII", name))
+        })?;
+        validation::percent_decode(value).map(Some)
+    };
+    let name = header("x-share-name")?.ok_or_else(|| {
+        error::Error::InvalidArgs("The 'x-share-name' header is required".to_string())
+    })?;
+    let mime_type = header("x-share-mime-type")?.unwrap_or_default();
+    let text = header("x-share-text")?;
+    let title = header("x-share-title")?;
+    let path = write_raw_body(&app.share().handoff_dir()?, &name, bytes)?;
+    let options = models::ShareOptions {
+        text,
+        title,
+        files: Some(vec![raw_body_file(&path, name, mime_type)]),
+        ..Default::default()
+    };
+    Ok((options, path))
+}
+
+/// The file handing the raw body written at `path` off to the share.
+fn raw_body_file(path: &Path, name: String, mime_type: String) -> models::SharedFile {
+    let mut file = models::SharedFile::new(name, mime_type, String::new());
+    file.path = Some(path.to_string_lossy().into_owned());
+    file.handoff = Some(true);
+    file
+}
+
+/// Deletes the raw body written at `path` for a share that failed, unless the
+/// share took it over: its files are then deleted along with the share's.
+///
+/// A share can fail before it takes its handed-off files, e.g. when the type of
+/// the body is blocked, which would otherwise leave the body on disk until the
+/// orphaned files are cleaned up.
+fn discard_raw_body(path: &Path, manager: &PluginTempFileManager) {
+    if manager.is_managed(path) {
+        return;
+    }
+    if let Err(e) = std::fs::remove_file(path) {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            eprintln!("Failed to delete file {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Writes `body` to a new file in `handoff_dir` whose name ends with `name`, and
+/// returns its path.
+fn write_raw_body(handoff_dir: &Path, name: &str, body: &[u8]) -> Result<PathBuf, error::Error> {
+    let name = validation::sanitize_file_name(name)?;
+    let mut file = tempfile::Builder::new()
+        .prefix("share-")
+        .suffix(&format!("-{}", name))
+        .tempfile_in(handoff_dir)
+        .map_err(|e| error::Error::TempFile(format!("Failed to create temp file: {}", e)))?;
+    file.write_all(body)
+        .map_err(|e| error::Error::TempFile(format!("Failed to write temp file: {}", e)))?;
+    file.keep()
+        .map(|(_, path)| path)
+        .map_err(|e| error::Error::TempFile(format!("Failed to keep temp file: {}", e.error)))
+}
+
+/// Shares a screenshot of the calling webview.
+///
+/// The visible content of the webview is captured into a PNG temporary file, which
+/// is shared along with the optional `options` and registered for cleanup. Not
+/// every platform can capture the window: Linux and mobile return an
+/// `Unsupported` error.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `webview`: The Tauri webview to capture, from which the sharing dialog will be shown.
+/// * `options`: An optional `ShareOptions` struct with content to share alongside the screenshot.
+/// * `state`: The `PluginTempFileManager` state, used internally to manage file cleanup.
+#[command]
+pub async fn share_screenshot<R: Runtime>(
+    app: AppHandle<R>,
+    webview: Webview<R>,
+    options: Option<models::ShareOptions>,
+    state: State<'_, PluginTempFileManager>,
+) -> Result<models::ShareResult, error::Error> {
+    let options = options.unwrap_or_default();
+    scope::check_paths(&app, &options)?;
+    let label = webview.window().label().to_string();
+    track(&app, label, async {
+        let options = app.share().resolve_url(options).await?;
+        app.share().share_screenshot(webview, options, state).await
+    })
+    .await
+}
+
+/// Shares the current content of the system clipboard.
+///
+/// Text on the clipboard is shared as text, and otherwise an image is shared as a
+/// PNG temporary file, registered for cleanup like any other shared file. This
+/// requires the `clipboard` feature and the `tauri-plugin-clipboard-manager`
+/// plugin to be registered, and fails with `Unsupported` otherwise.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `window`: The Tauri window from which the sharing dialog will be shown.
+/// * `state`: The `PluginTempFileManager` state, used internally to manage file cleanup.
+///
+/// ## Returns
+///
+/// The `ShareResult` of the share. Fails with `InvalidArgs` when the clipboard
+/// holds neither text nor an image.
+#[command]
+pub async fn share_clipboard<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    state: State<'_, PluginTempFileManager>,
+) -> Result<models::ShareResult, error::Error> {
+    let options = clipboard::clipboard_options(&app)?;
+    let label = window.label().to_string();
+    track(&app, label, async {
+        app.share().share_async(window, options, state).await
+    })
+    .await
+}
+
+/// Sends a PDF document or an image to the print service of the platform.
+///
+/// A file sent as data is written to a temporary file registered for cleanup,
+/// like any other shared file. A file shared by path must be allowed by the file
+/// system scope. Printing is supported on macOS and iOS, other platforms return
+/// an `Unsupported` error.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `window`: The Tauri window from which the print UI will be shown.
+/// * `file`: The PDF document or image to print.
+/// * `state`: The `PluginTempFileManager` state, used internally to manage file cleanup.
+///
+/// ## Returns
+///
+/// The `ShareResult` of the print. Fails with `InvalidArgs` when the file is
+/// neither a PDF document nor an image.
+#[command]
+pub async fn share_print<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    file: models::SharedFile,
+    state: State<'_, PluginTempFileManager>,
+) -> Result<models::ShareResult, error::Error> {
+    if file.path.is_some() {
+        let options = models::ShareOptions {
+            files: Some(vec![file.clone()]),
+            ..Default::default()
+        };
+        scope::check_paths(&app, &options)?;
+    }
+    let label = window.label().to_string();
+    track(&app, label, async {
+        app.share().share_print_async(window, file, state).await
+    })
+    .await
 }
 
 /// Checks if the native sharing dialog is available on the current platform.
@@ -41,7 +517,8 @@ pub async fn share<R: Runtime>(
 ///
 /// ## Returns
 ///
-/// A `CanShareResult` struct containing a boolean value.
+/// A `CanShareResult` struct containing a boolean value and the platform's
+/// limits on shared files, if any.
 #[command]
 pub async fn can_share<R: Runtime>(
     app: AppHandle<R>,
@@ -49,16 +526,352 @@ pub async fn can_share<R: Runtime>(
     app.share().can_share()
 }
 
+/// Lists the share targets the user recently shared to, where the platform
+/// exposes them, so the frontend can offer shortcuts to them.
+///
+/// This only probes the platform: none of the supported platforms currently lets
+/// apps read its share history, so the list is empty everywhere. The Windows share
+/// UI, the macOS picker, the Android chooser and the iOS share sheet all rank
+/// recent targets without exposing them.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+///
+/// ## Returns
+///
+/// The recent targets, most recent first, or an empty list where the platform
+/// does not expose them.
+#[command]
+pub async fn recent_targets<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Vec<models::RecentTarget>, error::Error> {
+    app.share().recent_targets()
+}
+
 /// Manually triggers the cleanup of temporary files.
 ///
 /// While file cleanup is automatically handled when the app exits, this command
 /// can be used to manually force a cleanup, for example, after a file is shared
 /// and is no longer needed by the plugin.
 ///
+/// When `paths` is given, exactly those files are deleted instead, even while a
+/// share still uses them. Paths outside the plugin's temp dir, or not managed by
+/// the plugin, are skipped, so no other file can be deleted. On mobile the native
+/// side manages the share files, so every path is skipped.
+///
 /// ## Arguments
 ///
 /// * `app`: The Tauri application handle.
+/// * `paths`: The temporary files to delete, if not all of them.
+///
+/// ## Returns
+///
+/// A `CleanupResult` with the number of deleted files and the paths of the files
+/// that could not be deleted, along with the removed and skipped `paths`.
+#[command]
+pub async fn cleanup<R: Runtime>(
+    app: AppHandle<R>,
+    paths: Option<Vec<String>>,
+) -> Result<models::CleanupResult, error::Error> {
+    match paths {
+        Some(paths) => app.share().cleanup_paths(&paths),
+        None => app.share().cleanup(),
+    }
+}
+
+/// Cancels every share in progress and deletes the temporary files of all shares.
+///
+/// The pending shares resolve as cancelled, deferred files are completed and the
+/// share UI is dismissed where possible, leaving the plugin ready for new shares.
+/// A share UI owned by the OS, like the macOS picker popover, the Windows share
+/// flyout or the mobile share sheets, cannot always be dismissed by the app and
+/// may stay open until the user closes it. On mobile, this only deletes the files
+/// of the native share directory.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+///
+/// ## Returns
+///
+/// A `CleanupResult` with the number of deleted files and the paths of the files
+/// that could not be deleted.
+#[command]
+pub async fn cancel_all_shares<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<models::CleanupResult, error::Error> {
+    app.share().cancel_all_shares()
+}
+
+/// Deletes orphaned temporary files, such as leftovers from a crash.
+///
+/// Only files in the plugin's temp directory that are not tied to an active share
+/// are removed. The directory is shared with other running apps using the
+/// plugin, so files modified within the last hour are kept as well. This is
+/// safe to call on app start. On mobile it does nothing.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+///
+/// ## Returns
+///
+/// The absolute paths of the removed files.
 #[command]
-pub async fn cleanup<R: Runtime>(app: AppHandle<R>) -> Result<(), error::Error> {
-    app.share().cleanup()
+pub async fn cleanup_orphans<R: Runtime>(app: AppHandle<R>) -> Result<Vec<String>, error::Error> {
+    let removed = app.share().cleanup_orphans()?;
+    Ok(removed
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect())
+}
+
+/// Returns the directory where the plugin writes its temporary files.
+///
+/// This is a dedicated subdirectory of the system temp directory on desktop, and
+/// the plugin's share directory inside the app cache on mobile. It is mainly
+/// useful for debugging, or for apps that want to place their own content
+/// alongside the plugin's files.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+///
+/// ## Returns
+///
+/// The absolute path of the directory.
+#[command]
+pub async fn temp_dir<R: Runtime>(app: AppHandle<R>) -> Result<String, error::Error> {
+    let dir = app.share().temp_dir()?;
+    Ok(dir.to_string_lossy().to_string())
+}
+
+/// Returns the directory where the frontend writes the files it hands off.
+///
+/// It is a subdirectory of the plugin temp dir, created on first use. Files
+/// passed with `handoff` must be in it, since the plugin deletes them once the
+/// share ends.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+///
+/// ## Returns
+///
+/// The absolute path of the directory.
+#[command]
+pub async fn handoff_dir<R: Runtime>(app: AppHandle<R>) -> Result<String, error::Error> {
+    let dir = app.share().handoff_dir()?;
+    Ok(dir.to_string_lossy().to_string())
+}
+
+/// Previews the content that `share` would send, without side effects.
+///
+/// Each file reports its sanitized name, its MIME type resolved as for a real
+/// share, and its size when known: the decoded size of its Base64 data, or the
+/// size of the file at its path. Platform-specific renames, such as the extension
+/// Windows appends or the suffix of a name collision, are not reported. No
+/// temporary files are written and no UI is shown. Problems with individual files,
+/// such as a type the `blockedMimeTypes` config blocks, are reported on the
+/// corresponding entry rather than failing the whole command, except for paths
+/// outside the file system scope, which fail it.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `options`: The `ShareOptions` to validate.
+///
+/// ## Returns
+///
+/// A `ValidatedShare` struct describing the resolved payload.
+#[command]
+pub async fn validate<R: Runtime>(
+    app: AppHandle<R>,
+    options: models::ShareOptions,
+) -> Result<models::ValidatedShare, error::Error> {
+    scope::check_paths(&app, &options)?;
+    app.share().validate(&options)
+}
+
+/// Estimates the disk space the temporary files of a share would take.
+///
+/// The decoded size of each file's Base64 data is computed from its length,
+/// without decoding it or writing anything. Files shared by path are used in
+/// place and are not counted. On Windows and macOS, the estimate is checked
+/// against the free space of the temp dir, which `share` also does before
+/// writing any file, so a share never fails half-written on a full disk.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `options`: The `ShareOptions` to estimate.
+///
+/// ## Returns
+///
+/// The estimated number of bytes. Fails with `TempFile` when they do not fit in
+/// the temp dir.
+#[command]
+pub async fn estimate_disk_usage<R: Runtime>(
+    app: AppHandle<R>,
+    options: models::ShareOptions,
+) -> Result<u64, error::Error> {
+    app.share().estimate_disk_usage(&options)
+}
+
+/// Returns the shares received from other apps before the frontend listened for them.
+///
+/// On mobile, the share the app was launched with arrives before the frontend
+/// can listen for the `vnidrop-share:received` event, so it is queued instead.
+/// After the first call, incoming shares are only emitted as events. Always
+/// empty on desktop.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+///
+/// ## Returns
+///
+/// The queued `ReceivedShare`s, in the order they were received.
+#[command]
+pub async fn take_received_shares<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<Vec<models::ReceivedShare>, error::Error> {
+    Ok(app.state::<received::ReceivedShares>().take())
+}
+
+/// Returns the outcome of the last share performed from a window.
+///
+/// Useful when a share is not awaited but the UI later wants to reflect its
+/// outcome. The result is cleared when the window starts a new share, and failed
+/// shares record nothing. Shares performed by Rust code through `ShareExt` are
+/// not recorded.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `label`: The label of the window the share was performed from.
+///
+/// ## Returns
+///
+/// The `ShareResult` of the last finished share, or `None` while a share is
+/// pending or when the window has not shared anything yet.
+#[command]
+pub async fn last_share_result<R: Runtime>(
+    app: AppHandle<R>,
+    label: String,
+) -> Result<Option<models::ShareResult>, error::Error> {
+    Ok(app.state::<LastShareResults>().get(&label))
+}
+
+/// Presents the last share of the window again, e.g. after the user cancelled it.
+///
+/// Requires the `cacheLastShare` config, which keeps the options of the last
+/// `share` or `share_nowait` of each window until it completes with a target. The
+/// options are shared again like with `share`, so they stay cached until this
+/// share completes with a target too. Files shared by path must still exist;
+/// handed-off files are kept on disk while their share is cached.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `window`: The Tauri window whose last share is presented again.
+/// * `state`: The `PluginTempFileManager` state, used internally to manage file cleanup.
+///
+/// ## Returns
+///
+/// The `ShareResult` of the resumed share. Fails with `InvalidArgs` when the
+/// window has no cached share.
+#[command]
+pub async fn resume_last_share<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    state: State<'_, PluginTempFileManager>,
+) -> Result<models::ShareResult, error::Error> {
+    let options = app
+        .state::<LastShareOptions>()
+        .get(window.label())
+        .ok_or_else(|| {
+            error::Error::InvalidArgs(format!(
+                "No share to resume for window '{}'",
+                window.label()
+            ))
+        })?;
+    scope::check_paths(&app, &options)?;
+    share_cached(app, window, options, state).await
+}
+
+/// Discards the cached last share of the window, so it can no longer be resumed.
+///
+/// Its cached files are released, and deleted once the `retainAfterShareMs`
+/// grace period ends.
+///
+/// ## Arguments
+///
+/// * `app`: The Tauri application handle.
+/// * `window`: The Tauri window whose last share is discarded.
+///
+/// ## Returns
+///
+/// Whether a cached share was discarded.
+#[command]
+pub async fn discard_last_share<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+) -> Result<bool, error::Error> {
+    let manager = app.state::<PluginTempFileManager>();
+    Ok(app
+        .state::<LastShareOptions>()
+        .discard(window.label(), &manager))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{discard_raw_body, raw_body_file, write_raw_body};
+    use crate::models::ShareOptions;
+    use crate::state::PluginTempFileManager;
+    use crate::validation::check_blocked_mime_types;
+    use std::collections::HashMap;
+
+    #[test]
+    fn raw_body_is_written_under_its_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_raw_body(dir.path(), "../photo.png", b"png").unwrap();
+
+        assert_eq!(path.parent(), Some(dir.path()));
+        assert!(path.to_string_lossy().ends_with("-photo.png"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"png");
+    }
+
+    #[test]
+    fn raw_body_of_a_rejected_share_is_deleted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_raw_body(dir.path(), "run.sh", b"echo").unwrap();
+        let options = ShareOptions {
+            files: Some(vec![raw_body_file(
+                &path,
+                "run.sh".to_string(),
+                "application/x-sh".to_string(),
+            )]),
+            ..Default::default()
+        };
+        let blocked = ["application/x-sh".to_string()];
+        let manager = PluginTempFileManager::new();
+
+        assert!(check_blocked_mime_types(&options, &HashMap::new(), &blocked).is_err());
+        discard_raw_body(&path, &manager);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn raw_body_taken_over_by_the_share_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_raw_body(dir.path(), "notes.txt", b"hi").unwrap();
+        let manager = PluginTempFileManager::new();
+        let share_files = manager.begin_share();
+        share_files.add_file(path.clone());
+
+        discard_raw_body(&path, &manager);
+        assert!(path.exists());
+    }
 }
@@ -0,0 +1,383 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::{Error, ShareOptions};
+
+/// How long the main thread is given to prepare the share UI when `setupTimeoutMs` is not set.
+const DEFAULT_SETUP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long shared files may take to load when `fileLoadTimeoutMs` is not set.
+const DEFAULT_FILE_LOAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a share waits for the app to regain focus when `focusWaitTimeoutMs` is not set.
+const DEFAULT_FOCUS_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long a transient share host window is kept when `shareHostTimeoutMs` is not set.
+const DEFAULT_SHARE_HOST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long shared files outlive their share when `retainAfterShareMs` is not set.
+const DEFAULT_RETAIN_AFTER_SHARE: Duration = Duration::from_secs(5);
+
+/// How many shares may be in flight at once when `maxConcurrentShares` is not set.
+const DEFAULT_MAX_CONCURRENT_SHARES: u32 = 3;
+
+/// The plugin configuration.
+///
+/// It is read from the `plugins > vnidrop-share` section of `tauri.conf.json`.
+/// Every field is optional.
+///
+/// ## Example
+///
+/// ```json
+/// {
+///   "plugins": {
+///     "vnidrop-share": {
+///       "applicationLink": "https://example.com/app"
+///     }
+///   }
+/// }
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    /// A link back to the app, used by receivers to attribute shared content
+    /// ("shared from ..."). Currently used on Windows only.
+    pub application_link: Option<String>,
+    /// Lets the frontend share files by path without checking them against the
+    /// file system scope. Defaults to `false`.
+    ///
+    /// With the `fs-scope` feature, paths are checked against the scope of
+    /// `tauri-plugin-fs`. Without it, the frontend cannot share paths at all unless
+    /// this is set. Paths passed from Rust are always trusted.
+    #[serde(default)]
+    pub allow_unscoped_paths: bool,
+    /// How long, in milliseconds, a share waits for the main thread to prepare the
+    /// native share UI before failing with a timeout. Defaults to 10 seconds.
+    /// Used on Windows and macOS.
+    pub setup_timeout_ms: Option<u64>,
+    /// How long, in milliseconds, the share UI waits for the shared files to be
+    /// loaded before the data request is completed with the files that are ready.
+    /// Files that miss the deadline are reported through the `vnidrop-share:error`
+    /// event. Defaults to 10 seconds. Used on Windows.
+    pub file_load_timeout_ms: Option<u64>,
+    /// How long, in milliseconds, a share waits for the app window to regain focus
+    /// after the share UI was shown before resolving anyway. Defaults to 60
+    /// seconds. Used on Windows.
+    ///
+    /// This timer is independent of `file_load_timeout_ms`: files keep loading in
+    /// the background after the share resolved, and a slow load does not resolve
+    /// the share. The focus wait should be at least as long as the file load
+    /// timeout, or a share may resolve while the share UI still waits for files.
+    pub focus_wait_timeout_ms: Option<u64>,
+    /// How long, in milliseconds, a tray-only app's share waits for the share UI
+    /// of its transient host window to close before resolving anyway and
+    /// destroying the window. Defaults to 60 seconds. Used on Windows.
+    pub share_host_timeout_ms: Option<u64>,
+    /// Coalesces identical shares started within this many milliseconds of each
+    /// other into a single native share, whose outcome they all receive. Off by
+    /// default.
+    ///
+    /// Only shares from the frontend are coalesced, and never those with a
+    /// streamed file.
+    pub coalesce_window_ms: Option<u64>,
+    /// Downscales shared PNG, JPEG, BMP and TIFF images whose width or height
+    /// exceeds this many pixels to fit within it, preserving their aspect ratio
+    /// and format, before their temporary file is written. Other files are shared
+    /// unchanged. Off by default.
+    ///
+    /// Requires the `image-resize` cargo feature, and is ignored without it. Files
+    /// shared by path are never resized. Used on Windows and macOS.
+    pub image_max_dimension: Option<u32>,
+    /// How long, in milliseconds, the temporary files of a share are kept on disk
+    /// after the share completed, so receivers that read them lazily can finish.
+    /// A file released earlier, e.g. by the `cleanup` command or a dropped
+    /// `ShareSession`, is deleted once this grace period ends. Defaults to 5
+    /// seconds; `0` deletes files as soon as they are released. Files are
+    /// deleted when the app exits, unless `cleanup_on_exit` is `false`. Used on
+    /// Windows and macOS.
+    pub retain_after_share_ms: Option<u64>,
+    /// The bytes the temporary files of the plugin may take on disk before the
+    /// oldest files no share uses anymore are deleted, e.g. released files within
+    /// their `retain_after_share_ms` grace period. Off by default.
+    ///
+    /// The mark is checked whenever a share adds a file, and files are deleted
+    /// oldest first by creation time until the files take at most this many bytes.
+    /// Files still used by a share are never deleted. Each eviction is reported
+    /// through the `vnidrop-share:cleaned` event.
+    pub temp_high_water_mark_bytes: Option<u64>,
+    /// Whether the temporary files of the plugin are deleted when the app exits.
+    /// Defaults to `true`.
+    ///
+    /// When `false`, files outlive the app, e.g. so a relaunched app can resume a
+    /// share, and are only deleted by the `cleanup` and `cleanup_orphans`
+    /// commands. Files the app never cleans up stay in the temp dir until the OS
+    /// clears it, which may be never.
+    pub cleanup_on_exit: Option<bool>,
+    /// The name of a subdirectory of the plugin temp dir to write the temporary
+    /// files of the app to, e.g. its bundle identifier. Off by default, in which
+    /// case every app using the plugin writes to the same directory.
+    ///
+    /// A directory of its own lets IT departments exclude the files of the app
+    /// from antivirus scans without excluding those of other apps. It must be a
+    /// single path component. Used on Windows, macOS and Linux.
+    pub temp_subdirectory: Option<String>,
+    /// Starts the names of temporary files instead of a random UUID. Random
+    /// characters still follow it, so names stay unique. Used on macOS; Windows
+    /// writes files under their own name.
+    pub temp_file_prefix: Option<String>,
+    /// Separates the random part of the names of temporary files from the name
+    /// of the shared file. Defaults to `-`. Used on macOS.
+    pub temp_file_suffix: Option<String>,
+    /// How many shares may be in flight at once across all windows. Defaults to
+    /// 3; `0` removes the limit. Used on desktop.
+    ///
+    /// Further shares queue until a running one resolves, and start in turn. The
+    /// `setup_timeout_ms` of a queued share only starts once it leaves the queue,
+    /// so a share UI that never reports its outcome holds up the queue until the
+    /// share resolves anyway, e.g. after `focus_wait_timeout_ms` on Windows.
+    pub max_concurrent_shares: Option<u32>,
+    /// MIME types for file extensions the built-in table does not know, or maps
+    /// differently, e.g. `{ "myapp": "application/x-myapp" }`. Extensions are
+    /// matched case-insensitively, with or without their leading dot, so two keys
+    /// naming the same extension, e.g. `txt` and `.TXT`, are rejected.
+    ///
+    /// They are consulted before the built-in table whenever the type of a shared
+    /// file is inferred from its name, i.e. when it has no type or
+    /// `application/octet-stream`. An explicit type always wins.
+    #[serde(default)]
+    pub extension_mime_overrides: HashMap<String, String>,
+    /// MIME types that are never shared, e.g. executables and scripts, as full
+    /// types like `application/x-msdownload` or as `type/*` for every subtype of
+    /// a type. Empty by default.
+    ///
+    /// A share with a file or preview image whose resolved type matches an entry,
+    /// or a print of such a file, fails with `InvalidArgs`. Types are resolved as for sharing, so a type inferred from the
+    /// file name, including through `extension_mime_overrides`, is blocked as well.
+    #[serde(default)]
+    pub blocked_mime_types: Vec<String>,
+    /// Caches the options of the last share of each window until it completes
+    /// with a target, so the `resume_last_share` command can present it again,
+    /// e.g. after the user cancelled it. Defaults to `false`.
+    ///
+    /// Only the `share` and `share_nowait` commands are cached. Files sent as data
+    /// are written to the temp dir rather than kept in memory, and they and the
+    /// handed-off files are kept on disk while cached. Shares with a streamed file
+    /// are not cached, since their content can only be read once. The entry of a
+    /// window is discarded when the window is destroyed.
+    #[serde(default)]
+    pub cache_last_share: bool,
+    /// Options applied to every share that leaves them unset, e.g. a `title` or
+    /// `applicationName` the app always shares with. Off by default.
+    ///
+    /// Each option set by a share takes precedence over its default as a whole,
+    /// so a share's `textItems` replace the default ones instead of extending
+    /// them. `files` and `urlProviderId` are never taken from the defaults.
+    pub default_options: Option<ShareOptions>,
+}
+
+impl Config {
+    /// The resolved main-thread setup timeout.
+    pub fn setup_timeout(&self) -> Duration {
+        self.setup_timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_SETUP_TIMEOUT)
+    }
+
+    /// The resolved deadline for loading the shared files.
+    pub fn file_load_timeout(&self) -> Duration {
+        self.file_load_timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_FILE_LOAD_TIMEOUT)
+    }
+
+    /// The resolved timeout of the wait for the app to regain focus.
+    pub fn focus_wait_timeout(&self) -> Duration {
+        self.focus_wait_timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_FOCUS_WAIT_TIMEOUT)
+    }
+
+    /// The resolved timeout of the transient share host window.
+    pub fn share_host_timeout(&self) -> Duration {
+        self.share_host_timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_SHARE_HOST_TIMEOUT)
+    }
+
+    /// The resolved grace period of the files of a completed share.
+    pub fn retain_after_share(&self) -> Duration {
+        self.retain_after_share_ms
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_RETAIN_AFTER_SHARE)
+    }
+
+    /// Whether the temporary files are deleted when the app exits.
+    pub fn cleanup_on_exit(&self) -> bool {
+        self.cleanup_on_exit.unwrap_or(true)
+    }
+
+    /// Fails with `InvalidArgs` when a temp file setting would write files outside
+    /// of the plugin temp dir.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        let settings = [
+            ("tempSubdirectory", &self.temp_subdirectory),
+            ("tempFilePrefix", &self.temp_file_prefix),
+            ("tempFileSuffix", &self.temp_file_suffix),
+        ];
+        for (setting, value) in settings {
+            if value
+                .as_deref()
+                .is_some_and(|value| value.contains(['/', '\\']))
+            {
+                return Err(Error::InvalidArgs(format!(
+                    "The `{}` config must not contain path separators",
+                    setting
+                )));
+            }
+        }
+        if matches!(self.temp_subdirectory.as_deref(), Some("" | "." | "..")) {
+            return Err(Error::InvalidArgs(
+                "The `tempSubdirectory` config must name a directory".to_string(),
+            ));
+        }
+        let mut extensions = HashMap::new();
+        let mut overrides: Vec<_> = self.extension_mime_overrides.iter().collect();
+        overrides.sort();
+        for (extension, mime_type) in overrides {
+            crate::validation::normalize_mime_type(mime_type).map_err(|_| {
+                Error::InvalidArgs(format!(
+                    "The `extensionMimeOverrides` config maps '{}' to the invalid MIME type '{}'",
+                    extension, mime_type
+                ))
+            })?;
+            if let Some(other) = extensions.insert(normalize_extension(extension), extension) {
+                return Err(Error::InvalidArgs(format!(
+                    "The `extensionMimeOverrides` config maps both '{}' and '{}', which are the same extension",
+                    other, extension
+                )));
+            }
+        }
+        for mime_type in &self.blocked_mime_types {
+            crate::validation::normalize_mime_pattern(mime_type).map_err(|_| {
+                Error::InvalidArgs(format!(
+                    "The `blockedMimeTypes` config has the invalid MIME type '{}'",
+                    mime_type
+                ))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Validates the config, see [`Self::validate`], and normalizes the settings
+    /// matched against every share, so they are not normalized again per share.
+    pub(crate) fn normalized(mut self) -> Result<Self, Error> {
+        self.validate()?;
+        self.blocked_mime_types = self
+            .blocked_mime_types
+            .iter()
+            .map(|pattern| crate::validation::normalize_mime_pattern(pattern))
+            .collect::<Result<_, _>>()?;
+        self.extension_mime_overrides = self
+            .extension_mime_overrides
+            .into_iter()
+            .map(|(extension, mime_type)| (normalize_extension(&extension), mime_type))
+            .collect();
+        Ok(self)
+    }
+
+    /// The resolved limit of shares in flight at once, if any.
+    pub fn max_concurrent_shares(&self) -> Option<usize> {
+        match self
+            .max_concurrent_shares
+            .unwrap_or(DEFAULT_MAX_CONCURRENT_SHARES)
+        {
+            0 => None,
+            limit => Some(limit as usize),
+        }
+    }
+
+    /// The window in which identical shares are coalesced, if enabled.
+    pub fn coalesce_window(&self) -> Option<Duration> {
+        self.coalesce_window_ms.map(Duration::from_millis)
+    }
+}
+
+/// The key of an extension in the `extensionMimeOverrides` config: lowercase,
+/// without a leading dot.
+pub(crate) fn normalize_extension(extension: &str) -> String {
+    extension.trim_start_matches('.').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_file_settings_cannot_escape_the_temp_dir() {
+        let config = |subdirectory: &str, prefix: &str| Config {
+            temp_subdirectory: Some(subdirectory.to_string()),
+            temp_file_prefix: Some(prefix.to_string()),
+            ..Default::default()
+        };
+
+        assert!(config("com.example.app", "example-").validate().is_ok());
+        assert!(config("..", "example-").validate().is_err());
+        assert!(config("com.example.app", "../example-").validate().is_err());
+        assert!(config("shared\\example", "").validate().is_err());
+    }
+
+    #[test]
+    fn mime_overrides_must_be_valid_mime_types() {
+        let config = |mime_type: &str| Config {
+            extension_mime_overrides: HashMap::from([("myapp".to_string(), mime_type.to_string())]),
+            ..Default::default()
+        };
+
+        assert!(config("application/x-myapp").validate().is_ok());
+        assert!(config("myapp").validate().is_err());
+    }
+
+    #[test]
+    fn mime_override_extensions_are_normalized_and_unique() {
+        let config = |extensions: &[&str]| Config {
+            extension_mime_overrides: extensions
+                .iter()
+                .map(|extension| (extension.to_string(), "application/x-myapp".to_string()))
+                .collect(),
+            ..Default::default()
+        };
+
+        let normalized = config(&[".MyApp", "board"]).normalized().unwrap();
+        let mut extensions: Vec<_> = normalized.extension_mime_overrides.keys().collect();
+        extensions.sort();
+        assert_eq!(extensions, ["board", "myapp"]);
+        assert!(config(&["myapp", ".MYAPP"]).validate().is_err());
+        assert!(config(&["txt", "TXT"]).normalized().is_err());
+    }
+
+    #[test]
+    fn blocked_mime_types_must_be_valid_patterns() {
+        let config = |mime_type: &str| Config {
+            blocked_mime_types: vec![mime_type.to_string()],
+            ..Default::default()
+        };
+
+        assert!(config("application/x-sh").validate().is_ok());
+        assert!(config("text/*").validate().is_ok());
+        assert!(config("*/*").validate().is_err());
+        assert!(config("executable").validate().is_err());
+    }
+
+    #[test]
+    fn blocked_mime_types_are_normalized_once_loaded() {
+        let config = Config {
+            blocked_mime_types: vec![" Text/* ".to_string(), "Application/X-SH".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            config.normalized().unwrap().blocked_mime_types,
+            ["text/*", "application/x-sh"]
+        );
+    }
+}
@@ -1,33 +1,474 @@
-use crate::state::PluginTempFileManager;
-use crate::{models::*, Result};
+use crate::limit::ShareLimiter;
+use crate::state::{PluginTempFileManager, ShareCancelToken, ShareFiles, ShareSession};
+use crate::url_provider::{UploadProgress, UrlProviders};
+use crate::{models::*, Config, Result};
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
 use tauri::plugin::PluginApi;
-use tauri::{AppHandle, Manager, Runtime, State, Window};
+use tauri::{AppHandle, Emitter, Manager, Runtime, State, Webview, Window};
 
 use crate::platform;
 
+/// The name of the file a screenshot is shared as.
+const SCREENSHOT_FILE_NAME: &str = "screenshot.png";
+
 /// A handle to the `tauri-plugin-share` APIs for desktop.
 ///
 /// This struct provides the public interface for the plugin's commands,
-/// abstracting away the platform-specific implementations.
-pub struct Share<R: Runtime>(AppHandle<R>);
+/// abstracting away the platform-specific implementations. Shares are queued
+/// beyond the `maxConcurrentShares` config.
+pub struct Share<R: Runtime>(AppHandle<R>, UrlProviders, ShareLimiter);
 
 impl<R: Runtime> Share<R> {
+    /// Registers an async function that mints the URL of shares referencing `id`
+    /// through their `url_provider_id`, e.g. by uploading content and returning a
+    /// time-limited link.
+    ///
+    /// The provider is awaited on the async runtime by the `share` and `share_to`
+    /// commands, before any temporary file is written or native UI is shown, so a
+    /// slow provider delays the share dialog. If it fails, the share fails with its
+    /// error. Rust callers of [`Self::share`] must call [`Self::resolve_url`] first.
+    pub fn register_url_provider<F, Fut>(&self, id: impl Into<String>, provider: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.1.register(id.into(), provider);
+    }
+
+    /// Registers an async function that uploads content and returns the URL to
+    /// share, for shares referencing `id` through their `url_provider_id`.
+    ///
+    /// It runs like a URL provider, see [`Self::register_url_provider`], and reports
+    /// its progress through the [`UploadProgress`] it is given, which the frontend
+    /// receives as the `vnidrop-share:upload-progress` event.
+    pub fn register_uploader<F, Fut>(&self, id: impl Into<String>, uploader: F)
+    where
+        F: Fn(UploadProgress) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.1.register_uploader(id.into(), uploader);
+    }
+
+    /// Sets an async function that rewrites the URL of every share before it is
+    /// shared, e.g. to shorten it or add tracking parameters. It replaces the
+    /// previous transformer, and without one URLs are shared as is.
+    ///
+    /// The transformer runs on the async runtime right after any URL provider,
+    /// before any temporary file is written or native UI is shown, so a slow
+    /// transformer delays the share dialog, for at most 10 seconds. If it fails or
+    /// takes longer, the error is logged and the original URL is shared instead of
+    /// failing the share. Rust callers of
+    /// [`Self::share`] must call [`Self::resolve_url`] first.
+    pub fn set_url_transformer<F, Fut>(&self, transformer: F)
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.1.set_transformer(transformer);
+    }
+
+    /// Resolves the `url_provider_id` of `options` into its `url`, and passes the
+    /// URL through the URL transformer.
+    pub async fn resolve_url(&self, options: ShareOptions) -> Result<ShareOptions> {
+        self.1.resolve(options).await
+    }
+
+    /// Shares content, blocking the calling thread until the share UI is dismissed.
+    ///
+    /// Prefer [`Self::share_async`] from async code. The thread is also blocked
+    /// while the share waits for a slot under the `maxConcurrentShares` config.
     pub fn share(
         &self,
         window: Window<R>,
         options: ShareOptions,
         state: State<'_, PluginTempFileManager>,
-    ) -> Result<()> {
-        platform::share(window, options, state)
+    ) -> Result<ShareResult> {
+        let (options, duplicates) = self.normalize(options)?;
+        let mode = options.share_mode.unwrap_or_default();
+        let _permit = self.2.acquire()?;
+        let files = state.begin_share();
+        self.adopt_handoff_files(options.files.iter().flatten(), &files);
+        let result = platform::share(window, options, files.clone());
+        files.finish(mode);
+        result.map(|result| result.with_duplicates_removed(duplicates))
+    }
+
+    /// Shares content, resolving once the share UI is dismissed.
+    ///
+    /// Unlike [`Self::share`], no thread is blocked while the share UI is shown:
+    /// the future is woken by the completion of the share, e.g. the app window
+    /// regaining focus on Windows or the share delegate on macOS.
+    pub async fn share_async(
+        &self,
+        window: Window<R>,
+        options: ShareOptions,
+        state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        let (options, duplicates) = self.normalize(options)?;
+        let mode = options.share_mode.unwrap_or_default();
+        let _permit = self.2.acquire_async().await?;
+        let files = state.begin_share();
+        self.adopt_handoff_files(options.files.iter().flatten(), &files);
+        let result = platform::share_async(window, options, files.clone()).await;
+        files.finish(mode);
+        result.map(|result| result.with_duplicates_removed(duplicates))
+    }
+
+    /// Shares content unless `cancel` is cancelled while the share is prepared.
+    ///
+    /// Once cancelled, no further temporary file is written, the share UI is not
+    /// shown and the files already written are deleted; the share then fails with
+    /// [`crate::Error::Cancelled`]. On Windows the files are written after the share
+    /// UI is shown, so cancelling then only stops the files that are not loaded yet.
+    pub fn share_cancellable(
+        &self,
+        window: Window<R>,
+        options: ShareOptions,
+        cancel: &ShareCancelToken,
+    ) -> Result<ShareResult> {
+        if cancel.is_cancelled() {
+            return Err(crate::Error::Cancelled);
+        }
+        let (options, duplicates) = self.normalize(options)?;
+        let mode = options.share_mode.unwrap_or_default();
+        let _permit = self.2.acquire()?;
+        let files = self
+            .0
+            .state::<PluginTempFileManager>()
+            .begin_cancellable_share(cancel.clone());
+        self.adopt_handoff_files(options.files.iter().flatten(), &files);
+        let result = platform::share(window, options, files.clone());
+        match result {
+            Err(crate::Error::Cancelled) => files.release(),
+            _ => files.finish(mode),
+        }
+        result.map(|result| result.with_duplicates_removed(duplicates))
+    }
+
+    /// Shares content and keeps its temporary files alive until the returned session is dropped.
+    ///
+    /// The session also counts as an in-flight share under the
+    /// `maxConcurrentShares` config until it is dropped.
+    pub fn begin_share(&self, window: Window<R>, options: ShareOptions) -> Result<ShareSession> {
+        let (options, duplicates) = self.normalize(options)?;
+        let mode = options.share_mode.unwrap_or_default();
+        let permit = self.2.acquire()?;
+        let files = self.0.state::<PluginTempFileManager>().begin_share();
+        self.adopt_handoff_files(options.files.iter().flatten(), &files);
+        let result = platform::share(window, options, files.clone());
+        files.finish(mode);
+        Ok(ShareSession::new(result?.with_duplicates_removed(duplicates), files).holding(permit))
+    }
+
+    /// Shares content straight to a specific sharing service, see [`ShareToOptions`].
+    pub fn share_to(
+        &self,
+        window: Window<R>,
+        options: ShareToOptions,
+        state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        let (share_options, duplicates) = self.normalize(options.options)?;
+        let mode = share_options.share_mode.unwrap_or_default();
+        let _permit = self.2.acquire()?;
+        let files = state.begin_share();
+        self.adopt_handoff_files(share_options.files.iter().flatten(), &files);
+        let result = platform::share_to(
+            window,
+            share_options,
+            &options.service,
+            &options.service_options,
+            files.clone(),
+        );
+        files.finish(mode);
+        result.map(|result| result.with_duplicates_removed(duplicates))
+    }
+
+    /// Shares content straight to a specific sharing service like [`Self::share_to`],
+    /// without blocking a thread while the share UI is shown.
+    pub async fn share_to_async(
+        &self,
+        window: Window<R>,
+        options: ShareToOptions,
+        state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        let (share_options, duplicates) = self.normalize(options.options)?;
+        let mode = share_options.share_mode.unwrap_or_default();
+        let _permit = self.2.acquire_async().await?;
+        let files = state.begin_share();
+        self.adopt_handoff_files(share_options.files.iter().flatten(), &files);
+        let result = platform::share_to_async(
+            window,
+            share_options,
+            &options.service,
+            &options.service_options,
+            files.clone(),
+        )
+        .await;
+        files.finish(mode);
+        result.map(|result| result.with_duplicates_removed(duplicates))
+    }
+
+    /// Performs the sharing service named in `options` directly, without a picker
+    /// and without falling back to one. (macOS only)
+    ///
+    /// `service` is a short name accepted by [`Self::share_to`] or any
+    /// `NSSharingService` name, such as [`crate::SERVICE_AIRDROP`]. Fails with
+    /// [`crate::Error::NativeApi`] when the service is unknown or cannot perform the
+    /// items, and with [`crate::Error::Unsupported`] on other platforms.
+    pub fn perform_service(
+        &self,
+        window: Window<R>,
+        options: ShareToOptions,
+        state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        let (share_options, duplicates) = self.normalize(options.options)?;
+        let mode = share_options.share_mode.unwrap_or_default();
+        let _permit = self.2.acquire()?;
+        let files = state.begin_share();
+        self.adopt_handoff_files(share_options.files.iter().flatten(), &files);
+        let result = platform::perform_service(
+            window,
+            share_options,
+            &options.service,
+            &options.service_options,
+            files.clone(),
+        );
+        files.finish(mode);
+        result.map(|result| result.with_duplicates_removed(duplicates))
+    }
+
+    /// Sends a PDF document or an image to the print service of the platform.
+    ///
+    /// A file sent as data is written to a temporary file registered for cleanup,
+    /// like any other shared file. Fails with [`crate::Error::Unsupported`] on
+    /// platforms without a print service, and with [`crate::Error::InvalidArgs`]
+    /// when the file cannot be printed.
+    pub fn share_print(
+        &self,
+        window: Window<R>,
+        mut file: SharedFile,
+        state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        file.mime_type = crate::validation::print_mime_type(&file, self.mime_overrides())?;
+        crate::validation::check_blocked_mime_type(
+            &file,
+            self.mime_overrides(),
+            &self.0.state::<Config>().blocked_mime_types,
+        )?;
+        let _permit = self.2.acquire()?;
+        let files = state.begin_share();
+        self.adopt_handoff_files([&file], &files);
+        let result = platform::print(window, file, files.clone());
+        files.finish(ShareMode::default());
+        result
+    }
+
+    /// Prints like [`Self::share_print`], without blocking a thread while the print
+    /// panel is shown.
+    pub async fn share_print_async(
+        &self,
+        window: Window<R>,
+        mut file: SharedFile,
+        state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        file.mime_type = crate::validation::print_mime_type(&file, self.mime_overrides())?;
+        crate::validation::check_blocked_mime_type(
+            &file,
+            self.mime_overrides(),
+            &self.0.state::<Config>().blocked_mime_types,
+        )?;
+        let _permit = self.2.acquire_async().await?;
+        let files = state.begin_share();
+        self.adopt_handoff_files([&file], &files);
+        let result = platform::print_async(window, file, files.clone()).await;
+        files.finish(ShareMode::default());
+        result
+    }
+
+    pub async fn share_as_file(
+        &self,
+        window: Window<R>,
+        options: ShareAsFileOptions,
+        state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        self.share_async(window, options.into(), state).await
+    }
+
+    /// Captures the webview as a PNG image and shares it along with `options`.
+    ///
+    /// The screenshot is written to a temporary file registered for cleanup, like
+    /// any other shared file.
+    pub async fn share_screenshot(
+        &self,
+        webview: Webview<R>,
+        mut options: ShareOptions,
+        state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        let png = platform::capture_window(&webview)?;
+        options
+            .files
+            .get_or_insert_with(Vec::new)
+            .push(SharedFile::from_reader(
+                SCREENSHOT_FILE_NAME,
+                "image/png",
+                Cursor::new(png),
+            ));
+        self.share_async(webview.window(), options, state).await
     }
 
     pub fn can_share(&self) -> Result<CanShareResult> {
         platform::can_share()
     }
 
-    pub fn cleanup(&self) -> Result<()> {
-        self.0.state::<PluginTempFileManager>().cleanup_all_managed_files();
-        platform::cleanup()
+    /// Lists the share targets the user recently shared to, as far as the platform
+    /// exposes them.
+    pub fn recent_targets(&self) -> Result<Vec<RecentTarget>> {
+        platform::recent_targets()
+    }
+
+    pub fn cleanup(&self) -> Result<CleanupResult> {
+        let result = self
+            .0
+            .state::<PluginTempFileManager>()
+            .release_all_managed_files();
+        platform::cleanup(&self.0.state::<Config>())?;
+        Ok(result)
+    }
+
+    /// Deletes the temporary files at `paths`, even while shares still use them.
+    ///
+    /// Paths outside the plugin's temp dir, or not managed by the plugin, are
+    /// skipped rather than deleted.
+    pub fn cleanup_paths(&self, paths: &[String]) -> Result<CleanupResult> {
+        let dir = self.temp_dir()?;
+        Ok(self
+            .0
+            .state::<PluginTempFileManager>()
+            .remove_files(paths, &dir))
+    }
+
+    /// Cancels every share in progress and deletes the temporary files of all shares.
+    ///
+    /// The pending shares resolve as cancelled, and the share UI is dismissed where
+    /// the platform lets the app do so. A share UI owned by the OS, such as the
+    /// Windows share flyout of an app window or the macOS picker popover, may stay
+    /// open until the user dismisses it, but no longer holds any file. The plugin is
+    /// then ready for new shares.
+    ///
+    /// Shares still waiting under the `maxConcurrentShares` config fail with
+    /// [`crate::Error::Cancelled`], and shares still writing their files stop. The
+    /// files are deleted once the platform released the share UI.
+    pub fn cancel_all_shares(&self) -> Result<CleanupResult> {
+        let manager = self.0.state::<PluginTempFileManager>();
+        manager.cancel_all();
+        self.2.cancel_queued();
+        #[cfg(any(target_os = "windows", target_os = "macos"))]
+        platform::cancel_all(&self.0);
+        Ok(manager.cleanup_all_managed_files())
+    }
+
+    /// Deletes the temporary files left behind by earlier runs, e.g. after a crash,
+    /// and the files written to the handoff dir but never shared.
+    pub fn cleanup_orphans(&self) -> Result<Vec<PathBuf>> {
+        platform::cleanup_orphans(
+            &self.0.state::<PluginTempFileManager>(),
+            &self.0.state::<Config>(),
+        )
+    }
+
+    pub fn temp_dir(&self) -> Result<PathBuf> {
+        platform::temp_dir(&self.0.state::<Config>())
+    }
+
+    /// The directory where the frontend writes the files it hands off.
+    pub fn handoff_dir(&self) -> Result<PathBuf> {
+        crate::scope::handoff_dir(&self.temp_dir()?)
+    }
+
+    pub fn validate(&self, options: &ShareOptions) -> Result<ValidatedShare> {
+        Ok(crate::validation::validate_share(
+            options,
+            &self.0.state::<Config>(),
+        ))
+    }
+
+    /// Estimates the bytes the temporary files of a share take on disk.
+    ///
+    /// Fails with [`crate::Error::TempFile`] when they do not fit in the free space
+    /// of the temp dir.
+    pub fn estimate_disk_usage(&self, options: &ShareOptions) -> Result<u64> {
+        let required = crate::validation::estimate_disk_usage(options);
+        platform::check_disk_space(required, &self.0.state::<Config>())?;
+        Ok(required)
+    }
+
+    /// Fills `options` with the configured default options, leaves out duplicate
+    /// files, normalizes them and rejects blocked file types, so every share
+    /// reaches the platform with the same resolved options. Also returns the
+    /// number of duplicate files left out.
+    fn normalize(&self, options: ShareOptions) -> Result<(ShareOptions, u32)> {
+        let config = self.0.state::<Config>();
+        let mut options =
+            crate::validation::apply_default_options(options, config.default_options.as_ref());
+        let (duplicates, handoffs) = crate::validation::dedupe_files(&mut options)?;
+        // No share will read the duplicates, so they are released right away.
+        let released = self.0.state::<PluginTempFileManager>().begin_share();
+        self.adopt_handoff_files(&handoffs, &released);
+        released.release();
+        let options =
+            crate::validation::normalize_share_options(options, &config.extension_mime_overrides)?;
+        crate::validation::check_blocked_mime_types(
+            &options,
+            &config.extension_mime_overrides,
+            &config.blocked_mime_types,
+        )?;
+        Ok((options, duplicates))
+    }
+
+    /// The MIME types the app maps file extensions to, from the plugin config.
+    fn mime_overrides(&self) -> &HashMap<String, String> {
+        &self.0.state::<Config>().inner().extension_mime_overrides
+    }
+
+    /// Registers the files handed off by path with the share, so they are deleted
+    /// along with its temporary files.
+    ///
+    /// Only files in the handoff dir that no share manages yet are adopted. Any
+    /// other handed-off file is shared like a path and left in place.
+    fn adopt_handoff_files<'a>(
+        &self,
+        files: impl IntoIterator<Item = &'a SharedFile>,
+        share_files: &ShareFiles,
+    ) {
+        let mut paths = files
+            .into_iter()
+            .filter(|file| file.handoff == Some(true))
+            .filter_map(|file| file.path.as_deref())
+            .peekable();
+        if paths.peek().is_none() {
+            return;
+        }
+        let handoff_dir = match self.handoff_dir() {
+            Ok(dir) => dir,
+            Err(e) => {
+                eprintln!("Failed to resolve the handoff dir: {}", e);
+                return;
+            }
+        };
+        let manager = self.0.state::<PluginTempFileManager>();
+        for path in paths.map(Path::new) {
+            if !crate::scope::is_handoff_file(path, &handoff_dir, &manager) {
+                eprintln!(
+                    "Not deleting '{}' after the share: it is not in the handoff dir",
+                    path.display()
+                );
+                continue;
+            }
+            share_files.add_file(path.to_path_buf());
+        }
     }
 }
 
@@ -35,5 +476,16 @@ pub fn init<R: Runtime, C: serde::de::DeserializeOwned>(
     app: &AppHandle<R>,
     _api: PluginApi<R, C>,
 ) -> crate::Result<Share<R>> {
-    Ok(Share(app.clone()))
+    let limiter = ShareLimiter::new(app.state::<Config>().max_concurrent_shares());
+    Ok(Share(app.clone(), url_providers(app), limiter))
+}
+
+/// Creates the URL provider registry, emitting the progress of uploaders as events.
+fn url_providers<R: Runtime>(app: &AppHandle<R>) -> UrlProviders {
+    let app = app.clone();
+    UrlProviders::with_progress_sink(move |event| {
+        if let Err(e) = app.emit(UPLOAD_PROGRESS_EVENT, event) {
+            eprintln!("Failed to emit upload progress event: {}", e);
+        }
+    })
 }
@@ -11,6 +11,7 @@ pub type Result<T> = std::result::Result<T, Error>;
 /// This enum is serializable, allowing these errors to be sent
 /// from the Rust backend to the JavaScript frontend.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
     #[error("Invalid arguments: {0}")]
     InvalidArgs(String),
@@ -22,6 +23,12 @@ pub enum Error {
     Tauri(#[from] tauri::Error),
     #[error("Failed to receive from channel: {0}")]
     Recv(#[from] RecvError),
+    #[error("Timed out: {0}")]
+    Timeout(String),
+    #[error("Not supported on this platform: {0}")]
+    Unsupported(String),
+    #[error("The share was cancelled before it was shown")]
+    Cancelled,
     #[error("File persistence error: {0}")]
     FilePersist(String),
     #[error("Failed to get window handle: {0}")]
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use crate::{Result, ShareResult};
+
+/// The outcome of the last share of each window, keyed by window label.
+///
+/// Lets the frontend find out what happened to a share it did not await. Managed
+/// as a Tauri state.
+#[derive(Default)]
+pub(crate) struct LastShareResults(Mutex<HashMap<String, ShareResult>>);
+
+impl LastShareResults {
+    /// Forgets the outcome of the previous share of `label`, as a new one starts.
+    pub(crate) fn begin(&self, label: &str) {
+        self.lock().remove(label);
+    }
+
+    /// Records the outcome of the share of `label`. Failed shares record nothing.
+    pub(crate) fn finish(&self, label: &str, result: &Result<ShareResult>) {
+        if let Ok(result) = result {
+            self.lock().insert(label.to_string(), result.clone());
+        }
+    }
+
+    /// Returns the outcome of the last finished share of `label`.
+    pub(crate) fn get(&self, label: &str) -> Option<ShareResult> {
+        self.lock().get(label).cloned()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, ShareResult>> {
+        match self.0.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn new_share_clears_the_last_result() {
+        let results = LastShareResults::default();
+        assert_eq!(results.get("main"), None);
+
+        results.begin("main");
+        results.finish("main", &Ok(ShareResult::Cancelled));
+        assert_eq!(results.get("main"), Some(ShareResult::Cancelled));
+        assert_eq!(results.get("other"), None);
+
+        results.begin("main");
+        assert_eq!(results.get("main"), None);
+        results.finish("main", &Err(Error::Cancelled));
+        assert_eq!(results.get("main"), None);
+    }
+}
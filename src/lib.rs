@@ -19,7 +19,7 @@
 //!
 //! You need to initialize the plugin in your `main.rs` or `lib.rs` to register the commands and set up state management.
 //!
-//! ```rust
+//! ```rust,ignore
 //! // src/main.rs
 //! fn main() {
 //!     tauri::Builder::default()
@@ -61,7 +61,7 @@
 
 use tauri::{
     plugin::{Builder, TauriPlugin},
-    Manager, Runtime,
+    AppHandle, Emitter, Manager, Runtime,
 };
 
 pub use models::*;
@@ -71,13 +71,32 @@ mod desktop;
 #[cfg(mobile)]
 mod mobile;
 
+#[cfg(all(feature = "bench", desktop))]
+#[doc(hidden)]
+pub mod bench;
+#[cfg(any(mobile, test))]
+mod bridge;
+mod clipboard;
+mod coalesce;
 mod commands;
+mod config;
 mod error;
+mod last_result;
+#[cfg(desktop)]
+mod limit;
 mod models;
 mod platform;
+mod received;
+mod resume;
+mod scope;
 mod state;
+mod url_provider;
+mod validation;
 
+pub use config::Config;
 pub use error::{Error, Result};
+pub use state::{ShareCancelToken, ShareSession};
+pub use url_provider::UploadProgress;
 
 #[cfg(desktop)]
 use desktop::Share;
@@ -99,26 +118,184 @@ impl<R: Runtime, T: Manager<R>> crate::ShareExt<R> for T {
 ///
 /// This function sets up the plugin, registers its commands, and configures the
 /// state management for temporary files. The cleanup of these files is
-/// automatically handled when the application exits.
-pub fn init<R: Runtime>() -> TauriPlugin<R> {
-    Builder::new("vnidrop-share")
+/// automatically handled when the application exits, unless the `cleanupOnExit`
+/// config is `false`.
+pub fn init<R: Runtime>() -> TauriPlugin<R, Option<Config>> {
+    Builder::<R, Option<Config>>::new("vnidrop-share")
         .invoke_handler(tauri::generate_handler![
             commands::share,
+            commands::share_nowait,
+            commands::share_batch,
+            commands::share_to,
+            commands::share_as_file,
+            commands::share_raw_data,
+            commands::share_screenshot,
+            commands::share_clipboard,
+            commands::share_print,
             commands::can_share,
+            commands::recent_targets,
             commands::cleanup,
+            commands::cleanup_orphans,
+            commands::cancel_all_shares,
+            commands::temp_dir,
+            commands::handoff_dir,
+            commands::validate,
+            commands::estimate_disk_usage,
+            commands::take_received_shares,
+            commands::last_share_result,
+            commands::resume_last_share,
+            commands::discard_last_share,
         ])
         .setup(|app, api| {
+            let config = api.config().clone().unwrap_or_default().normalized()?;
+            app.manage(config);
+            // Shares may be received while the mobile plugin is registered.
+            app.manage(received::ReceivedShares::default());
             #[cfg(mobile)]
             let share = mobile::init(app, api)?;
             #[cfg(desktop)]
             let share = desktop::init(app, api)?;
             app.manage(share);
-            app.manage(state::PluginTempFileManager::new());
+            app.manage(temp_file_manager(app));
+            app.manage(coalesce::ShareCoalescer::default());
+            app.manage(last_result::LastShareResults::default());
+            app.manage(resume::LastShareOptions::default());
             Ok(())
         })
+        .on_event(|app, event| {
+            #[cfg(target_os = "ios")]
+            if let tauri::RunEvent::Opened { urls } = event {
+                mobile::receive_opened_urls(app, urls);
+            }
+            if let tauri::RunEvent::WindowEvent {
+                label,
+                event: tauri::WindowEvent::Destroyed,
+                ..
+            } = event
+            {
+                // A window closed while its share UI is open leaves the share behind.
+                #[cfg(any(target_os = "windows", target_os = "macos"))]
+                platform::window_destroyed(app, label);
+                // Its last share can no longer be resumed.
+                let manager = app.state::<state::PluginTempFileManager>();
+                app.state::<resume::LastShareOptions>()
+                    .discard(label, &manager);
+            }
+        })
         .on_drop(|app| {
-            app.state::<state::PluginTempFileManager>()
-                .cleanup_all_managed_files();
+            if app.state::<Config>().cleanup_on_exit() {
+                app.state::<state::PluginTempFileManager>()
+                    .cleanup_all_managed_files();
+                #[cfg(desktop)]
+                let _ = app.share().cleanup();
+            }
+            #[cfg(target_os = "windows")]
+            platform::teardown(&app);
         })
         .build()
 }
+
+/// Creates the temp file manager from the config, emitting the files deleted to
+/// stay under the high-water mark as events.
+fn temp_file_manager<R: Runtime>(app: &AppHandle<R>) -> state::PluginTempFileManager {
+    let config = app.state::<Config>();
+    let manager =
+        state::PluginTempFileManager::new().with_retain_after_share(config.retain_after_share());
+    let Some(bytes) = config.temp_high_water_mark_bytes else {
+        return manager;
+    };
+    let app = app.clone();
+    manager.with_high_water_mark(bytes, move |files| {
+        let files = files
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        if let Err(e) = app.emit(CLEANED_EVENT, CleanedEvent { files }) {
+            eprintln!("Failed to emit cleaned event: {}", e);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    /// Returns the names passed to `generate_handler!` in [`init`](super::init).
+    fn registered_commands() -> Vec<&'static str> {
+        let source = include_str!("lib.rs");
+        let start = source.find("generate_handler![").unwrap();
+        let end = start + source[start..].find(']').unwrap();
+        source[start..end]
+            .split("commands::")
+            .skip(1)
+            .map(|command| command.split(',').next().unwrap().trim())
+            .collect()
+    }
+
+    /// Returns the entries of the `COMMANDS` list in `build.rs`.
+    fn build_commands() -> Vec<&'static str> {
+        let source = include_str!("../build.rs");
+        let start = source.find("const COMMANDS").unwrap();
+        let end = start + source[start..].find("];").unwrap();
+        source[start..end].split('"').skip(1).step_by(2).collect()
+    }
+
+    /// Returns the native method names invoked through `run_mobile_plugin` in
+    /// `mobile.rs` and through the bridge in `bridge.rs`.
+    fn mobile_methods() -> Vec<&'static str> {
+        [
+            (include_str!("mobile.rs"), "run_mobile_plugin"),
+            (include_str!("bridge.rs"), "bridge.run"),
+        ]
+        .into_iter()
+        .flat_map(|(source, call)| source.split(call).skip(1))
+        .filter_map(|call| call.split_once("(\""))
+        .map(|(_, rest)| rest.split('"').next().unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn build_commands_match_registered_commands() {
+        let mut registered = registered_commands();
+        let mut declared = build_commands();
+        registered.sort_unstable();
+        declared.sort_unstable();
+        assert!(!registered.is_empty());
+        assert_eq!(registered, declared);
+    }
+
+    #[test]
+    fn default_permissions_allow_every_command() {
+        let default = include_str!("../permissions/default.toml");
+        for command in build_commands() {
+            let permission = format!("\"allow-{}\"", command.replace('_', "-"));
+            assert!(
+                default.contains(&permission),
+                "default permissions are missing {}",
+                permission
+            );
+        }
+    }
+
+    #[test]
+    fn mobile_methods_exist_on_both_platforms() {
+        let android = include_str!("../android/src/main/java/SharePlugin.kt");
+        let ios = include_str!("../ios/Sources/SharePlugin.swift");
+        let methods = mobile_methods();
+        assert!(!methods.is_empty());
+        for method in methods {
+            // Inherited from the base class of every Tauri mobile plugin.
+            if method == "registerListener" {
+                continue;
+            }
+            assert!(
+                android.contains(&format!("fun {}(invoke: Invoke)", method)),
+                "Android plugin is missing `{}`",
+                method
+            );
+            assert!(
+                ios.contains(&format!("func {}(_ invoke: Invoke)", method)),
+                "iOS plugin is missing `{}`",
+                method
+            );
+        }
+    }
+}
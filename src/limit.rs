@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore};
+
+use crate::{Error, Result};
+
+/// Bounds how many shares are in flight at once, across all windows.
+///
+/// A share holds a [`SharePermit`] from before its temporary files are written
+/// until it resolves, so further shares queue until one finishes.
+pub(crate) struct ShareLimiter {
+    permits: Option<Arc<Semaphore>>,
+    /// Bumped by [`Self::cancel_queued`], failing the shares queued at that point.
+    cancelled: watch::Sender<u64>,
+}
+
+impl ShareLimiter {
+    /// Creates a limiter letting `limit` shares run at once, or any number with `None`.
+    pub(crate) fn new(limit: Option<usize>) -> Self {
+        Self {
+            permits: limit.map(|limit| Arc::new(Semaphore::new(limit.min(Semaphore::MAX_PERMITS)))),
+            cancelled: watch::Sender::new(0),
+        }
+    }
+
+    /// Blocks until a share may start.
+    ///
+    /// Only for the blocking share APIs: async code uses [`Self::acquire_async`],
+    /// which leaves the thread free while the share is queued.
+    pub(crate) fn acquire(&self) -> Result<SharePermit> {
+        crate::platform::block_on(self.acquire_async())?
+    }
+
+    /// Waits until a share may start, without blocking the async runtime.
+    ///
+    /// Fails with [`Error::Cancelled`] when [`Self::cancel_queued`] is called while
+    /// the share waits.
+    pub(crate) async fn acquire_async(&self) -> Result<SharePermit> {
+        let Some(permits) = &self.permits else {
+            return Ok(SharePermit { _permit: None });
+        };
+        let mut cancelled = self.cancelled.subscribe();
+        let permit = tokio::select! {
+            permit = permits.clone().acquire_owned() => permit.map_err(|_| Error::Cancelled)?,
+            _ = cancelled.changed() => return Err(Error::Cancelled),
+        };
+        Ok(SharePermit {
+            _permit: Some(permit),
+        })
+    }
+
+    /// Fails every share waiting for a permit, e.g. when all shares are cancelled.
+    /// Shares queued later wait as usual.
+    pub(crate) fn cancel_queued(&self) {
+        self.cancelled.send_modify(|generation| *generation += 1);
+    }
+}
+
+/// Lets a share run. Dropping it lets the next queued share start.
+pub(crate) struct SharePermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn excess_share_waits_for_a_permit() {
+        let limiter = Arc::new(ShareLimiter::new(Some(1)));
+        let permit = limiter.acquire().unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let queued = std::thread::spawn({
+            let limiter = limiter.clone();
+            move || {
+                let _permit = limiter.acquire().unwrap();
+                tx.send(()).unwrap();
+            }
+        });
+        assert!(rx.recv_timeout(Duration::from_millis(50)).is_err());
+
+        drop(permit);
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        queued.join().unwrap();
+    }
+
+    #[test]
+    fn queued_async_share_leaves_the_runtime_free() {
+        let limiter = ShareLimiter::new(Some(1));
+        tauri::async_runtime::block_on(async {
+            let permit = limiter.acquire_async().await.unwrap();
+            let queued =
+                tokio::time::timeout(Duration::from_millis(50), limiter.acquire_async()).await;
+            assert!(queued.is_err());
+
+            drop(permit);
+            tokio::time::timeout(Duration::from_secs(5), limiter.acquire_async())
+                .await
+                .unwrap()
+                .unwrap();
+        });
+    }
+
+    #[test]
+    fn cancelling_fails_only_the_queued_shares() {
+        let limiter = ShareLimiter::new(Some(1));
+        tauri::async_runtime::block_on(async {
+            let permit = limiter.acquire_async().await.unwrap();
+            let (queued, _) = tokio::join!(limiter.acquire_async(), async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                limiter.cancel_queued();
+            });
+            assert!(matches!(queued, Err(Error::Cancelled)));
+
+            drop(permit);
+            tokio::time::timeout(Duration::from_secs(5), limiter.acquire_async())
+                .await
+                .unwrap()
+                .unwrap();
+        });
+    }
+
+    #[test]
+    fn unlimited_shares_never_wait() {
+        let limiter = ShareLimiter::new(None);
+        let permits: Vec<_> = (0..10).map(|_| limiter.acquire().unwrap()).collect();
+        assert!(permits.iter().all(|permit| permit._permit.is_none()));
+    }
+}
@@ -1,11 +1,18 @@
-use crate::state::PluginTempFileManager;
+use crate::bridge;
+use crate::state::{PluginTempFileManager, ShareCancelToken, ShareSession};
+use crate::url_provider::{UploadProgress, UrlProviders};
 use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+#[cfg(target_os = "android")]
+use tauri::ipc::Channel;
 use tauri::{
     plugin::{PluginApi, PluginHandle},
-    AppHandle, Runtime, State, Window,
+    AppHandle, Emitter, Manager, Runtime, State, Webview, Window,
 };
 
-use crate::{models::*, Result};
+use crate::{models::*, Config, Result};
 
 #[cfg(target_os = "android")]
 const PLUGIN_IDENTIFIER: &str = "plugin.vnidrop.share";
@@ -26,32 +33,364 @@ pub fn init<R: Runtime, C: DeserializeOwned>(
         .unwrap();
     #[cfg(target_os = "ios")]
     let handle = api.register_ios_plugin(init_plugin_share)?;
-    Ok(Share(handle))
+    #[cfg(target_os = "android")]
+    forward_received_shares(_app, &handle)?;
+    let config = _app.state::<Config>().inner().clone();
+    let app = _app.clone();
+    let url_providers = UrlProviders::with_progress_sink(move |event| {
+        if let Err(e) = app.emit(UPLOAD_PROGRESS_EVENT, event) {
+            eprintln!("Failed to emit upload progress event: {}", e);
+        }
+    });
+    Ok(Share(handle, url_providers, config))
+}
+
+/// The native event fired when the app is launched or resumed by a share intent.
+#[cfg(target_os = "android")]
+const NATIVE_SHARE_RECEIVED_EVENT: &str = "shareReceived";
+
+/// The arguments of the native `registerListener` command.
+#[cfg(target_os = "android")]
+#[derive(serde::Serialize)]
+struct RegisterListener {
+    event: &'static str,
+    handler: Channel,
+}
+
+/// Forwards the shares the Android plugin receives as [`SHARE_RECEIVED_EVENT`].
+///
+/// The native side holds the share the app was launched with until this listener
+/// is registered, so it is not lost.
+#[cfg(target_os = "android")]
+fn forward_received_shares<R: Runtime>(
+    app: &AppHandle<R>,
+    handle: &PluginHandle<R>,
+) -> crate::Result<()> {
+    let app = app.clone();
+    let handler = Channel::new(move |body| {
+        match body.deserialize::<ReceivedShare>() {
+            Ok(share) => crate::received::deliver(&app, share),
+            Err(e) => eprintln!("Failed to read the received share: {}", e),
+        }
+        Ok(())
+    });
+    handle.run_mobile_plugin::<()>(
+        "registerListener",
+        RegisterListener {
+            event: NATIVE_SHARE_RECEIVED_EVENT,
+            handler,
+        },
+    )?;
+    Ok(())
+}
+
+/// Forwards the files another app opened in this app as [`SHARE_RECEIVED_EVENT`].
+///
+/// iOS hands documents shared with "Open in" or "Copy to" to the app as file URLs.
+/// Other URLs, such as deep links, are ignored.
+#[cfg(target_os = "ios")]
+pub(crate) fn receive_opened_urls<R: Runtime>(app: &AppHandle<R>, urls: &[tauri::Url]) {
+    let files: Vec<ReceivedFile> = urls
+        .iter()
+        .filter_map(|url| url.to_file_path().ok())
+        .map(|path| ReceivedFile {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            path: path.to_string_lossy().into_owned(),
+            mime_type: None,
+        })
+        .collect();
+    if files.is_empty() {
+        return;
+    }
+    let share = ReceivedShare {
+        files,
+        ..Default::default()
+    };
+    crate::received::deliver(app, share);
 }
 
 /// A handle to the `tauri-plugin-share` APIs for mobile.
 ///
 /// This struct provides the public interface for the plugin's commands on mobile,
 /// which are invoked through the mobile bridge.
-pub struct Share<R: Runtime>(PluginHandle<R>);
+pub struct Share<R: Runtime>(PluginHandle<R>, UrlProviders, Config);
 
 impl<R: Runtime> Share<R> {
+    /// Registers an async function that mints the URL of shares referencing `id`
+    /// through their `url_provider_id`, e.g. by uploading content and returning a
+    /// time-limited link.
+    ///
+    /// The provider is awaited on the async runtime by the `share` and `share_to`
+    /// commands, before any temporary file is written or native UI is shown, so a
+    /// slow provider delays the share dialog. If it fails, the share fails with its
+    /// error. Rust callers of [`Self::share`] must call [`Self::resolve_url`] first.
+    pub fn register_url_provider<F, Fut>(&self, id: impl Into<String>, provider: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.1.register(id.into(), provider);
+    }
+
+    /// Registers an async function that uploads content and returns the URL to
+    /// share, reporting its progress as the `vnidrop-share:upload-progress` event,
+    /// see [`Self::register_url_provider`].
+    pub fn register_uploader<F, Fut>(&self, id: impl Into<String>, uploader: F)
+    where
+        F: Fn(UploadProgress) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.1.register_uploader(id.into(), uploader);
+    }
+
+    /// Sets an async function that rewrites the URL of every share before it is
+    /// shared, e.g. to shorten it or add tracking parameters. It replaces the
+    /// previous transformer, and without one URLs are shared as is.
+    ///
+    /// The transformer runs on the async runtime right after any URL provider,
+    /// before any temporary file is written or native UI is shown, so a slow
+    /// transformer delays the share dialog, for at most 10 seconds. If it fails or
+    /// takes longer, the error is logged and the original URL is shared instead of
+    /// failing the share. Rust callers of
+    /// [`Self::share`] must call [`Self::resolve_url`] first.
+    pub fn set_url_transformer<F, Fut>(&self, transformer: F)
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        self.1.set_transformer(transformer);
+    }
+
+    /// Resolves the `url_provider_id` of `options` into its `url`, and passes the
+    /// URL through the URL transformer.
+    pub async fn resolve_url(&self, options: ShareOptions) -> Result<ShareOptions> {
+        self.1.resolve(options).await
+    }
+
     pub fn share(
         &self,
         _window: Window<R>,
         payload: ShareOptions,
         _state: State<'_, PluginTempFileManager>,
-    ) -> Result<()> {
-        self.0
-            .run_mobile_plugin("share", payload)
-            .map_err(Into::into)
+    ) -> Result<ShareResult> {
+        self.run_share(payload)
+    }
+
+    /// Shares content like [`Self::share`], for symmetry with desktop.
+    ///
+    /// The native side resolves the share itself, so this resolves like the
+    /// blocking variant.
+    pub async fn share_async(
+        &self,
+        window: Window<R>,
+        payload: ShareOptions,
+        state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        self.share(window, payload, state)
+    }
+
+    /// Shares content unless `cancel` is cancelled while the share is prepared.
+    ///
+    /// The token is checked before and after the streamed files are read; the
+    /// native side writes its temporary files only once the share starts.
+    pub fn share_cancellable(
+        &self,
+        _window: Window<R>,
+        payload: ShareOptions,
+        cancel: &ShareCancelToken,
+    ) -> Result<ShareResult> {
+        bridge::share(
+            &self.0,
+            self.with_defaults(payload)?,
+            self.mime_overrides(),
+            Some(cancel),
+        )
+    }
+
+    /// Shares content and returns a session for symmetry with desktop.
+    ///
+    /// Temporary files are cleaned up by the native side, so the session holds no files.
+    pub fn begin_share(&self, _window: Window<R>, options: ShareOptions) -> Result<ShareSession> {
+        self.run_share(options).map(ShareSession::detached)
+    }
+
+    fn run_share(&self, payload: ShareOptions) -> Result<ShareResult> {
+        bridge::share(
+            &self.0,
+            self.with_defaults(payload)?,
+            self.mime_overrides(),
+            None,
+        )
+    }
+
+    /// Fills `payload` with the configured default options and rejects blocked
+    /// file types, like on desktop.
+    fn with_defaults(&self, payload: ShareOptions) -> Result<ShareOptions> {
+        let payload =
+            crate::validation::apply_default_options(payload, self.2.default_options.as_ref());
+        crate::validation::check_blocked_mime_types(
+            &payload,
+            self.mime_overrides(),
+            &self.2.blocked_mime_types,
+        )?;
+        Ok(payload)
+    }
+
+    /// The MIME types the app maps file extensions to, from the plugin config.
+    fn mime_overrides(&self) -> &HashMap<String, String> {
+        &self.2.extension_mime_overrides
+    }
+
+    /// Shares content to a specific service. Targeted sharing is not supported on
+    /// mobile, so this shows the regular share sheet.
+    pub fn share_to(
+        &self,
+        _window: Window<R>,
+        options: ShareToOptions,
+        _state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        self.run_share(options.options)
+    }
+
+    /// Shares content like [`Self::share_to`], for symmetry with desktop.
+    pub async fn share_to_async(
+        &self,
+        window: Window<R>,
+        options: ShareToOptions,
+        state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        self.share_to(window, options, state)
+    }
+
+    /// Sharing services are a macOS concept, so this is not supported on mobile.
+    pub fn perform_service(
+        &self,
+        _window: Window<R>,
+        _options: ShareToOptions,
+        _state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        Err(crate::Error::Unsupported(
+            "performing a sharing service is not supported on mobile".to_string(),
+        ))
+    }
+
+    /// Sends a PDF document or an image to the iOS print service.
+    ///
+    /// Android has no print target in its share sheet, so this fails with
+    /// [`crate::Error::Unsupported`] there.
+    pub fn share_print(
+        &self,
+        _window: Window<R>,
+        file: SharedFile,
+        _state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        #[cfg(target_os = "android")]
+        {
+            let _ = file;
+            Err(crate::Error::Unsupported(
+                "printing is not supported on Android".to_string(),
+            ))
+        }
+        #[cfg(not(target_os = "android"))]
+        {
+            crate::validation::check_blocked_mime_type(
+                &file,
+                self.mime_overrides(),
+                &self.2.blocked_mime_types,
+            )?;
+            bridge::print(&self.0, file, self.mime_overrides())
+        }
+    }
+
+    /// Prints like [`Self::share_print`], for symmetry with desktop.
+    pub async fn share_print_async(
+        &self,
+        window: Window<R>,
+        file: SharedFile,
+        state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        self.share_print(window, file, state)
+    }
+
+    pub async fn share_as_file(
+        &self,
+        window: Window<R>,
+        options: ShareAsFileOptions,
+        state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        self.share_async(window, options.into(), state).await
+    }
+
+    /// Capturing the webview is not supported on mobile.
+    pub async fn share_screenshot(
+        &self,
+        _webview: Webview<R>,
+        _options: ShareOptions,
+        _state: State<'_, PluginTempFileManager>,
+    ) -> Result<ShareResult> {
+        Err(crate::Error::Unsupported(
+            "capturing the window is not supported on mobile".to_string(),
+        ))
     }
 
     pub fn can_share(&self) -> Result<CanShareResult> {
-        self.0.run_mobile_plugin("canShare", ()).map_err(Into::into)
+        bridge::can_share(&self.0)
+    }
+
+    /// Neither the Android chooser nor the iOS share sheet lets apps read the
+    /// targets they rank first, so no recent targets are reported on mobile.
+    pub fn recent_targets(&self) -> Result<Vec<RecentTarget>> {
+        Ok(Vec::new())
+    }
+
+    pub fn cleanup(&self) -> Result<CleanupResult> {
+        bridge::cleanup(&self.0)
+    }
+
+    /// The native side manages the files of its share directory, so no path is a
+    /// temporary file of the plugin on mobile and every path is skipped.
+    pub fn cleanup_paths(&self, paths: &[String]) -> Result<CleanupResult> {
+        Ok(CleanupResult {
+            skipped: paths.to_vec(),
+            ..Default::default()
+        })
+    }
+
+    /// Deletes the files of the native share directory.
+    ///
+    /// The share sheets are owned by the OS and cannot be dismissed by the app, so
+    /// a sheet already shown stays open and resolves its share when the user closes it.
+    pub fn cancel_all_shares(&self) -> Result<CleanupResult> {
+        bridge::cleanup(&self.0)
+    }
+
+    /// The native side cleans up its own share directory, so there are no orphans
+    /// to remove on mobile.
+    pub fn cleanup_orphans(&self) -> Result<Vec<PathBuf>> {
+        Ok(Vec::new())
+    }
+
+    pub fn temp_dir(&self) -> Result<PathBuf> {
+        bridge::temp_dir(&self.0)
+    }
+
+    /// The directory where the frontend writes the files it hands off.
+    pub fn handoff_dir(&self) -> Result<PathBuf> {
+        crate::scope::handoff_dir(&self.temp_dir()?)
+    }
+
+    pub fn validate(&self, options: &ShareOptions) -> Result<ValidatedShare> {
+        Ok(crate::validation::validate_share(options, &self.2))
     }
 
-    pub fn cleanup(&self) -> Result<()> {
-        self.0.run_mobile_plugin("cleanup", ()).map_err(Into::into)
+    /// Estimates the bytes the temporary files of a share take on disk.
+    ///
+    /// The native side writes the files to the app cache, whose free space is not
+    /// checked.
+    pub fn estimate_disk_usage(&self, options: &ShareOptions) -> Result<u64> {
+        Ok(crate::validation::estimate_disk_usage(options))
     }
 }
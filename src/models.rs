@@ -1,16 +1,205 @@
+use base64::{engine::general_purpose, Engine as _};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::validation::{infer_mime_type, DEFAULT_MIME_TYPE};
+use crate::Error;
 
 /// Represents a file to be shared, including its content, name, and MIME type.
 ///
 /// The `data` field holds the Base64 encoded content of the file. This approach
 /// allows files to be easily passed from the frontend to the Rust backend
 /// without needing to manage local file paths directly.
+///
+/// Rust callers can instead provide the content as a stream with
+/// [`SharedFile::from_reader`], which avoids holding the whole file in memory.
+///
+/// An existing file can also be shared by `path`. Paths coming from the frontend
+/// must be allowed by the file system scope, see [`crate::Config`].
+///
+/// Rust callers create it with [`SharedFile::new`], [`SharedFile::from_reader`] or
+/// [`SharedFile::from_path`], and set the optional fields afterwards.
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "ts-bindings",
+    derive(ts_rs::TS),
+    ts(export, export_to = "../guest-js/bindings/", optional_fields)
+)]
+#[non_exhaustive]
 pub struct SharedFile {
+    #[serde(default)]
     pub data: String,
     pub name: String,
+    #[serde(default)]
     pub mime_type: String,
+    /// The absolute path of an existing file or directory to share instead of `data`.
+    ///
+    /// On desktop the file is shared in place and never deleted by the plugin.
+    /// On mobile it is read into memory first, like a streamed file, unless it is
+    /// handed off: the native side then moves it into its share directory.
+    ///
+    /// Directories are shared as folder items, without zipping them, on macOS and
+    /// Windows only. Whether a receiver accepts them is up to the receiver: AirDrop
+    /// and Nearby Share take folders, while most mail and messaging targets only
+    /// take files. Sharing a directory on mobile fails.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Hands the file at `path` off to the plugin, which deletes it along with the
+    /// temporary files of the share.
+    ///
+    /// This lets the frontend write a large file itself, e.g. with `tauri-plugin-fs`
+    /// into the directory returned by `temp_dir`, and share it without sending its
+    /// content over IPC or copying it. A handed-off file must be in the plugin temp
+    /// dir or allowed by the file system scope, even with `allowUnscopedPaths`, and
+    /// cannot be a directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub handoff: Option<bool>,
+    /// The Uniform Type Identifier of the file on Apple platforms, e.g.
+    /// `com.example.project` for a proprietary document type.
+    ///
+    /// When absent, receivers infer the type from the file extension, or from
+    /// `mime_type` for files without one. Ignored on other platforms.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uti: Option<String>,
+    /// The width in pixels of an image file, so receivers can lay out its preview
+    /// before loading it.
+    ///
+    /// When `width` or `height` is absent, the dimensions are read from the header
+    /// of the image with the `image-dimensions` cargo feature, without decoding the
+    /// whole image. macOS passes them as the preferred presentation size of the
+    /// item. Windows ignores them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    /// The height in pixels of an image file, see `width`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    /// The name receivers show for the file, e.g. `Invoice.pdf`, when it differs
+    /// from `name`.
+    ///
+    /// macOS passes it as the suggested name of the shared item, leaving the name
+    /// of the temporary file as is. Windows and mobile show the name of the file
+    /// itself, so the temporary file is written under the display name instead.
+    /// Windows shares files given by `path` in place, under their own name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// A stream to read the content from instead of `data`. Only available from Rust.
+    #[serde(skip)]
+    pub reader: Option<SharedFileReader>,
+}
+
+impl SharedFile {
+    /// Creates a file with the Base64 encoded content `data`.
+    pub fn new(
+        name: impl Into<String>,
+        mime_type: impl Into<String>,
+        data: impl Into<String>,
+    ) -> Self {
+        Self {
+            data: data.into(),
+            name: name.into(),
+            mime_type: mime_type.into(),
+            path: None,
+            handoff: None,
+            uti: None,
+            width: None,
+            height: None,
+            display_name: None,
+            reader: None,
+        }
+    }
+
+    /// Creates a file whose content is streamed from `reader` when the share is performed.
+    ///
+    /// On desktop the reader is copied straight into the temporary file with
+    /// [`std::io::copy`]. On mobile the content has to cross the native bridge as
+    /// Base64, so it is read into memory first.
+    ///
+    /// The reader must be `Send + 'static` because it is consumed on the thread that
+    /// prepares the native share (the main thread on macOS, a background thread on
+    /// Windows), possibly after the call that created it has returned. It is read at
+    /// most once; clones of this `SharedFile` share the same reader.
+    pub fn from_reader(
+        name: impl Into<String>,
+        mime_type: impl Into<String>,
+        reader: impl Read + Send + 'static,
+    ) -> Self {
+        Self {
+            reader: Some(SharedFileReader::new(reader)),
+            ..Self::new(name, mime_type, String::new())
+        }
+    }
+
+    /// Creates a file that shares the existing file or directory at `path` instead of a copy.
+    ///
+    /// The file name is taken from the path.
+    pub fn from_path(path: impl AsRef<std::path::Path>, mime_type: impl Into<String>) -> Self {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Self {
+            path: Some(path.to_string_lossy().into_owned()),
+            ..Self::new(name, mime_type, String::new())
+        }
+    }
+}
+
+/// Shares the existing file at `path` in place, like [`SharedFile::from_path`].
+///
+/// The name is taken from the path and the MIME type is inferred from its
+/// extension, falling back to `application/octet-stream`. Fails with
+/// [`Error::InvalidArgs`] when the path has no file name, and with [`Error::Io`]
+/// when the file cannot be opened for reading.
+impl TryFrom<&Path> for SharedFile {
+    type Error = Error;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        let name = path.file_name().ok_or_else(|| {
+            Error::InvalidArgs(format!("Path '{}' has no file name", path.display()))
+        })?;
+        let mime_type = infer_mime_type(&name.to_string_lossy()).unwrap_or(DEFAULT_MIME_TYPE);
+        std::fs::File::open(path)?;
+        Ok(Self::from_path(path, mime_type))
+    }
+}
+
+/// Shares the existing file at `path` in place, see [`SharedFile::try_from`].
+impl TryFrom<PathBuf> for SharedFile {
+    type Error = Error;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        Self::try_from(path.as_path())
+    }
+}
+
+/// A one-shot, shareable handle to the stream backing a [`SharedFile`].
+#[derive(Clone)]
+pub struct SharedFileReader(Arc<Mutex<Option<Box<dyn Read + Send>>>>);
+
+impl SharedFileReader {
+    pub fn new(reader: impl Read + Send + 'static) -> Self {
+        Self(Arc::new(Mutex::new(Some(Box::new(reader)))))
+    }
+
+    /// Takes the underlying reader, leaving `None` for any later caller.
+    pub(crate) fn take(&self) -> Option<Box<dyn Read + Send>> {
+        match self.0.lock() {
+            Ok(mut reader) => reader.take(),
+            Err(poisoned) => poisoned.into_inner().take(),
+        }
+    }
+}
+
+impl fmt::Debug for SharedFileReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedFileReader { .. }")
+    }
 }
 
 /// Defines the content and options for a native sharing dialog.
@@ -43,17 +232,405 @@ pub struct SharedFile {
 ///   ]
 /// }
 /// ```
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "ts-bindings",
+    derive(ts_rs::TS),
+    ts(export, export_to = "../guest-js/bindings/", optional_fields)
+)]
 pub struct ShareOptions {
     /// Optional text content to include in the share dialog.
     pub text: Option<String>,
     /// Optional title for the share dialog. (This is mainly used on Android)
+    ///
+    /// `{fileCount}` is replaced with the number of shared files and `{fileName}`
+    /// with the name of the first one. On Windows, a share without a title is
+    /// titled after the name or count of its files, or the host of its URL.
     pub title: Option<String>,
     /// Optional URL to include in the share dialog.
     pub url: Option<String>,
     /// A list of files to share, each represented by a `SharedFile` struct.
     pub files: Option<Vec<SharedFile>>,
+    /// Overrides the name of the app the content is shared from. Defaults to the
+    /// product name from the Tauri config. (This is only used on Windows)
+    pub application_name: Option<String>,
+    /// Overrides the link back to the app the content is shared from. Defaults to
+    /// the `applicationLink` plugin config. (This is only used on Windows)
+    pub application_link: Option<String>,
+    /// Whether the share resolves only once the share sheet is dismissed. Defaults
+    /// to `true`. When `false`, the share resolves as soon as the sheet is shown,
+    /// with a `Completed` result without target. (This is only used on mobile)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_for_dismissal: Option<bool>,
+    /// The id of a URL provider registered with `Share::register_url_provider`.
+    /// When set, `url` is replaced by the link the provider mints right before
+    /// the share is performed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_provider_id: Option<String>,
+    /// Whether the Base64 `data` of each file is wiped from memory as soon as its
+    /// temporary file is written, instead of when the share ends. Defaults to
+    /// `false`. Copies held by the webview or by the IPC layer are out of the
+    /// plugin's control. (This is only used on Windows and macOS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub zeroize_data: Option<bool>,
+    /// Shares `text` and `url` as plain text only, so receivers do not turn text
+    /// that looks like a URL into a link. Defaults to `false`. (This is only used
+    /// on Windows and macOS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_is_plain: Option<bool>,
+    /// What to do instead when native sharing is unavailable, e.g. on Linux.
+    /// Defaults to nothing, in which case the share is reported as cancelled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<ShareFallback>,
+    /// The side of its anchor the share picker appears on. Defaults to `minY`.
+    /// (This is only used on macOS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_edge: Option<PreferredEdge>,
+    /// How the share picker is presented. Defaults to `popover`.
+    /// (This is only used on macOS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presentation: Option<SharePresentation>,
+    /// What happens when several files share the same name. Defaults to `suffix`.
+    /// (This is only used on Windows and macOS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_collision: Option<NameCollision>,
+    /// The separator placed between the text and the URL when both are shared as
+    /// one text, e.g. `" "` or `""`. Defaults to a newline. (This is only used on
+    /// Windows and macOS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_url_separator: Option<String>,
+    /// Whether the URL comes before the text when both are shared as one text.
+    /// Defaults to `false`. (This is only used on Windows and macOS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url_first: Option<bool>,
+    /// Further texts shared after `text` and `url`, e.g. the title and the body of
+    /// a note. On macOS each one is a separate item, which receivers can handle
+    /// apart. Windows has a single text slot, so there they are appended to the
+    /// shared text, joined by `text_items_separator`. (This is only used on
+    /// Windows and macOS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_items: Option<Vec<String>>,
+    /// The separator placed between the text and each of the `text_items` when
+    /// they are joined into one text. Defaults to a blank line. (This is only used
+    /// on Windows)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_items_separator: Option<String>,
+    /// An image previewing the shared link, e.g. for a link card in the receiver.
+    /// It is not shared as an attachment, and must be given as `data` or a reader
+    /// rather than by path. (This is only used on Windows and macOS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preview_image: Option<SharedFile>,
+    /// How long the temporary files of the share are kept once it completed.
+    /// Defaults to `copy`. (This is only used on Windows and macOS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub share_mode: Option<ShareMode>,
+    /// A URL opened once the share sheet is dismissed, e.g. a deep link of the app
+    /// itself to resume a workflow. The app must handle its scheme. On Android, it
+    /// is only opened when `wait_for_dismissal` is not `false`. (This is only used
+    /// on Android and iOS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_url: Option<String>,
+    /// A hint, as a Unix timestamp in seconds, of when the shared content stops
+    /// being valid, for receivers that honor ephemeral content. It is attached as
+    /// the `ExpiresAt` custom property of the data package on Windows and as the
+    /// `plugin.vnidrop.share.EXPIRES_AT` long extra on Android, and ignored
+    /// elsewhere. Nothing is enforced by the plugin itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "ts-bindings", ts(as = "Option<f64>"))]
+    pub expires_at: Option<i64>,
+    /// The index of the monitor to show the share UI on, in the order of the
+    /// window's `available_monitors`. Falls back to the screen of the window when
+    /// out of range. (This is only used on Windows and macOS)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub screen: Option<u32>,
+    /// Whether files whose content is identical to an earlier file of the share
+    /// are left out, keeping the first one. Files are compared by the hash of their
+    /// decoded data, or of the content they point to, before any temporary file is
+    /// written. Streamed files and folders are never left out. Defaults to `false`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dedupe_files: Option<bool>,
+    /// Payloads in custom data formats, as Base64 keyed by format id, for receivers
+    /// that understand them. Each is set on the data package with `SetData`. Ids
+    /// should be unique to the app, e.g. `com.example.app.board`, and cannot be one
+    /// of the standard formats such as `Text`. Each payload decodes to at most
+    /// 4 MiB, since the data package holds it in memory. (This is only used on
+    /// Windows)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub custom_formats: Option<BTreeMap<String, String>>,
+    /// The addresses a mail target copies the share to. Each must be a non-empty
+    /// string, malformed addresses are left to the mail app. It is set as the
+    /// `EXTRA_CC` of the share intent on Android, and ignored by targets that are
+    /// not mail apps. (This is only used on Android)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cc: Option<Vec<String>>,
+    /// The address replies to a shared mail go to, which must be non-empty. Android
+    /// has no standard extra for it, so it is attached as the plugin's own
+    /// `plugin.vnidrop.share.REPLY_TO` string extra, which mail apps do not read:
+    /// it has no effect unless the receiving app is written to look for it.
+    /// (This is only used on Android)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+}
+
+/// How files of a single share that end up with the same name are written.
+///
+/// Names are compared after sanitization and ignoring case, as the default file
+/// systems of Windows and macOS do. Files shared by path are never renamed.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "ts-bindings",
+    derive(ts_rs::TS),
+    ts(export, export_to = "../guest-js/bindings/")
+)]
+pub enum NameCollision {
+    /// Renames later files to `name (1).ext`, `name (2).ext` and so on.
+    #[default]
+    Suffix,
+    /// Rejects the share.
+    Error,
+    /// Keeps only the last file with a given name.
+    Overwrite,
+}
+
+/// The edge of its anchor the macOS share picker appears on, one of the four
+/// `NSRectEdge` values.
+///
+/// The picker is anchored at the center of the window's content view. Edges are
+/// in AppKit coordinates, where the y axis points up unless a view is flipped, so
+/// `MinY` is the bottom edge and `MaxY` the top edge.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "ts-bindings",
+    derive(ts_rs::TS),
+    ts(export, export_to = "../guest-js/bindings/")
+)]
+pub enum PreferredEdge {
+    /// `NSMinXEdge`, the left edge.
+    MinX,
+    /// `NSMinYEdge`, the bottom edge.
+    #[default]
+    MinY,
+    /// `NSMaxXEdge`, the right edge.
+    MaxX,
+    /// `NSMaxYEdge`, the top edge.
+    MaxY,
+}
+
+/// How the receiver of a share is expected to use its temporary files.
+///
+/// The desktop platforms hand receivers a URL to the temporary file rather than
+/// its content, so a receiver that reads it after the file was deleted loses it.
+/// Files shared by path are never deleted by the plugin, whatever the mode.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "ts-bindings",
+    derive(ts_rs::TS),
+    ts(export, export_to = "../guest-js/bindings/")
+)]
+pub enum ShareMode {
+    /// The receiver copies the files, possibly after the share completed, so they
+    /// are kept on disk for the `retainAfterShareMs` grace period.
+    #[default]
+    Copy,
+    /// The receiver only references the files while the share is in progress, so
+    /// they are deleted as soon as they are released, without a grace period.
+    Reference,
+}
+
+/// How the macOS share picker is presented.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "ts-bindings",
+    derive(ts_rs::TS),
+    ts(export, export_to = "../guest-js/bindings/")
+)]
+pub enum SharePresentation {
+    /// A popover anchored in the window, which the rest of the window stays usable
+    /// around.
+    #[default]
+    Popover,
+    /// A window-modal sheet the picker is anchored in, which blocks the window
+    /// until a sharing service is chosen or the picker is dismissed.
+    Sheet,
+}
+
+/// The behavior used in place of a share when native sharing is unavailable.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "ts-bindings",
+    derive(ts_rs::TS),
+    ts(export, export_to = "../guest-js/bindings/")
+)]
+#[non_exhaustive]
+pub enum ShareFallback {
+    /// Opens the shared `url` in the default browser. Only `http` and `https`
+    /// URLs are opened.
+    OpenUrl,
+    /// Opens the first shared file in the default application for its type. A
+    /// file sent as data is written to a temporary file first. A share without
+    /// files is reported as cancelled.
+    OpenFile,
+}
+
+/// Defines a share sent straight to a specific sharing service, without the picker.
+///
+/// `service` is one of the short names below or, on macOS, any `NSSharingService`
+/// name. Services and options that the platform does not support are ignored,
+/// falling back to a regular share.
+///
+/// | `service`   | macOS service                          | `serviceOptions`                                      |
+/// |-------------|----------------------------------------|-------------------------------------------------------|
+/// | `mail`      | `com.apple.share.Mail.compose`         | `subject`, `recipients` (comma-separated)             |
+/// | `messages`  | `com.apple.share.Messages.window`      | `recipients` (comma-separated)                        |
+/// | `airdrop`   | `com.apple.share.AirDrop.send`         |                                                       |
+/// | `notes`     | `com.apple.Notes.SharingExtension`     |                                                       |
+/// | `reminders` | `com.apple.reminders.sharingextension` | `list` (accepted, but macOS always asks for the list) |
+///
+/// Targeted sharing is currently implemented on macOS only; other platforms show
+/// their regular share dialog.
+///
+/// ## Example
+///
+/// ```json
+/// {
+///   "service": "notes",
+///   "options": { "text": "Meeting notes" }
+/// }
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareToOptions {
+    /// The name of the sharing service to share to.
+    pub service: String,
+    /// Service-specific options, see the table above.
+    #[serde(default)]
+    pub service_options: HashMap<String, String>,
+    /// The content to share.
+    #[serde(default)]
+    pub options: ShareOptions,
+}
+
+/// Defines a plain-text document to be shared as a file attachment.
+///
+/// This is a convenience for sharing a string as a file (e.g. a `.txt` export)
+/// without having to Base64-encode it first. The MIME type is inferred from
+/// the file name and defaults to `text/plain`.
+///
+/// ## Example
+///
+/// ```json
+/// {
+///   "content": "Hello from Tauri!",
+///   "name": "greeting.txt"
+/// }
+/// ```
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ShareAsFileOptions {
+    /// The text content of the file.
+    pub content: String,
+    /// The name of the file, including its extension.
+    pub name: String,
+}
+
+impl From<ShareAsFileOptions> for ShareOptions {
+    fn from(options: ShareAsFileOptions) -> Self {
+        let mime_type = crate::validation::infer_mime_type(&options.name)
+            .unwrap_or("text/plain")
+            .to_string();
+        let data = general_purpose::STANDARD.encode(options.content);
+        ShareOptions {
+            files: Some(vec![SharedFile::new(options.name, mime_type, data)]),
+            ..Default::default()
+        }
+    }
+}
+
+/// The outcome of a share operation, returned by the `share` command.
+///
+/// Serialized with a `status` tag, e.g. `{ "status": "completed", "target": "Mail" }`,
+/// `{ "status": "cancelled" }`, `{ "status": "fallback", "method": "openUrl" }` or
+/// `{ "status": "notShown" }`.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "ts-bindings",
+    derive(ts_rs::TS),
+    ts(export, export_to = "../guest-js/bindings/")
+)]
+#[non_exhaustive]
+pub enum ShareResult {
+    /// The content was handed over to a share target.
+    ///
+    /// `target` identifies the chosen target when the platform reports it: the
+    /// sharing service title on macOS, the application name on Windows, the
+    /// package name of the chosen component on Android and the activity type on iOS.
+    ///
+    /// `failed_files` lists the files that could not be attached to the share, each
+    /// as `"<name>: <reason>"`, as far as known when the share resolved. They are
+    /// also reported through the `vnidrop-share:error` event. (This is only
+    /// reported on Windows)
+    ///
+    /// `accepted_files` lists the shared files the target reported as shared, by
+    /// their path when shared by path and by their name otherwise, in the order
+    /// they were shared. It is empty where the platform does not report which
+    /// items were consumed. (This is only reported on macOS)
+    ///
+    /// `duplicates_removed` counts the files left out of the share by
+    /// `dedupe_files`.
+    #[non_exhaustive]
+    Completed {
+        target: Option<String>,
+        #[serde(rename = "failedFiles", default, skip_serializing_if = "Vec::is_empty")]
+        failed_files: Vec<String>,
+        #[serde(
+            rename = "acceptedFiles",
+            default,
+            skip_serializing_if = "Vec::is_empty"
+        )]
+        accepted_files: Vec<String>,
+        #[serde(rename = "duplicatesRemoved", default, skip_serializing_if = "is_zero")]
+        duplicates_removed: u32,
+    },
+    /// The share dialog was dismissed without choosing a target.
+    Cancelled,
+    /// Native sharing was unavailable, so the requested fallback was used instead.
+    ///
+    /// `method` names the fallback, e.g. `openUrl`.
+    Fallback { method: String },
+    /// The share UI most likely never appeared. (This is only reported on Windows)
+    ///
+    /// `ShowShareUIForWindow` succeeds even where sharing is disabled, e.g. by group
+    /// policy, so this is inferred from the app window keeping its focus for the
+    /// short grace period after the share UI was requested. The heuristic is not
+    /// exact: a share UI slower to take focus than the grace period is reported as
+    /// not shown, and anything else taking the focus counts as the share UI. It is
+    /// never reported for tray-only apps, whose share UI is hosted by a transient
+    /// window instead.
+    NotShown,
+}
+
+impl ShareResult {
+    /// Reports the `count` files `dedupe_files` left out of a completed share.
+    pub(crate) fn with_duplicates_removed(mut self, count: u32) -> Self {
+        if let ShareResult::Completed {
+            duplicates_removed, ..
+        } = &mut self
+        {
+            *duplicates_removed = count;
+        }
+        self
+    }
+}
+
+fn is_zero(count: &u32) -> bool {
+    *count == 0
 }
 
 /// The result type for the `can_share` command.
@@ -61,8 +638,269 @@ pub struct ShareOptions {
 /// A `true` value indicates that the current platform supports native sharing.
 /// The [`crate::commands::can_share`] command will return `true` on Windows, macOS, and mobile platforms,
 /// and `false` on Linux since there is no native sharing dialog available.
-#[derive(Debug, Deserialize, Serialize, Clone)]
+///
+/// `max_files` and `max_total_bytes` report the limits the platform itself puts on
+/// a share, so the frontend can check files before calling `share`. They are
+/// `None` when the platform has no known limit. None of the supported platforms
+/// currently documents one: limits such as MMS size caps are set by individual
+/// share targets, which cannot be known before the user picks one.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
+#[cfg_attr(
+    feature = "ts-bindings",
+    derive(ts_rs::TS),
+    ts(export, export_to = "../guest-js/bindings/")
+)]
+#[non_exhaustive]
 pub struct CanShareResult {
     pub value: bool,
+    /// The maximum number of files in a single share, if limited.
+    #[serde(default)]
+    pub max_files: Option<u32>,
+    /// The maximum combined size of the files in a single share, if limited.
+    #[serde(default)]
+    #[cfg_attr(feature = "ts-bindings", ts(as = "Option<f64>"))]
+    pub max_total_bytes: Option<u64>,
+}
+
+/// A share target the user recently shared to, as reported by the platform.
+///
+/// Returned by the `recent_targets` command, most recent first.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct RecentTarget {
+    /// The display name of the target, e.g. an app or a contact.
+    pub name: String,
+    /// The name `share_to` accepts for the target, if it can be shared to directly.
+    #[serde(default)]
+    pub service: Option<String>,
+}
+
+/// The result type for the `cleanup` command.
+///
+/// `removed` counts the temporary files that were deleted, and `failed` lists the
+/// paths of the files that could not be deleted.
+///
+/// When specific paths are cleaned up, `removed_paths` lists those that were
+/// deleted and `skipped` those that were left alone, because they are not
+/// temporary files managed by the plugin.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct CleanupResult {
+    pub removed: u32,
+    #[serde(default)]
+    pub failed: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_paths: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<String>,
+}
+
+/// Describes a single file as it would be shared, as reported by the `validate` command.
+///
+/// When the file is invalid (for example its Base64 data cannot be decoded or its name
+/// is reserved), the resolved fields are `None` and `error` explains why.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct ValidatedFile {
+    /// The file name as provided by the caller.
+    pub name: String,
+    /// The sanitized file name, before any platform-specific rename.
+    pub resolved_name: Option<String>,
+    /// The MIME type that would be attached to the shared item.
+    pub resolved_mime: Option<String>,
+    /// The size of the file content in bytes: the decoded size of its data, or the
+    /// size of the file at its path. Unknown for directories and streamed files.
+    pub byte_length: Option<u64>,
+    /// The reason this file would be rejected, if any.
+    pub error: Option<String>,
+}
+
+/// The result type for the `validate` command.
+///
+/// It previews what a call to `share` with the same options would send, without
+/// creating temporary files or showing the share dialog.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct ValidatedShare {
+    /// The title that would be passed to the share dialog.
+    pub title: Option<String>,
+    /// The text and URL merged the way the desktop platforms share them.
+    pub text: Option<String>,
+    /// One entry per file, in the order they were provided.
+    pub files: Vec<ValidatedFile>,
+}
+
+/// The `NSSharingService` name of the Mail compose window, accepted as `mail`.
+pub const SERVICE_MAIL: &str = "com.apple.share.Mail.compose";
+/// The `NSSharingService` name of the Messages window, accepted as `messages`.
+pub const SERVICE_MESSAGES: &str = "com.apple.share.Messages.window";
+/// The `NSSharingService` name of AirDrop, accepted as `airdrop`.
+pub const SERVICE_AIRDROP: &str = "com.apple.share.AirDrop.send";
+/// The `NSSharingService` name of the Notes extension, accepted as `notes`.
+pub const SERVICE_NOTES: &str = "com.apple.Notes.SharingExtension";
+/// The `NSSharingService` name of the Reminders extension, accepted as `reminders`.
+pub const SERVICE_REMINDERS: &str = "com.apple.reminders.sharingextension";
+
+/// The event emitted when part of a share could not be completed.
+///
+/// The payload is a [`ShareErrorEvent`].
+pub const SHARE_ERROR_EVENT: &str = "vnidrop-share:error";
+
+/// The payload of the [`SHARE_ERROR_EVENT`] event.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct ShareErrorEvent {
+    /// A human-readable description of the problem.
+    pub message: String,
+    /// The files affected by the problem, with the reason for each.
+    pub files: Vec<String>,
+}
+
+/// The event emitted when a share started by the `share_nowait` command finishes.
+///
+/// The payload is a [`ShareCompletedEvent`].
+pub const SHARE_COMPLETED_EVENT: &str = "vnidrop-share:completed";
+
+/// The payload of the [`SHARE_COMPLETED_EVENT`] event.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct ShareCompletedEvent {
+    /// The id `share_nowait` returned for the share.
+    pub share_id: String,
+    /// The outcome of the share, unless it failed.
+    pub result: Option<ShareResult>,
+    /// Why the share failed, if it did.
+    pub error: Option<String>,
+}
+
+/// The event emitted while an uploader registered with `register_uploader` runs
+/// for a share.
+///
+/// The payload is an [`UploadProgressEvent`].
+pub const UPLOAD_PROGRESS_EVENT: &str = "vnidrop-share:upload-progress";
+
+/// The payload of the [`UPLOAD_PROGRESS_EVENT`] event.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct UploadProgressEvent {
+    /// The id the uploader was registered with, as set in `url_provider_id`.
+    pub uploader_id: String,
+    /// The number of bytes uploaded so far.
+    pub sent: u64,
+    /// The total number of bytes to upload, when the uploader knows it.
+    pub total: Option<u64>,
+}
+
+/// The event emitted when temporary files were deleted to stay under the
+/// `tempHighWaterMarkBytes` config.
+///
+/// The payload is a [`CleanedEvent`].
+pub const CLEANED_EVENT: &str = "vnidrop-share:cleaned";
+
+/// The payload of the [`CLEANED_EVENT`] event.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct CleanedEvent {
+    /// The paths of the deleted files, oldest first.
+    pub files: Vec<String>,
+}
+
+/// The event emitted when another app shares content with this app (mobile only).
+///
+/// The payload is a [`ReceivedShare`].
+pub const SHARE_RECEIVED_EVENT: &str = "vnidrop-share:received";
+
+/// The payload of the [`SHARE_RECEIVED_EVENT`] event.
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct ReceivedShare {
+    /// The subject the content was shared with, if any.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// The shared text, which may be a URL.
+    #[serde(default)]
+    pub text: Option<String>,
+    /// The shared files, copied to the app's temp directory.
+    #[serde(default)]
+    pub files: Vec<ReceivedFile>,
+}
+
+/// A file received from another app.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+#[non_exhaustive]
+pub struct ReceivedFile {
+    /// The absolute path of the file.
+    pub path: String,
+    /// The name of the file.
+    pub name: String,
+    /// The MIME type reported by the sharing app, if any.
+    #[serde(default)]
+    pub mime_type: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_file_from_path_infers_name_and_mime_type() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.pdf");
+        std::fs::write(&path, b"%PDF").unwrap();
+
+        let file = SharedFile::try_from(path.as_path()).unwrap();
+        assert_eq!(file.name, "report.pdf");
+        assert_eq!(file.mime_type, "application/pdf");
+        assert_eq!(file.path, Some(path.to_string_lossy().into_owned()));
+        assert!(file.data.is_empty());
+    }
+
+    #[test]
+    fn shared_file_from_path_rejects_missing_and_unnamed_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            SharedFile::try_from(dir.path().join("missing.txt")),
+            Err(Error::Io(_))
+        ));
+        assert!(matches!(
+            SharedFile::try_from(Path::new("/")),
+            Err(Error::InvalidArgs(_))
+        ));
+    }
+
+    #[test]
+    fn completed_result_fields_are_serialized_in_camel_case() {
+        let result = ShareResult::Completed {
+            target: Some("Mail".to_string()),
+            failed_files: vec!["a.txt: denied".to_string()],
+            accepted_files: vec!["b.txt".to_string()],
+            duplicates_removed: 1,
+        };
+        let value = serde_json::to_value(&result).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "status": "completed",
+                "target": "Mail",
+                "failedFiles": ["a.txt: denied"],
+                "acceptedFiles": ["b.txt"],
+                "duplicatesRemoved": 1,
+            })
+        );
+        assert_eq!(
+            serde_json::from_value::<ShareResult>(value).unwrap(),
+            result
+        );
+    }
 }
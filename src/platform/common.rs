@@ -0,0 +1,1611 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::models::CanShareResult;
+use crate::state::PluginTempFileManager;
+use crate::validation::decode_file_data;
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+use crate::NameCollision;
+use crate::{Config, Error, SharedFile};
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+use raw_window_handle::RawWindowHandle;
+use std::future::Future;
+#[cfg(feature = "image-resize")]
+use std::io::Cursor;
+#[cfg(any(target_os = "windows", target_os = "macos", feature = "image-resize"))]
+use std::io::Read;
+use std::io::Write;
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+use std::sync::mpsc::RecvError;
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+use std::sync::mpsc::RecvTimeoutError;
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+use std::sync::{Arc, Mutex, MutexGuard};
+#[cfg(any(target_os = "windows", test))]
+use std::time::Instant;
+use tempfile::Builder;
+use tokio::runtime::{Handle, RuntimeFlavor};
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+use tokio::sync::oneshot;
+use zeroize::Zeroizing;
+
+/// The name of the subdirectory of the system temp directory holding the plugin's files.
+const PLUGIN_TEMP_DIR_NAME: &str = "tauri-plugin-share";
+
+/// How long an unmanaged file must have been left untouched before it is
+/// considered orphaned.
+///
+/// The plugin temp directory is shared by every running app using the plugin, so
+/// a recent file that this process does not manage may still be in use elsewhere.
+const ORPHAN_MIN_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Returns the path to a dedicated, secure directory for this plugin's temporary files.
+///
+/// Every desktop platform writes its temporary files here, so they never mix with
+/// unrelated files in the system temp directory. The `tempSubdirectory` config
+/// gives the app a directory of its own within it.
+pub(crate) fn get_plugin_temp_dir(config: &Config) -> Result<PathBuf, Error> {
+    let dir = plugin_temp_dir_path(config);
+    if !dir.exists() {
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| Error::TempFile(format!("Failed to create temp dir: {}", e)))?;
+    }
+    Ok(dir)
+}
+
+fn plugin_temp_dir_path(config: &Config) -> PathBuf {
+    let dir = std::env::temp_dir().join(PLUGIN_TEMP_DIR_NAME);
+    match config.temp_subdirectory.as_deref() {
+        Some(subdirectory) => dir.join(subdirectory),
+        None => dir,
+    }
+}
+
+/// Removes the plugin temp directory once it no longer holds any file.
+///
+/// Individual files are deleted by the shared `PluginTempFileManager` when no share
+/// uses them anymore, so a non-empty directory is left untouched: whatever remains
+/// still belongs to an in-flight share. The subdirectory of the app is removed
+/// first, so the directory shared by every app goes once the last one is done.
+pub(crate) fn cleanup_plugin_temp_dir(config: &Config) -> Result<(), Error> {
+    let dir = plugin_temp_dir_path(config);
+    remove_dir_if_empty(&dir)?;
+    if config.temp_subdirectory.is_some() {
+        if let Some(parent) = dir.parent() {
+            remove_dir_if_empty(parent)?;
+        }
+    }
+    Ok(())
+}
+
+/// Removes the plugin temp directory of the app along with whatever is left in it,
+/// so no directory is left behind. The directory shared by every app is only
+/// removed once it is empty.
+#[cfg(any(target_os = "windows", test))]
+pub(crate) fn remove_plugin_temp_dir(config: &Config) -> Result<(), Error> {
+    let dir = plugin_temp_dir_path(config);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)
+            .map_err(|e| Error::TempFile(format!("Failed to cleanup temp dir: {}", e)))?;
+    }
+    if config.temp_subdirectory.is_some() {
+        if let Some(parent) = dir.parent() {
+            remove_dir_if_empty(parent)?;
+        }
+    }
+    Ok(())
+}
+
+fn remove_dir_if_empty(dir: &Path) -> Result<(), Error> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    let is_empty = dir
+        .read_dir()
+        .map(|mut entries| entries.next().is_none())
+        .map_err(|e| Error::TempFile(format!("Failed to read temp dir: {}", e)))?;
+    if is_empty {
+        std::fs::remove_dir(dir)
+            .map_err(|e| Error::TempFile(format!("Failed to cleanup temp dir: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// The prefix and suffix around the random part of the name of the temporary
+/// file holding `file_name`, from the `tempFilePrefix` and `tempFileSuffix` config.
+pub(crate) fn temp_file_affixes(config: &Config, file_name: &str) -> (String, String) {
+    let prefix = config
+        .temp_file_prefix
+        .clone()
+        .unwrap_or_else(|| format!("{}-", uuid::Uuid::new_v4()));
+    let separator = config.temp_file_suffix.as_deref().unwrap_or("-");
+    (prefix, format!("{}{}", separator, file_name))
+}
+
+/// Deletes the orphaned files of the plugin temp dir and of its handoff dir.
+///
+/// These are the files left behind by crashed runs, and the files written for a
+/// handoff that was never shared, once they are [`ORPHAN_MIN_AGE`] old. Returns
+/// the paths that were removed.
+pub fn cleanup_orphans(
+    manager: &PluginTempFileManager,
+    config: &Config,
+) -> Result<Vec<PathBuf>, Error> {
+    let temp_dir = get_plugin_temp_dir(config)?;
+    let handoff_dir = crate::scope::handoff_dir(&temp_dir)?;
+    let mut removed = Vec::new();
+    for dir in [temp_dir, handoff_dir] {
+        removed.extend(remove_orphaned_files(
+            &dir,
+            |path| manager.is_managed(path),
+            ORPHAN_MIN_AGE,
+        )?);
+    }
+    Ok(removed)
+}
+
+/// Deletes the files in `dir` that are not managed by this process and have not
+/// been modified for at least `min_age`.
+///
+/// Files tied to an active share are skipped through `is_managed`, and
+/// subdirectories are never touched. Returns the paths that were removed.
+pub(crate) fn remove_orphaned_files(
+    dir: &Path,
+    is_managed: impl Fn(&Path) -> bool,
+    min_age: Duration,
+) -> Result<Vec<PathBuf>, Error> {
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(Error::TempFile(format!("Failed to read temp dir: {}", e))),
+    };
+
+    let now = SystemTime::now();
+    let mut removed = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let age = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .unwrap_or_default();
+        if !metadata.is_file() || age < min_age || is_managed(&path) {
+            continue;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => removed.push(path),
+            Err(e) => eprintln!("Failed to delete orphaned file {}: {}", path.display(), e),
+        }
+    }
+    Ok(removed)
+}
+
+/// The `can_share` result of a desktop platform, which has a native share dialog
+/// when `available`.
+///
+/// None of the desktop platforms documents a limit on the files of a share.
+pub(crate) fn can_share_result(available: bool) -> CanShareResult {
+    CanShareResult {
+        value: available,
+        ..Default::default()
+    }
+}
+
+/// Fails with [`Error::TempFile`] when `required` bytes do not fit in the
+/// `available` space of the temp dir.
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+pub(crate) fn check_disk_space(required: u64, available: u64) -> Result<(), Error> {
+    if required > available {
+        return Err(Error::TempFile(format!(
+            "The share needs {} bytes but only {} are available in the temp dir",
+            required, available
+        )));
+    }
+    Ok(())
+}
+
+/// Applies `strategy` to the files written to temporary files that share a name.
+///
+/// Names are compared by their sanitized file name, ignoring case. With
+/// [`NameCollision::Suffix`], later files are renamed to `name (1).ext`,
+/// `name (2).ext` and so on, skipping names that are already taken. Files shared
+/// by path are left alone, and names that cannot be sanitized are left for the
+/// writer to reject.
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+pub(crate) fn resolve_name_collisions(
+    files: &mut Vec<SharedFile>,
+    strategy: NameCollision,
+) -> Result<(), Error> {
+    let sanitized_name = |file: &SharedFile| {
+        file.path
+            .is_none()
+            .then(|| crate::validation::sanitize_file_name(&file.name).ok())
+            .flatten()
+    };
+    let key = |file: &SharedFile| sanitized_name(file).map(|name| name.to_lowercase());
+
+    match strategy {
+        NameCollision::Overwrite => {
+            // Later files win, so names are claimed from the end.
+            let mut seen = std::collections::HashSet::new();
+            let mut keep: Vec<bool> = files
+                .iter()
+                .rev()
+                .map(|file| match key(file) {
+                    Some(name) => seen.insert(name),
+                    None => true,
+                })
+                .collect();
+            files.retain(|_| keep.pop().unwrap_or(true));
+        }
+        NameCollision::Error => {
+            let mut seen = std::collections::HashSet::new();
+            for file in files.iter() {
+                if let Some(name) = key(file) {
+                    if !seen.insert(name) {
+                        return Err(Error::InvalidArgs(format!(
+                            "Several files are named '{}'",
+                            file.name
+                        )));
+                    }
+                }
+            }
+        }
+        NameCollision::Suffix => {
+            let mut taken: std::collections::HashSet<String> =
+                files.iter().filter_map(key).collect();
+            let mut seen = std::collections::HashSet::new();
+            for file in files.iter_mut() {
+                let Some(name) = sanitized_name(file) else {
+                    continue;
+                };
+                if seen.insert(name.to_lowercase()) {
+                    continue;
+                }
+                // The new name is built from the name the file is written under, so
+                // it is compared with the other written names as is.
+                let sanitized = Path::new(&name);
+                let stem = sanitized
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or_default();
+                let extension = sanitized
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .map(|extension| format!(".{}", extension))
+                    .unwrap_or_default();
+                let renamed = (1..)
+                    .map(|n| format!("{} ({}){}", stem, n, extension))
+                    .find(|candidate| taken.insert(candidate.to_lowercase()))
+                    .expect("a free name is found among unbounded candidates");
+                file.name = renamed;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes the content of a shared file into `writer`.
+///
+/// Files backed by a reader are streamed with [`std::io::copy`]; all others have
+/// their Base64 `data` decoded in memory first. The decoded bytes are zeroized
+/// once written, so they do not linger in freed memory.
+///
+/// With the `image-resize` feature and an `image_max_dimension`, images are read
+/// into memory and downscaled to fit within it first, see [`downscale_image`].
+#[cfg_attr(not(feature = "image-resize"), allow(unused_variables))]
+pub(crate) fn write_file_data(
+    file: &SharedFile,
+    index: usize,
+    writer: &mut impl Write,
+    image_max_dimension: Option<u32>,
+) -> Result<(), Error> {
+    #[cfg(feature = "image-resize")]
+    if let Some(max_dimension) =
+        image_max_dimension.filter(|_| file.mime_type.starts_with("image/"))
+    {
+        let bytes = Zeroizing::new(read_file_data(file, Some(index))?);
+        let resized = downscale_image(&bytes, max_dimension).map(Zeroizing::new);
+        let bytes: &[u8] = match &resized {
+            Some(resized) => resized,
+            None => &bytes,
+        };
+        return writer
+            .write_all(bytes)
+            .map_err(|e| Error::TempFile(format!("Failed to write to temp file: {}", e)));
+    }
+
+    match file.reader.as_ref().and_then(|reader| reader.take()) {
+        Some(mut reader) => {
+            std::io::copy(&mut reader, writer)
+                .map_err(|e| Error::TempFile(format!("Failed to write to temp file: {}", e)))?;
+        }
+        None => {
+            let decoded_bytes = Zeroizing::new(decode_file_data(file, Some(index))?);
+            writer
+                .write_all(&decoded_bytes)
+                .map_err(|e| Error::TempFile(format!("Failed to write to temp file: {}", e)))?;
+        }
+    }
+    Ok(())
+}
+
+/// Where the temporary files of a share are written.
+///
+/// The platforms write to disk through [`DiskTempFiles`], while tests write to a
+/// [`MemoryTempFiles`], so what a share would write (names, bytes and order) can
+/// be checked without touching the temp dir.
+pub(crate) trait TempFileWriter {
+    /// The handle the content of a file is written through.
+    type File: Write;
+
+    /// Creates an empty temporary file holding `file_name`, which is already
+    /// sanitized, and returns its handle and path.
+    fn create(&self, file_name: &str) -> Result<(Self::File, PathBuf), Error>;
+
+    /// Removes the temporary file at `path` after its content failed to be written.
+    fn discard(&self, path: &Path);
+}
+
+/// Writes `file`, at `index` among the files of its share, to a new temporary
+/// file of `temp_files` and returns its path.
+///
+/// The name of the file is sanitized first, and a file whose content cannot be
+/// written is discarded rather than shared incomplete.
+pub(crate) fn write_temp_file(
+    temp_files: &impl TempFileWriter,
+    file: &SharedFile,
+    index: usize,
+    image_max_dimension: Option<u32>,
+) -> Result<PathBuf, Error> {
+    let sanitized_name = crate::validation::sanitize_file_name(&file.name)?;
+    let (mut handle, path) = temp_files.create(&sanitized_name)?;
+    if let Err(e) = write_file_data(file, index, &mut handle, image_max_dimension) {
+        drop(handle);
+        temp_files.discard(&path);
+        return Err(e);
+    }
+    Ok(path)
+}
+
+/// Writes temporary files to a directory on disk.
+pub(crate) struct DiskTempFiles<'a> {
+    dir: PathBuf,
+    /// The config whose `tempFilePrefix` and `tempFileSuffix` surround a random
+    /// part of each name, or `None` to write files under their own name.
+    affixes: Option<&'a Config>,
+}
+
+impl<'a> DiskTempFiles<'a> {
+    /// Writes files in `dir` under random names built with the affixes of
+    /// `config`, so concurrent shares never overwrite each other's files.
+    #[cfg(any(target_os = "macos", target_os = "linux", test))]
+    pub(crate) fn random_names(dir: PathBuf, config: &'a Config) -> Self {
+        Self {
+            dir,
+            affixes: Some(config),
+        }
+    }
+
+    /// Writes files in `dir` under their own name, replacing any file with it.
+    #[cfg(any(target_os = "windows", test))]
+    pub(crate) fn exact_names(dir: PathBuf) -> Self {
+        Self { dir, affixes: None }
+    }
+}
+
+impl TempFileWriter for DiskTempFiles<'_> {
+    type File = std::fs::File;
+
+    fn create(&self, file_name: &str) -> Result<(Self::File, PathBuf), Error> {
+        let Some(config) = self.affixes else {
+            let path = self.dir.join(file_name);
+            let handle = std::fs::File::create(&path)
+                .map_err(|e| Error::TempFile(format!("Failed to create temp file: {}", e)))?;
+            return Ok((handle, path));
+        };
+        let (prefix, suffix) = temp_file_affixes(config, file_name);
+        Builder::new()
+            .prefix(&prefix)
+            .suffix(&suffix)
+            .tempfile_in(&self.dir)
+            .map_err(|e| Error::TempFile(format!("Failed to create temp file: {}", e)))?
+            .keep()
+            .map_err(|e| Error::TempFile(format!("Failed to keep temp file: {}", e.error)))
+    }
+
+    fn discard(&self, path: &Path) {
+        if let Err(e) = std::fs::remove_file(path) {
+            eprintln!("Failed to delete file {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// Keeps temporary files in memory, in the order they were created.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct MemoryTempFiles {
+    files: Mutex<Vec<(PathBuf, MemoryContent)>>,
+}
+
+/// The content of a file of a [`MemoryTempFiles`].
+#[cfg(test)]
+type MemoryContent = Arc<Mutex<Vec<u8>>>;
+
+#[cfg(test)]
+impl MemoryTempFiles {
+    /// The names and contents of the files written so far, in order.
+    pub(crate) fn written(&self) -> Vec<(String, Vec<u8>)> {
+        lock(&self.files)
+            .iter()
+            .map(|(path, content)| {
+                let name = path.file_name().unwrap_or_default();
+                (name.to_string_lossy().into_owned(), lock(content).clone())
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl TempFileWriter for MemoryTempFiles {
+    type File = MemoryFile;
+
+    fn create(&self, file_name: &str) -> Result<(Self::File, PathBuf), Error> {
+        let path = Path::new("memory").join(file_name);
+        let content = Arc::new(Mutex::new(Vec::new()));
+        lock(&self.files).push((path.clone(), content.clone()));
+        Ok((MemoryFile(content), path))
+    }
+
+    fn discard(&self, path: &Path) {
+        lock(&self.files).retain(|(written, _)| written != path);
+    }
+}
+
+/// A temporary file of a [`MemoryTempFiles`].
+#[cfg(test)]
+pub(crate) struct MemoryFile(MemoryContent);
+
+#[cfg(test)]
+impl Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        lock(&self.0).extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads the whole content of a shared file into memory.
+///
+/// `index` is the position of the file among the files of the share, if any.
+#[cfg(any(target_os = "windows", target_os = "macos", feature = "image-resize"))]
+pub(crate) fn read_file_data(file: &SharedFile, index: Option<usize>) -> Result<Vec<u8>, Error> {
+    match file.reader.as_ref().and_then(|reader| reader.take()) {
+        Some(mut reader) => {
+            let mut bytes = Vec::new();
+            reader
+                .read_to_end(&mut bytes)
+                .map_err(|e| Error::TempFile(format!("Failed to read file data: {}", e)))?;
+            Ok(bytes)
+        }
+        None => decode_file_data(file, index),
+    }
+}
+
+/// Downscales an encoded image to fit within `max_dimension` pixels, keeping its
+/// aspect ratio and format.
+///
+/// Returns `None` when the image already fits, is in a format that is not
+/// resized, or cannot be decoded, in which case it is shared unchanged. Animated
+/// and vector formats such as GIF and SVG are left alone, since resizing them
+/// would drop frames or rasterize them.
+#[cfg(feature = "image-resize")]
+fn downscale_image(bytes: &[u8], max_dimension: u32) -> Option<Vec<u8>> {
+    use image::ImageFormat;
+
+    let format = image::guess_format(bytes).ok()?;
+    if !matches!(
+        format,
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Bmp | ImageFormat::Tiff
+    ) {
+        return None;
+    }
+    let image = image::load_from_memory_with_format(bytes, format).ok()?;
+    if image.width() <= max_dimension && image.height() <= max_dimension {
+        return None;
+    }
+    let resized = image.resize(
+        max_dimension,
+        max_dimension,
+        image::imageops::FilterType::Lanczos3,
+    );
+    let mut encoded = Cursor::new(Vec::new());
+    resized.write_to(&mut encoded, format).ok()?;
+    Some(encoded.into_inner())
+}
+
+/// The largest width or height of the thumbnail of a share, since the share UI
+/// only shows it small.
+#[cfg(all(
+    any(feature = "image-dimensions", feature = "image-resize"),
+    any(target_os = "windows", test)
+))]
+const THUMBNAIL_MAX_DIMENSION: u32 = 1024;
+
+/// The largest width or height of an image a thumbnail is decoded from.
+#[cfg(all(
+    any(feature = "image-dimensions", feature = "image-resize"),
+    any(target_os = "windows", test)
+))]
+const THUMBNAIL_SOURCE_MAX_DIMENSION: u32 = 8192;
+
+/// The most memory the decoder may allocate for the image of a thumbnail.
+#[cfg(all(
+    any(feature = "image-dimensions", feature = "image-resize"),
+    any(target_os = "windows", test)
+))]
+const THUMBNAIL_SOURCE_MAX_ALLOC: u64 = 128 * 1024 * 1024;
+
+/// The pixel dimensions of an image file: those set by the caller, or else those
+/// in the header of the image at `path`, or at the path or in the data of `file`.
+///
+/// Returns `None` for files that are not images, and when the dimensions are not
+/// set and cannot be read, e.g. without the `image-dimensions` feature or for a
+/// streamed file, which can only be read once.
+#[cfg(any(target_os = "macos", test))]
+pub(crate) fn image_dimensions(file: &SharedFile, path: Option<&Path>) -> Option<(u32, u32)> {
+    if !file.mime_type.starts_with("image/") {
+        return None;
+    }
+    if let (Some(width), Some(height)) = (file.width, file.height) {
+        return Some((width, height));
+    }
+    read_image_dimensions(file, path.or(file.path.as_deref().map(Path::new)))
+}
+
+/// Reads the dimensions of an image from its header, without decoding the image.
+#[cfg(all(feature = "image-dimensions", any(target_os = "macos", test)))]
+fn read_image_dimensions(file: &SharedFile, path: Option<&Path>) -> Option<(u32, u32)> {
+    match path {
+        Some(path) => image::ImageReader::open(path)
+            .ok()?
+            .with_guessed_format()
+            .ok()?
+            .into_dimensions()
+            .ok(),
+        None if file.reader.is_none() => {
+            let bytes = Zeroizing::new(decode_file_data(file, None).ok()?);
+            image::ImageReader::new(std::io::Cursor::new(&bytes[..]))
+                .with_guessed_format()
+                .ok()?
+                .into_dimensions()
+                .ok()
+        }
+        None => None,
+    }
+}
+
+#[cfg(all(not(feature = "image-dimensions"), any(target_os = "macos", test)))]
+fn read_image_dimensions(_file: &SharedFile, _path: Option<&Path>) -> Option<(u32, u32)> {
+    None
+}
+
+/// Encodes a PNG thumbnail of an image file, at most [`THUMBNAIL_MAX_DIMENSION`]
+/// pixels wide and high.
+///
+/// The image is decoded from a stream of its path or data within fixed limits,
+/// whatever dimensions the caller set, and `None` is returned when it exceeds
+/// them or cannot be decoded. Streamed files are skipped, since they can only be
+/// read once.
+#[cfg(all(
+    any(feature = "image-dimensions", feature = "image-resize"),
+    any(target_os = "windows", test)
+))]
+pub(crate) fn image_thumbnail(file: &SharedFile) -> Option<Vec<u8>> {
+    if !file.mime_type.starts_with("image/") || file.reader.is_some() {
+        return None;
+    }
+    let image = match file.path.as_deref() {
+        Some(path) => decode_thumbnail_source(image::ImageReader::open(path).ok()?)?,
+        None => {
+            let bytes = Zeroizing::new(decode_file_data(file, None).ok()?);
+            decode_thumbnail_source(image::ImageReader::new(std::io::Cursor::new(&bytes[..])))?
+        }
+    };
+    let thumbnail =
+        if image.width() > THUMBNAIL_MAX_DIMENSION || image.height() > THUMBNAIL_MAX_DIMENSION {
+            image.thumbnail(THUMBNAIL_MAX_DIMENSION, THUMBNAIL_MAX_DIMENSION)
+        } else {
+            image
+        };
+    let mut encoded = std::io::Cursor::new(Vec::new());
+    thumbnail
+        .write_to(&mut encoded, image::ImageFormat::Png)
+        .ok()?;
+    Some(encoded.into_inner())
+}
+
+#[cfg(all(
+    not(any(feature = "image-dimensions", feature = "image-resize")),
+    target_os = "windows"
+))]
+pub(crate) fn image_thumbnail(_file: &SharedFile) -> Option<Vec<u8>> {
+    None
+}
+
+/// Decodes the image a thumbnail is made from, within the thumbnail limits.
+#[cfg(all(
+    any(feature = "image-dimensions", feature = "image-resize"),
+    any(target_os = "windows", test)
+))]
+fn decode_thumbnail_source<R: std::io::BufRead + std::io::Seek>(
+    reader: image::ImageReader<R>,
+) -> Option<image::DynamicImage> {
+    let mut reader = reader.with_guessed_format().ok()?;
+    let mut limits = image::Limits::default();
+    limits.max_image_width = Some(THUMBNAIL_SOURCE_MAX_DIMENSION);
+    limits.max_image_height = Some(THUMBNAIL_SOURCE_MAX_DIMENSION);
+    limits.max_alloc = Some(THUMBNAIL_SOURCE_MAX_ALLOC);
+    reader.limits(limits);
+    reader.decode().ok()
+}
+
+/// Runs the main-thread part of a share, turning a panic into an error.
+///
+/// The result is sent back to a thread blocked on a channel, so a panic must not
+/// unwind past the send: the caller would otherwise wait until it times out.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub(crate) fn catch_panic<T>(f: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(Error::NativeApi(format!(
+            "Share setup panicked on the main thread: {}",
+            message
+        )))
+    })
+}
+
+/// Waits for the main thread to report the outcome of the share setup.
+///
+/// `rx` is borrowed, so that after a timeout the caller can still follow the
+/// setup, which may show the share UI late, see [`release_after_late_share`].
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+pub(crate) async fn recv_setup_result<T: Send + 'static>(
+    rx: &mut oneshot::Receiver<Result<T, Error>>,
+    timeout: Duration,
+) -> Result<T, Error> {
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => Err(RecvError.into()),
+        Err(_) => Err(Error::Timeout(format!(
+            "the main thread did not prepare the share UI within {:?}",
+            timeout
+        ))),
+    }
+}
+
+/// Releases the files of a share whose setup timed out once `late_share` is done.
+///
+/// The share already failed, but the main thread may still show its share UI
+/// afterwards, which reads the files. `late_share` follows that late setup until
+/// its share UI is dismissed, or resolves right away when the setup failed.
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+pub(crate) fn release_after_late_share(
+    share_files: crate::state::ShareFiles,
+    late_share: impl Future<Output = ()> + Send + 'static,
+) {
+    tauri::async_runtime::spawn(async move {
+        late_share.await;
+        share_files.release();
+    });
+}
+
+/// Fails the share when its setup could not be dispatched to the main thread,
+/// e.g. while the event loop shuts down.
+///
+/// The setup closure is dropped along with the sender of its result, so nothing is
+/// left waiting on it. The temporary files the share already created are released,
+/// as no share UI will ever read them.
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+pub(crate) fn check_dispatched<E: std::fmt::Display>(
+    dispatched: Result<(), E>,
+    share_files: &crate::state::ShareFiles,
+) -> Result<(), Error> {
+    dispatched.map_err(|e| {
+        share_files.release();
+        Error::NativeApi(format!(
+            "Failed to dispatch the share to the main thread: {}",
+            e
+        ))
+    })
+}
+
+/// Runs `f` on the main thread and blocks until it ran, for at most `timeout`.
+///
+/// For work whose effects later steps rely on, e.g. dismissing the share UI before
+/// the files it reads are deleted. Called from the main thread, `f` runs right away.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub(crate) fn run_on_main_thread_and_wait<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    timeout: Duration,
+    f: impl FnOnce() + Send + 'static,
+) -> Result<(), Error> {
+    let (tx, rx) = oneshot::channel();
+    app.run_on_main_thread(move || {
+        f();
+        let _ = tx.send(());
+    })
+    .map_err(|e| Error::NativeApi(format!("Failed to dispatch to the main thread: {}", e)))?;
+    match block_on(recv_timeout(rx, timeout))? {
+        Ok(()) => Ok(()),
+        Err(RecvTimeoutError::Timeout) => Err(Error::Timeout(format!(
+            "the main thread did not respond within {:?}",
+            timeout
+        ))),
+        Err(RecvTimeoutError::Disconnected) => Err(RecvError.into()),
+    }
+}
+
+/// Waits for the value of `rx`, resolving with `Timeout` once `timeout` elapsed.
+///
+/// The share UI reports its outcome from the main thread or from window events, so
+/// a share can wait for it without tying up a thread. Blocking callers wait with
+/// [`block_on`].
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+pub(crate) async fn recv_timeout<T>(
+    rx: oneshot::Receiver<T>,
+    timeout: Duration,
+) -> Result<T, RecvTimeoutError> {
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(_)) => Err(RecvTimeoutError::Disconnected),
+        Err(_) => Err(RecvTimeoutError::Timeout),
+    }
+}
+
+/// Runs `future` to completion on the async runtime, blocking the current thread.
+///
+/// Unlike the `block_on` of the async runtime, it can be called from the threads
+/// of the runtime, which is where the blocking share functions are usually called.
+/// Such a thread hands its work over to another one while it is blocked, so the
+/// runtime keeps driving the timers and tasks the future waits for.
+///
+/// The thread of a current-thread runtime cannot be handed over: blocking it would
+/// stop the timers the future waits for, so this fails with
+/// [`Error::Unsupported`] there instead.
+pub(crate) fn block_on<F: Future>(future: F) -> Result<F::Output, Error> {
+    let runtime = tauri::async_runtime::handle().inner().clone();
+    match Handle::try_current().map(|current| current.runtime_flavor()) {
+        Err(_) => Ok(runtime.block_on(future)),
+        Ok(RuntimeFlavor::CurrentThread) => Err(Error::Unsupported(
+            "the blocking share APIs cannot run on a current-thread async runtime, use the async ones"
+                .to_string(),
+        )),
+        Ok(_) => Ok(tokio::task::block_in_place(|| runtime.block_on(future))),
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+/// Builds the error returned when a window is backed by an unexpected handle type.
+///
+/// The actual variant is included so that unusual window backends, e.g. an app
+/// embedded through a different windowing layer, can be diagnosed.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub(crate) fn unsupported_window_handle(expected: &str, actual: &RawWindowHandle) -> Error {
+    Error::NativeApi(format!(
+        "Unsupported window handle type: expected {}, got {}",
+        expected,
+        window_handle_kind(actual)
+    ))
+}
+
+/// Returns the name of the `RawWindowHandle` variant, without its fields.
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn window_handle_kind(handle: &RawWindowHandle) -> &'static str {
+    match handle {
+        RawWindowHandle::UiKit(_) => "UiKit",
+        RawWindowHandle::AppKit(_) => "AppKit",
+        RawWindowHandle::Orbital(_) => "Orbital",
+        RawWindowHandle::OhosNdk(_) => "OhosNdk",
+        RawWindowHandle::Xlib(_) => "Xlib",
+        RawWindowHandle::Xcb(_) => "Xcb",
+        RawWindowHandle::Wayland(_) => "Wayland",
+        RawWindowHandle::Drm(_) => "Drm",
+        RawWindowHandle::Gbm(_) => "Gbm",
+        RawWindowHandle::Win32(_) => "Win32",
+        RawWindowHandle::WinRt(_) => "WinRt",
+        RawWindowHandle::Web(_) => "Web",
+        RawWindowHandle::WebCanvas(_) => "WebCanvas",
+        RawWindowHandle::WebOffscreenCanvas(_) => "WebOffscreenCanvas",
+        RawWindowHandle::AndroidNdk(_) => "AndroidNdk",
+        RawWindowHandle::Haiku(_) => "Haiku",
+        _ => "an unknown handle type",
+    }
+}
+
+/// The most threads [`load_in_parallel`] loads items on at once.
+#[cfg(any(target_os = "windows", test))]
+const MAX_LOAD_THREADS: usize = 4;
+
+/// Runs `load` on every item on a pool of at most [`MAX_LOAD_THREADS`] threads and
+/// returns the results in the order of `items`.
+///
+/// Each thread calls `init_thread` before its first item and keeps what it returns
+/// until it is done, e.g. to initialize and uninitialize a native API on it.
+/// Results are awaited until `deadline` without blocking the caller. Items still
+/// loading by then, or whose thread panicked, are reported as `None`; the threads
+/// finish their current item in the background and skip the rest.
+#[cfg(any(target_os = "windows", test))]
+pub(crate) async fn load_in_parallel<I, T, G>(
+    items: Vec<I>,
+    init_thread: impl Fn() -> G + Send + Sync + 'static,
+    load: impl Fn(I) -> T + Send + Sync + 'static,
+    deadline: Instant,
+) -> Vec<Option<T>>
+where
+    I: Send + 'static,
+    T: Send + 'static,
+{
+    let count = items.len();
+    let queue = Arc::new(Mutex::new(items.into_iter().enumerate()));
+    let init_thread = Arc::new(init_thread);
+    let load = Arc::new(load);
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    for _ in 0..count.min(MAX_LOAD_THREADS) {
+        let queue = queue.clone();
+        let init_thread = init_thread.clone();
+        let load = load.clone();
+        let tx = tx.clone();
+        std::thread::spawn(move || {
+            let _thread = init_thread();
+            loop {
+                // The queue is only locked to take the next item, never while loading.
+                let next = lock(&queue).next();
+                let Some((index, item)) = next else {
+                    break;
+                };
+                // Once the results are no longer awaited, the rest is skipped.
+                if tx.send((index, load(item))).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(tx);
+
+    let mut results: Vec<Option<T>> = (0..count).map(|_| None).collect();
+    let deadline = tokio::time::Instant::from_std(deadline);
+    while let Ok(Some((index, result))) = tokio::time::timeout_at(deadline, rx.recv()).await {
+        results[index] = Some(result);
+    }
+    results
+}
+
+/// Attaches the loaded files of a share with `read_only`, retrying with
+/// `read_write` when the read-only items are refused.
+///
+/// Returns the error of the retry when both fail, so the files are reported as
+/// missing from the share instead of silently vanishing.
+#[cfg(any(target_os = "windows", test))]
+pub(crate) fn attach_with_fallback<E: std::fmt::Display>(
+    read_only: impl FnOnce() -> Result<(), E>,
+    read_write: impl FnOnce() -> Result<(), E>,
+) -> Result<(), String> {
+    match read_only() {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            eprintln!(
+                "Failed to attach read-only files, retrying read-write: {}",
+                e
+            );
+            read_write().map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Identifies a shared file in `ShareResult::Completed::accepted_files`: by its
+/// path when shared by path, by its name otherwise.
+#[cfg(any(target_os = "macos", test))]
+pub(crate) fn file_identifier(file: &SharedFile) -> String {
+    file.path.clone().unwrap_or_else(|| file.name.clone())
+}
+
+/// The identifiers of the shared files whose item is among the `accepted` items,
+/// in the order the files were shared.
+#[cfg(any(target_os = "macos", test))]
+pub(crate) fn accepted_files<K: PartialEq>(shared: &[(K, String)], accepted: &[K]) -> Vec<String> {
+    shared
+        .iter()
+        .filter(|(item, _)| accepted.contains(item))
+        .map(|(_, identifier)| identifier.clone())
+        .collect()
+}
+
+/// A monitor, by its position and size in physical pixels on the desktop.
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ScreenRect {
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) scale_factor: f64,
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+impl ScreenRect {
+    /// The center of the monitor, in physical pixels.
+    pub(crate) fn center(&self) -> (i32, i32) {
+        (
+            self.x + (self.width / 2) as i32,
+            self.y + (self.height / 2) as i32,
+        )
+    }
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+impl From<&tauri::Monitor> for ScreenRect {
+    fn from(monitor: &tauri::Monitor) -> Self {
+        Self {
+            x: monitor.position().x,
+            y: monitor.position().y,
+            width: monitor.size().width,
+            height: monitor.size().height,
+            scale_factor: monitor.scale_factor(),
+        }
+    }
+}
+
+/// The monitor `ShareOptions::screen` directs the share UI of `window` to, unless
+/// it is the monitor of the window. See [`requested_screen`].
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+pub(crate) fn requested_screen_for<R: tauri::Runtime>(
+    window: &tauri::Window<R>,
+    index: Option<u32>,
+) -> Option<ScreenRect> {
+    index?;
+    let screens: Vec<ScreenRect> = window
+        .available_monitors()
+        .ok()?
+        .iter()
+        .map(ScreenRect::from)
+        .collect();
+    let current = window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .map(|monitor| ScreenRect::from(&monitor));
+    requested_screen(&screens, index, current.as_ref())
+}
+
+/// Picks the monitor `ShareOptions::screen` directs the share UI to.
+///
+/// Returns `None`, leaving the share UI on the screen of the window, when no
+/// screen is requested, when `index` is out of range, or when the requested
+/// screen already is the one of the window.
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+pub(crate) fn requested_screen(
+    screens: &[ScreenRect],
+    index: Option<u32>,
+    current: Option<&ScreenRect>,
+) -> Option<ScreenRect> {
+    let screen = *screens.get(usize::try_from(index?).ok()?)?;
+    (current != Some(&screen)).then_some(screen)
+}
+
+/// What pending shares hold on to, by the label of the window they were started from.
+///
+/// A share takes its entry back with [`WindowShares::finish`] once it completes.
+/// When its window is destroyed first, [`WindowShares::window_destroyed`] hands the
+/// entry out instead, so whatever it holds is released exactly once.
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+pub(crate) struct WindowShares<T> {
+    pending: Mutex<Vec<(u64, String, T)>>,
+    next_id: AtomicU64,
+}
+
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+impl<T> WindowShares<T> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            pending: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Records `value` as pending on the window `label`, and returns its id.
+    pub(crate) fn begin(&self, label: &str, value: T) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        lock(&self.pending).push((id, label.to_string(), value));
+        id
+    }
+
+    /// Takes the entry `id` back, unless its window was destroyed in the meantime.
+    pub(crate) fn finish(&self, id: u64) -> Option<T> {
+        let mut pending = lock(&self.pending);
+        let index = pending
+            .iter()
+            .position(|(pending_id, _, _)| *pending_id == id)?;
+        Some(pending.remove(index).2)
+    }
+
+    /// Takes every entry still pending on the window `label`.
+    pub(crate) fn window_destroyed(&self, label: &str) -> Vec<T> {
+        let mut pending = lock(&self.pending);
+        let (destroyed, kept) = std::mem::take(&mut *pending)
+            .into_iter()
+            .partition(|(_, pending_label, _)| pending_label == label);
+        *pending = kept;
+        destroyed.into_iter().map(|(_, _, value)| value).collect()
+    }
+
+    /// Takes every pending entry, whatever its window.
+    pub(crate) fn take_all(&self) -> Vec<T> {
+        std::mem::take(&mut *lock(&self.pending))
+            .into_iter()
+            .map(|(_, _, value)| value)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        accepted_files, attach_with_fallback, block_on, can_share_result, check_disk_space,
+        check_dispatched, file_identifier, get_plugin_temp_dir, image_dimensions, load_in_parallel,
+        lock, recv_setup_result, recv_timeout, release_after_late_share, remove_orphaned_files,
+        remove_plugin_temp_dir, requested_screen, resolve_name_collisions, temp_file_affixes,
+        write_temp_file, DiskTempFiles, MemoryTempFiles, ScreenRect, WindowShares,
+        MAX_LOAD_THREADS,
+    };
+    use crate::{Config, Error, NameCollision, SharedFile};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::RecvTimeoutError;
+    use std::sync::{Arc, Barrier, Mutex};
+    use std::time::{Duration, Instant};
+    use tokio::sync::oneshot;
+
+    #[test]
+    fn can_share_reports_the_availability_and_no_file_limits() {
+        for available in [true, false] {
+            let result = can_share_result(available);
+            assert_eq!(result.value, available);
+            assert_eq!(result.max_files, None);
+            assert_eq!(result.max_total_bytes, None);
+        }
+    }
+
+    fn pdf(name: &str, content: &'static [u8]) -> SharedFile {
+        SharedFile::from_reader(name, "application/pdf", content)
+    }
+
+    fn names(files: &[SharedFile]) -> Vec<&str> {
+        files.iter().map(|file| file.name.as_str()).collect()
+    }
+
+    #[test]
+    fn remove_orphaned_files_keeps_managed_and_recent_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let managed = dir.path().join("managed.txt");
+        let orphan = dir.path().join("orphan.txt");
+        std::fs::write(&managed, b"managed").unwrap();
+        std::fs::write(&orphan, b"orphan").unwrap();
+        std::fs::create_dir(dir.path().join("nested")).unwrap();
+
+        let is_managed = |path: &std::path::Path| path == managed;
+        let removed =
+            remove_orphaned_files(dir.path(), is_managed, Duration::from_secs(3600)).unwrap();
+        assert!(removed.is_empty());
+        assert!(orphan.exists());
+
+        let removed = remove_orphaned_files(dir.path(), is_managed, Duration::ZERO).unwrap();
+        assert_eq!(removed, vec![orphan.clone()]);
+        assert!(!orphan.exists());
+        assert!(managed.exists());
+        assert!(dir.path().join("nested").exists());
+    }
+
+    #[test]
+    fn removing_the_temp_dir_removes_what_is_left_in_it() {
+        let config = Config {
+            temp_subdirectory: Some(format!("remove-test-{}", std::process::id())),
+            ..Default::default()
+        };
+        let dir = get_plugin_temp_dir(&config).unwrap();
+        std::fs::write(dir.join("left.txt"), b"left").unwrap();
+        std::fs::create_dir(dir.join("nested")).unwrap();
+
+        remove_plugin_temp_dir(&config).unwrap();
+        assert!(!dir.exists());
+        remove_plugin_temp_dir(&config).unwrap();
+    }
+
+    #[test]
+    fn resolve_name_collisions_applies_the_strategy() {
+        let report = || vec![pdf("report.pdf", b"first"), pdf("Report.pdf", b"second")];
+
+        let mut files = report();
+        files.push(pdf("report (1).pdf", b"third"));
+        resolve_name_collisions(&mut files, NameCollision::Suffix).unwrap();
+        assert_eq!(
+            names(&files),
+            vec!["report.pdf", "Report (2).pdf", "report (1).pdf"]
+        );
+
+        let mut files = report();
+        assert!(resolve_name_collisions(&mut files, NameCollision::Error).is_err());
+
+        let mut files = report();
+        resolve_name_collisions(&mut files, NameCollision::Overwrite).unwrap();
+        assert_eq!(names(&files), vec!["Report.pdf"]);
+
+        let mut files = vec![
+            SharedFile::from_path("/docs/report.pdf", "application/pdf"),
+            pdf("report.pdf", b"written"),
+        ];
+        resolve_name_collisions(&mut files, NameCollision::Error).unwrap();
+    }
+
+    #[test]
+    fn suffixed_names_are_built_from_the_sanitized_name() {
+        let mut files = vec![
+            pdf("c:cat.pdf", b"first"),
+            pdf("c_cat (1).pdf", b"second"),
+            pdf("c:cat.pdf", b"third"),
+        ];
+        resolve_name_collisions(&mut files, NameCollision::Suffix).unwrap();
+        assert_eq!(
+            names(&files),
+            vec!["c:cat.pdf", "c_cat (1).pdf", "c_cat (2).pdf"]
+        );
+    }
+
+    #[test]
+    fn share_files_are_written_in_order() {
+        let temp_files = MemoryTempFiles::default();
+        let mut broken = pdf("broken.pdf", b"");
+        broken.reader = None;
+        broken.data = "a!b".to_string();
+        let mut files = vec![
+            pdf("report.pdf", b"first"),
+            pdf("../report.pdf", b"second"),
+            broken,
+            pdf("notes.pdf", b"third"),
+        ];
+
+        resolve_name_collisions(&mut files, NameCollision::Suffix).unwrap();
+        let results: Vec<_> = files
+            .iter()
+            .enumerate()
+            .map(|(index, file)| write_temp_file(&temp_files, file, index, None))
+            .collect();
+
+        assert!(matches!(results[2], Err(Error::InvalidArgs(_))));
+        let written = temp_files.written();
+        let expected: [(&str, &[u8]); 3] = [
+            ("report.pdf", b"first"),
+            ("report (1).pdf", b"second"),
+            ("notes.pdf", b"third"),
+        ];
+        assert_eq!(written.len(), expected.len());
+        for ((name, content), (expected_name, expected_content)) in written.iter().zip(expected) {
+            assert_eq!(
+                (name.as_str(), content.as_slice()),
+                (expected_name, expected_content)
+            );
+        }
+    }
+
+    #[test]
+    fn disk_temp_files_are_named_and_discarded_on_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let exact = DiskTempFiles::exact_names(dir.path().to_path_buf());
+        let path = write_temp_file(&exact, &pdf("report.pdf", b"first"), 0, None).unwrap();
+        assert_eq!(path, dir.path().join("report.pdf"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"first");
+
+        let config = Config {
+            temp_file_prefix: Some("share-".to_string()),
+            ..Default::default()
+        };
+        let random = DiskTempFiles::random_names(dir.path().to_path_buf(), &config);
+        let path = write_temp_file(&random, &pdf("report.pdf", b"second"), 0, None).unwrap();
+        let name = path.file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with("share-") && name.ends_with("-report.pdf"));
+
+        let mut broken = pdf("broken.pdf", b"");
+        broken.reader = None;
+        broken.data = "a!b".to_string();
+        assert!(write_temp_file(&exact, &broken, 0, None).is_err());
+        assert!(!dir.path().join("broken.pdf").exists());
+    }
+
+    #[test]
+    fn load_in_parallel_keeps_order_and_overlaps_loads() {
+        // The first loads only get past the barrier together, so they must overlap.
+        let barrier = Arc::new(Barrier::new(MAX_LOAD_THREADS));
+        let results = block_on(load_in_parallel(
+            (0..20).collect(),
+            || (),
+            move |index: usize| {
+                if index < MAX_LOAD_THREADS {
+                    barrier.wait();
+                }
+                index * 2
+            },
+            Instant::now() + Duration::from_secs(10),
+        ))
+        .unwrap();
+
+        let expected: Vec<Option<usize>> = (0..20).map(|index| Some(index * 2)).collect();
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn load_in_parallel_bounds_its_threads() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let most_running = Arc::new(AtomicUsize::new(0));
+        let threads = Arc::new(AtomicUsize::new(0));
+        let results = block_on(load_in_parallel(
+            (0..20).collect(),
+            {
+                let threads = threads.clone();
+                move || threads.fetch_add(1, Ordering::SeqCst)
+            },
+            {
+                let (running, most_running) = (running.clone(), most_running.clone());
+                move |index: usize| {
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    most_running.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(1));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    index
+                }
+            },
+            Instant::now() + Duration::from_secs(10),
+        ))
+        .unwrap();
+
+        assert!(results.iter().all(Option::is_some));
+        assert_eq!(threads.load(Ordering::SeqCst), MAX_LOAD_THREADS);
+        assert!(most_running.load(Ordering::SeqCst) <= MAX_LOAD_THREADS);
+    }
+
+    #[test]
+    fn load_in_parallel_reports_late_items_as_missing() {
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        let released = Mutex::new(released);
+        let results = block_on(load_in_parallel(
+            vec![false, true],
+            || (),
+            move |blocks| {
+                if blocks {
+                    let _ = lock(&released).recv();
+                }
+                blocks
+            },
+            Instant::now() + Duration::from_millis(50),
+        ))
+        .unwrap();
+        assert_eq!(results, vec![Some(false), None]);
+        drop(release);
+    }
+
+    #[cfg(any(feature = "image-dimensions", feature = "image-resize"))]
+    #[test]
+    fn image_thumbnail_is_decoded_within_its_limits() {
+        use image::{DynamicImage, ImageFormat};
+        use std::io::Cursor;
+
+        let png = |width, height| {
+            let mut png = Cursor::new(Vec::new());
+            DynamicImage::new_rgb8(width, height)
+                .write_to(&mut png, ImageFormat::Png)
+                .unwrap();
+            png.into_inner()
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("photo.png");
+        std::fs::write(&path, png(2048, 1024)).unwrap();
+        // The dimensions set by the caller are not trusted.
+        let image = SharedFile {
+            width: Some(16),
+            height: Some(16),
+            ..SharedFile::from_path(&path, "image/png")
+        };
+
+        let thumbnail = super::image_thumbnail(&image).unwrap();
+        let thumbnail = image::load_from_memory(&thumbnail).unwrap();
+        assert_eq!((thumbnail.width(), thumbnail.height()), (1024, 512));
+
+        std::fs::write(&path, png(super::THUMBNAIL_SOURCE_MAX_DIMENSION + 1, 1)).unwrap();
+        assert_eq!(super::image_thumbnail(&image), None);
+        std::fs::write(&path, b"not an image").unwrap();
+        assert_eq!(super::image_thumbnail(&image), None);
+    }
+
+    #[cfg(feature = "image-resize")]
+    #[test]
+    fn downscale_image_fits_large_images_and_keeps_small_ones() {
+        use image::{DynamicImage, ImageFormat};
+        use std::io::Cursor;
+
+        let mut png = Cursor::new(Vec::new());
+        DynamicImage::new_rgb8(40, 20)
+            .write_to(&mut png, ImageFormat::Png)
+            .unwrap();
+        let png = png.into_inner();
+
+        let resized = super::downscale_image(&png, 10).unwrap();
+        assert_eq!(image::guess_format(&resized).unwrap(), ImageFormat::Png);
+        let resized = image::load_from_memory(&resized).unwrap();
+        assert_eq!((resized.width(), resized.height()), (10, 5));
+
+        assert!(super::downscale_image(&png, 40).is_none());
+        assert!(super::downscale_image(b"not an image", 10).is_none());
+    }
+
+    #[test]
+    fn check_disk_space_rejects_shares_that_do_not_fit() {
+        assert!(check_disk_space(0, 0).is_ok());
+        assert!(check_disk_space(100, 100).is_ok());
+        assert!(matches!(
+            check_disk_space(101, 100),
+            Err(Error::TempFile(_))
+        ));
+    }
+
+    #[test]
+    fn recv_timeout_resolves_with_the_value_sent_from_another_thread() {
+        let (tx, rx) = oneshot::channel();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            let _ = tx.send(7);
+        });
+        assert_eq!(
+            block_on(recv_timeout(rx, Duration::from_secs(10))).unwrap(),
+            Ok(7)
+        );
+    }
+
+    #[test]
+    fn recv_timeout_times_out_or_disconnects_without_a_value() {
+        let (_tx, rx) = oneshot::channel::<()>();
+        assert_eq!(
+            block_on(recv_timeout(rx, Duration::from_millis(20))).unwrap(),
+            Err(RecvTimeoutError::Timeout)
+        );
+
+        let (tx, rx) = oneshot::channel::<()>();
+        drop(tx);
+        assert_eq!(
+            block_on(recv_timeout(rx, Duration::from_secs(10))).unwrap(),
+            Err(RecvTimeoutError::Disconnected)
+        );
+    }
+
+    #[test]
+    fn block_on_runs_on_the_threads_of_the_async_runtime() {
+        let value = tauri::async_runtime::block_on(async {
+            tauri::async_runtime::spawn(async {
+                block_on(async {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                    7
+                })
+            })
+            .await
+        });
+        assert_eq!(value.unwrap().unwrap(), 7);
+    }
+
+    #[test]
+    fn block_on_fails_on_a_current_thread_runtime() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let result = runtime.block_on(async {
+            block_on(async {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+                7
+            })
+        });
+        assert!(matches!(result, Err(Error::Unsupported(_))));
+    }
+
+    #[test]
+    fn accepted_files_are_identified_as_they_were_shared() {
+        let shared = [
+            (1, file_identifier(&pdf("invoice.pdf", b"%PDF"))),
+            (
+                2,
+                file_identifier(&SharedFile::from_path("/tmp/photo.png", "image/png")),
+            ),
+            (3, file_identifier(&pdf("notes.pdf", b"%PDF"))),
+        ];
+
+        assert_eq!(
+            accepted_files(&shared, &[3, 2, 4]),
+            ["/tmp/photo.png", "notes.pdf"]
+        );
+        assert!(accepted_files(&shared, &[]).is_empty());
+    }
+
+    #[test]
+    fn refused_read_only_files_are_attached_read_write() {
+        let read_write_called = std::cell::Cell::new(false);
+        let attached = attach_with_fallback(
+            || Err("E_ACCESSDENIED"),
+            || {
+                read_write_called.set(true);
+                Ok(())
+            },
+        );
+        assert_eq!(attached, Ok(()));
+        assert!(read_write_called.get());
+
+        let attached = attach_with_fallback(|| Err("E_ACCESSDENIED"), || Err("E_FAIL"));
+        assert_eq!(attached, Err("E_FAIL".to_string()));
+
+        let attached = attach_with_fallback(|| Ok::<(), &str>(()), || panic!("not retried"));
+        assert_eq!(attached, Ok(()));
+    }
+
+    #[test]
+    fn temp_file_names_follow_the_configured_affixes() {
+        let (prefix, suffix) = temp_file_affixes(&Config::default(), "report.pdf");
+        assert_eq!(prefix.len(), 37);
+        assert_eq!(suffix, "-report.pdf");
+
+        let config = Config {
+            temp_file_prefix: Some("example-".to_string()),
+            temp_file_suffix: Some("_".to_string()),
+            ..Default::default()
+        };
+        let (prefix, suffix) = temp_file_affixes(&config, "report.pdf");
+        assert_eq!(prefix, "example-");
+        assert_eq!(suffix, "_report.pdf");
+    }
+
+    #[test]
+    fn requested_screen_falls_back_to_the_window_screen() {
+        let screen = |x| ScreenRect {
+            x,
+            y: 0,
+            width: 1920,
+            height: 1080,
+            scale_factor: 1.0,
+        };
+        let screens = [screen(0), screen(1920)];
+
+        assert_eq!(
+            requested_screen(&screens, Some(1), Some(&screens[0])),
+            Some(screen(1920))
+        );
+        assert_eq!(screen(1920).center(), (2880, 540));
+        assert_eq!(requested_screen(&screens, Some(0), Some(&screens[0])), None);
+        assert_eq!(requested_screen(&screens, Some(2), Some(&screens[0])), None);
+        assert_eq!(requested_screen(&screens, None, Some(&screens[0])), None);
+        assert_eq!(requested_screen(&screens, Some(0), None), Some(screen(0)));
+    }
+
+    #[test]
+    fn image_dimensions_prefer_the_caller_values() {
+        let image = SharedFile {
+            width: Some(640),
+            height: Some(480),
+            ..SharedFile::from_reader("photo.png", "image/png", &b""[..])
+        };
+        assert_eq!(image_dimensions(&image, None), Some((640, 480)));
+
+        let document = SharedFile {
+            mime_type: "application/pdf".to_string(),
+            ..image.clone()
+        };
+        assert_eq!(image_dimensions(&document, None), None);
+        let streamed = SharedFile {
+            height: None,
+            ..image
+        };
+        assert_eq!(image_dimensions(&streamed, None), None);
+    }
+
+    #[test]
+    fn destroyed_window_releases_its_pending_shares_once() {
+        let shares = WindowShares::new();
+        let finished = shares.begin("main", "finished");
+        let interrupted = shares.begin("main", "interrupted");
+        let other = shares.begin("other", "other");
+        assert_eq!(shares.finish(finished), Some("finished"));
+
+        // The window is closed while the share UI is still open.
+        assert_eq!(shares.window_destroyed("main"), vec!["interrupted"]);
+        assert_eq!(shares.window_destroyed("main"), Vec::<&str>::new());
+
+        // The interrupted share completes later, and finds nothing left to release.
+        assert_eq!(shares.finish(interrupted), None);
+        assert_eq!(shares.finish(other), Some("other"));
+    }
+
+    #[test]
+    fn failed_dispatch_releases_the_files_of_the_share() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("written.txt");
+        std::fs::write(&path, b"written").unwrap();
+        let share_files = crate::state::PluginTempFileManager::new().begin_share();
+        share_files.add_file(path.clone());
+
+        check_dispatched(Ok::<(), &str>(()), &share_files).unwrap();
+        assert!(path.exists());
+
+        let result = check_dispatched(Err("the event loop is closed"), &share_files);
+        assert!(matches!(result, Err(Error::NativeApi(message)) if message.contains("event loop")));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn late_share_ui_releases_the_files_once_dismissed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("written.txt");
+        std::fs::write(&path, b"written").unwrap();
+        let share_files = crate::state::PluginTempFileManager::new().begin_share();
+        share_files.add_file(path.clone());
+
+        block_on(async {
+            let (setup_tx, mut setup_rx) = oneshot::channel::<Result<(), Error>>();
+            let (dismissed_tx, dismissed_rx) = oneshot::channel::<()>();
+            let setup = recv_setup_result(&mut setup_rx, Duration::from_millis(10)).await;
+            assert!(matches!(setup, Err(Error::Timeout(_))));
+            release_after_late_share(share_files, async move {
+                if let Ok(Ok(())) = setup_rx.await {
+                    let _ = dismissed_rx.await;
+                }
+            });
+
+            // The share UI appears late, and is still shown.
+            setup_tx.send(Ok(())).unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            assert!(path.exists());
+
+            dismissed_tx.send(()).unwrap();
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while path.exists() && Instant::now() < deadline {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            assert!(!path.exists());
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn take_all_releases_the_pending_shares_of_every_window() {
+        let shares = WindowShares::new();
+        let main = shares.begin("main", "main");
+        shares.begin("other", "other");
+
+        assert_eq!(shares.take_all(), vec!["main", "other"]);
+        assert_eq!(shares.take_all(), Vec::<&str>::new());
+        assert_eq!(shares.finish(main), None);
+    }
+}
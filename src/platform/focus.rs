@@ -1,17 +1,18 @@
 use std::collections::HashMap;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    mpsc,
     Arc, Mutex, OnceLock,
 };
 use std::time::Duration;
 
-use tauri::{Runtime, Window, WindowEvent};
+use tauri::{Manager, Runtime, Window, WindowEvent};
 
-use crate::Error;
+use tokio::sync::oneshot;
+
+use super::common;
+use crate::{Config, Error};
 
 const FOCUS_WAIT_GRACE: Duration = Duration::from_millis(250);
-const FOCUS_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum FocusPhase {
@@ -68,9 +69,21 @@ impl FocusState {
     }
 }
 
+/// How a focus wait ended.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FocusWaitEnd {
+    /// The window lost focus and regained it, or was destroyed.
+    Returned,
+    /// The window never lost focus within the grace period, so the share UI
+    /// most likely never appeared.
+    NoFocusLoss,
+    /// The configured timeout elapsed first.
+    TimedOut,
+}
+
 struct FocusWaiter {
     label: String,
-    pending: Mutex<Option<mpsc::Sender<()>>>,
+    pending: Mutex<Option<oneshot::Sender<FocusWaitEnd>>>,
     state: Mutex<FocusState>,
     listener_registered: AtomicBool,
 }
@@ -86,20 +99,65 @@ impl FocusWaiter {
     }
 }
 
+/// A window event relevant to a focus wait.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FocusEvent {
+    Focused(bool),
+    Destroyed,
+}
+
+/// The window a focus wait listens to.
+///
+/// Implemented by Tauri windows, and by fakes in tests so the whole wait can be
+/// driven by injected focus events.
+pub trait FocusSource {
+    fn label(&self) -> &str;
+
+    /// The labels of the windows that are still open.
+    fn open_labels(&self) -> Vec<String>;
+
+    fn focus_wait_timeout(&self) -> Duration;
+
+    /// Calls `handler` on every focus change and on the destruction of the window.
+    fn on_focus_event(&self, handler: impl Fn(FocusEvent) + Send + 'static);
+}
+
+impl<R: Runtime> FocusSource for Window<R> {
+    fn label(&self) -> &str {
+        Window::label(self)
+    }
+
+    fn open_labels(&self) -> Vec<String> {
+        self.webview_windows().into_keys().collect()
+    }
+
+    fn focus_wait_timeout(&self) -> Duration {
+        self.state::<Config>().focus_wait_timeout()
+    }
+
+    fn on_focus_event(&self, handler: impl Fn(FocusEvent) + Send + 'static) {
+        self.on_window_event(move |event| match event {
+            WindowEvent::Focused(focused) => handler(FocusEvent::Focused(*focused)),
+            WindowEvent::Destroyed => handler(FocusEvent::Destroyed),
+            _ => {}
+        });
+    }
+}
+
 pub struct FocusWaitHandle {
     waiter: Arc<FocusWaiter>,
-    rx: mpsc::Receiver<()>,
+    rx: oneshot::Receiver<FocusWaitEnd>,
+    timeout: Duration,
 }
 
 impl FocusWaitHandle {
-    pub fn wait(self) -> Result<(), Error> {
-        match self.rx.recv_timeout(FOCUS_WAIT_TIMEOUT) {
-            Ok(()) => {}
-            Err(mpsc::RecvTimeoutError::Timeout) => {}
-            Err(mpsc::RecvTimeoutError::Disconnected) => {}
-        }
+    /// Resolves once the wait ends, without blocking a thread until then.
+    pub async fn wait(self) -> Result<FocusWaitEnd, Error> {
+        let end = common::recv_timeout(self.rx, self.timeout)
+            .await
+            .unwrap_or(FocusWaitEnd::TimedOut);
         clear_pending(&self.waiter);
-        Ok(())
+        Ok(end)
     }
 
     pub fn cancel(self) {
@@ -107,11 +165,11 @@ impl FocusWaitHandle {
     }
 }
 
-pub fn begin_focus_wait<R: Runtime>(window: &Window<R>) -> Result<FocusWaitHandle, Error> {
+pub fn begin_focus_wait(window: &impl FocusSource) -> Result<FocusWaitHandle, Error> {
     let waiter = get_focus_waiter(window)?;
     ensure_focus_listener(window, waiter.clone());
 
-    let (tx, rx) = mpsc::channel();
+    let (tx, rx) = oneshot::channel();
     {
         let mut pending = waiter
             .pending
@@ -128,7 +186,12 @@ pub fn begin_focus_wait<R: Runtime>(window: &Window<R>) -> Result<FocusWaitHandl
 
     spawn_focus_grace_timer(waiter.clone());
 
-    Ok(FocusWaitHandle { waiter, rx })
+    let timeout = window.focus_wait_timeout();
+    Ok(FocusWaitHandle {
+        waiter,
+        rx,
+        timeout,
+    })
 }
 
 fn clear_pending(waiter: &FocusWaiter) {
@@ -140,34 +203,37 @@ fn clear_pending(waiter: &FocusWaiter) {
     }
 }
 
-fn get_focus_waiter<R: Runtime>(window: &Window<R>) -> Result<Arc<FocusWaiter>, Error> {
+fn get_focus_waiter(window: &impl FocusSource) -> Result<Arc<FocusWaiter>, Error> {
+    let open_windows = window.open_labels();
     let mut map = focus_waiters()
         .lock()
         .map_err(|_| Error::NativeApi("Focus wait registry poisoned.".to_string()))?;
+    sweep_focus_waiters(&mut map, |label| {
+        label == window.label() || open_windows.iter().any(|open| open == label)
+    });
     Ok(map
         .entry(window.label().to_string())
         .or_insert_with(|| Arc::new(FocusWaiter::new(window.label().to_string())))
         .clone())
 }
 
-fn ensure_focus_listener<R: Runtime>(window: &Window<R>, waiter: Arc<FocusWaiter>) {
+fn ensure_focus_listener(window: &impl FocusSource, waiter: Arc<FocusWaiter>) {
     if waiter.listener_registered.swap(true, Ordering::SeqCst) {
         return;
     }
 
-    window.on_window_event(move |event| match event {
-        WindowEvent::Focused(focused) => {
-            if should_complete_on_focus(&waiter, *focused) {
-                complete_wait(&waiter);
+    window.on_focus_event(move |event| match event {
+        FocusEvent::Focused(focused) => {
+            if should_complete_on_focus(&waiter, focused) {
+                complete_wait(&waiter, FocusWaitEnd::Returned);
             }
         }
-        WindowEvent::Destroyed => {
+        FocusEvent::Destroyed => {
             if should_complete_on_destroy(&waiter) {
-                complete_wait(&waiter);
+                complete_wait(&waiter, FocusWaitEnd::Returned);
             }
             remove_focus_waiter(&waiter.label);
         }
-        _ => {}
     });
 }
 
@@ -198,30 +264,65 @@ fn spawn_focus_grace_timer(waiter: Arc<FocusWaiter>) {
             state.on_grace_elapsed()
         };
         if should_complete {
-            complete_wait(&waiter);
+            complete_wait(&waiter, FocusWaitEnd::NoFocusLoss);
         }
     });
 }
 
-fn complete_wait(waiter: &FocusWaiter) {
+fn complete_wait(waiter: &FocusWaiter, end: FocusWaitEnd) {
     let sender = match waiter.pending.lock() {
         Ok(mut pending) => pending.take(),
         Err(poisoned) => poisoned.into_inner().take(),
     };
     if let Some(tx) = sender {
-        let _ = tx.send(());
+        let _ = tx.send(end);
     }
     if let Ok(mut state) = waiter.state.lock() {
         state.mark_completed();
     }
 }
 
+/// Ends every wait in progress as if its window had come back, so the shares
+/// waiting on them resolve without a target.
+pub fn end_all_waits() {
+    let waiters: Vec<Arc<FocusWaiter>> = match focus_waiters().lock() {
+        Ok(map) => map.values().cloned().collect(),
+        Err(poisoned) => poisoned.into_inner().values().cloned().collect(),
+    };
+    end_waits(&waiters);
+}
+
+fn end_waits(waiters: &[Arc<FocusWaiter>]) {
+    for waiter in waiters {
+        complete_wait(waiter, FocusWaitEnd::Returned);
+    }
+}
+
 fn remove_focus_waiter(label: &str) {
     if let Ok(mut map) = focus_waiters().lock() {
         map.remove(label);
     }
 }
 
+/// Removes the waiters of windows that are no longer open.
+///
+/// A waiter is normally removed when its window is destroyed, but only once a share
+/// registered the listener for it. Sweeping whenever a wait begins keeps entries of
+/// windows closed without it from accumulating. Waiters with a wait in progress
+/// are kept.
+fn sweep_focus_waiters(
+    waiters: &mut HashMap<String, Arc<FocusWaiter>>,
+    is_open: impl Fn(&str) -> bool,
+) {
+    waiters.retain(|label, waiter| {
+        let waiting = match waiter.pending.lock() {
+            Ok(pending) => pending.is_some(),
+            Err(poisoned) => poisoned.into_inner().is_some(),
+        };
+        waiting || is_open(label)
+    });
+}
+
 fn focus_waiters() -> &'static Mutex<HashMap<String, Arc<FocusWaiter>>> {
     static WAITERS: OnceLock<Mutex<HashMap<String, Arc<FocusWaiter>>>> = OnceLock::new();
     WAITERS.get_or_init(|| Mutex::new(HashMap::new()))
@@ -229,7 +330,57 @@ fn focus_waiters() -> &'static Mutex<HashMap<String, Arc<FocusWaiter>>> {
 
 #[cfg(test)]
 mod tests {
-    use super::{FocusPhase, FocusState};
+    use super::{
+        begin_focus_wait, complete_wait, end_waits, sweep_focus_waiters, FocusEvent, FocusPhase,
+        FocusSource, FocusState, FocusWaitEnd, FocusWaiter,
+    };
+    use crate::platform::common::block_on;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use tokio::sync::oneshot;
+
+    type Handler = Box<dyn Fn(FocusEvent) + Send>;
+
+    /// A window whose focus events are injected by the test.
+    struct FakeWindow {
+        label: String,
+        handlers: Mutex<Vec<Handler>>,
+    }
+
+    impl FakeWindow {
+        /// Each test uses its own label, since waiters are registered globally.
+        fn new(label: &str) -> Self {
+            Self {
+                label: label.to_string(),
+                handlers: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn emit(&self, event: FocusEvent) {
+            for handler in self.handlers.lock().unwrap().iter() {
+                handler(event);
+            }
+        }
+    }
+
+    impl FocusSource for FakeWindow {
+        fn label(&self) -> &str {
+            &self.label
+        }
+
+        fn open_labels(&self) -> Vec<String> {
+            vec![self.label.clone()]
+        }
+
+        fn focus_wait_timeout(&self) -> Duration {
+            Duration::from_secs(30)
+        }
+
+        fn on_focus_event(&self, handler: impl Fn(FocusEvent) + Send + 'static) {
+            self.handlers.lock().unwrap().push(Box::new(handler));
+        }
+    }
 
     #[test]
     fn focus_state_completes_after_loss_and_regain() {
@@ -262,4 +413,125 @@ mod tests {
         assert!(!state.on_grace_elapsed());
         assert_eq!(state.phase, FocusPhase::WaitingForRegain);
     }
+
+    #[test]
+    fn completed_wait_reports_how_it_ended() {
+        let waiter = FocusWaiter::new("main".to_string());
+        let (tx, rx) = oneshot::channel();
+        *waiter.pending.lock().unwrap() = Some(tx);
+
+        complete_wait(&waiter, FocusWaitEnd::NoFocusLoss);
+        complete_wait(&waiter, FocusWaitEnd::Returned);
+
+        assert_eq!(block_on(rx).unwrap(), Ok(FocusWaitEnd::NoFocusLoss));
+        assert_eq!(waiter.state.lock().unwrap().phase, FocusPhase::Completed);
+    }
+
+    #[test]
+    fn ended_waits_return_without_a_focus_change() {
+        let waiting = Arc::new(FocusWaiter::new("waiting".to_string()));
+        let idle = Arc::new(FocusWaiter::new("idle".to_string()));
+        let (tx, rx) = oneshot::channel();
+        *waiting.pending.lock().unwrap() = Some(tx);
+
+        end_waits(&[waiting.clone(), idle.clone()]);
+
+        assert_eq!(block_on(rx).unwrap(), Ok(FocusWaitEnd::Returned));
+        assert!(waiting.pending.lock().unwrap().is_none());
+        assert_eq!(idle.state.lock().unwrap().phase, FocusPhase::Completed);
+    }
+
+    #[test]
+    fn wait_returns_once_focus_is_lost_and_regained() {
+        let window = FakeWindow::new("loss-and-regain");
+        let started = Instant::now();
+        let wait = begin_focus_wait(&window).unwrap();
+
+        window.emit(FocusEvent::Focused(false));
+        window.emit(FocusEvent::Focused(true));
+
+        assert_eq!(
+            block_on(wait.wait()).unwrap().unwrap(),
+            FocusWaitEnd::Returned
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn wait_ignores_focus_regained_before_it_was_lost() {
+        let window = FakeWindow::new("early-regain");
+        let wait = begin_focus_wait(&window).unwrap();
+
+        window.emit(FocusEvent::Focused(true));
+        window.emit(FocusEvent::Focused(false));
+        std::thread::spawn({
+            let window = Arc::new(window);
+            move || {
+                std::thread::sleep(Duration::from_millis(400));
+                window.emit(FocusEvent::Focused(true));
+            }
+        });
+
+        // Focus was lost before the grace period ended, so the wait lasts until
+        // it is regained instead of resolving as not shown.
+        assert_eq!(
+            block_on(wait.wait()).unwrap().unwrap(),
+            FocusWaitEnd::Returned
+        );
+    }
+
+    #[test]
+    fn wait_reports_a_window_that_kept_its_focus() {
+        let window = FakeWindow::new("kept-focus");
+        let wait = begin_focus_wait(&window).unwrap();
+
+        assert_eq!(
+            block_on(wait.wait()).unwrap().unwrap(),
+            FocusWaitEnd::NoFocusLoss
+        );
+    }
+
+    #[test]
+    fn wait_returns_when_the_window_is_destroyed() {
+        let window = FakeWindow::new("destroyed");
+        let wait = begin_focus_wait(&window).unwrap();
+
+        window.emit(FocusEvent::Focused(false));
+        window.emit(FocusEvent::Destroyed);
+
+        assert_eq!(
+            block_on(wait.wait()).unwrap().unwrap(),
+            FocusWaitEnd::Returned
+        );
+    }
+
+    #[test]
+    fn only_one_wait_per_window_at_a_time() {
+        let window = FakeWindow::new("concurrent");
+        let wait = begin_focus_wait(&window).unwrap();
+        assert!(begin_focus_wait(&window).is_err());
+
+        wait.cancel();
+        let wait = begin_focus_wait(&window).unwrap();
+        wait.cancel();
+        // The listener is registered once, however many waits the window had.
+        assert_eq!(window.handlers.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn sweep_removes_waiters_of_closed_windows() {
+        let mut waiters = HashMap::new();
+        for label in ["main", "closed", "sharing"] {
+            let waiter = Arc::new(FocusWaiter::new(label.to_string()));
+            waiters.insert(label.to_string(), waiter);
+        }
+        let (tx, _rx) = oneshot::channel();
+        *waiters["sharing"].pending.lock().unwrap() = Some(tx);
+
+        sweep_focus_waiters(&mut waiters, |label| label == "main");
+
+        let mut labels: Vec<_> = waiters.keys().map(String::as_str).collect();
+        labels.sort_unstable();
+        assert_eq!(labels, ["main", "sharing"]);
+    }
 }
@@ -1,21 +1,311 @@
-use tauri::{Runtime, State, Window};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::PathBuf;
+use std::process::Command;
+use tauri::{Manager, Runtime, Url, Webview, Window};
 
-use crate::models::{CanShareResult, ShareOptions};
-use crate::state::PluginTempFileManager;
-use crate::Error;
+use crate::models::{
+    CanShareResult, RecentTarget, ShareFallback, ShareOptions, ShareResult, SharedFile,
+};
+use crate::state::ShareFiles;
+use crate::{Config, Error};
+
+use super::common;
 
 pub fn share<R: Runtime>(
+    window: Window<R>,
+    options: ShareOptions,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    share_files.check_cancelled()?;
+    // There is no native share dialog on Linux, so nothing is ever shared.
+    if let Some(url) = fallback_url(&options)? {
+        xdg_open(url.as_str().as_ref())?;
+        return Ok(ShareResult::Fallback {
+            method: "openUrl".to_string(),
+        });
+    }
+    let first_file = options.files.as_deref().unwrap_or_default().first();
+    if let (Some(ShareFallback::OpenFile), Some(file)) = (options.fallback, first_file) {
+        let config = window.state::<Config>();
+        let path = match &file.path {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let temp_dir = common::get_plugin_temp_dir(&config)?;
+                let temp_files = common::DiskTempFiles::random_names(temp_dir, &config);
+                fallback_file_path(file, &share_files, &temp_files, config.image_max_dimension)?
+            }
+        };
+        xdg_open(path.as_os_str())?;
+        return Ok(ShareResult::Fallback {
+            method: "openFile".to_string(),
+        });
+    }
+    Ok(ShareResult::Cancelled)
+}
+
+/// Shares content like [`share`], which never waits for a dialog on Linux.
+pub async fn share_async<R: Runtime>(
+    window: Window<R>,
+    options: ShareOptions,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    share(window, options, share_files)
+}
+
+/// Returns the URL to open in the browser when the share asks for the
+/// `OpenUrl` fallback.
+///
+/// Only web URLs are opened, so a share cannot launch arbitrary handlers such as
+/// `file:` URLs.
+fn fallback_url(options: &ShareOptions) -> Result<Option<Url>, Error> {
+    let (Some(ShareFallback::OpenUrl), Some(url)) = (options.fallback, options.url.as_deref())
+    else {
+        return Ok(None);
+    };
+    let parsed =
+        Url::parse(url).map_err(|e| Error::InvalidArgs(format!("Invalid URL '{}': {}", url, e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(Error::InvalidArgs(format!(
+            "Only http and https URLs can be opened, got '{}'",
+            url
+        )));
+    }
+    Ok(Some(parsed))
+}
+
+/// Writes the content of `file`, sent as data, to `temp_files` for the `OpenFile`
+/// fallback and returns its path.
+///
+/// The app it is opened in may read it for as long as it is open, so it is kept
+/// past the share and its grace period, until the `cleanup` command or the app
+/// exits.
+fn fallback_file_path(
+    file: &SharedFile,
+    share_files: &ShareFiles,
+    temp_files: &impl common::TempFileWriter,
+    image_max_dimension: Option<u32>,
+) -> Result<PathBuf, Error> {
+    let path = common::write_temp_file(temp_files, file, 0, image_max_dimension)?;
+    share_files.add_lasting_file(path.clone());
+    Ok(path)
+}
+
+/// Opens `target` with `xdg-open`, in the default application for its type.
+///
+/// `xdg-open` is waited for on a separate thread, so it does not linger as a
+/// zombie process once it exits.
+fn xdg_open(target: &OsStr) -> Result<(), Error> {
+    let mut child = Command::new("xdg-open")
+        .arg(target)
+        .spawn()
+        .map_err(|e| open_error(target, e))?;
+    let target = target.to_string_lossy().into_owned();
+    std::thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => {
+            eprintln!("xdg-open failed to open '{}': {}", target, status);
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to wait for xdg-open: {}", e),
+    });
+    Ok(())
+}
+
+/// Maps the failure to launch `xdg-open`, which is not installed everywhere.
+fn open_error(target: &OsStr, err: std::io::Error) -> Error {
+    if err.kind() == std::io::ErrorKind::NotFound {
+        Error::Unsupported("opening a share fallback requires xdg-open".to_string())
+    } else {
+        Error::NativeApi(format!(
+            "Failed to open '{}': {}",
+            target.to_string_lossy(),
+            err
+        ))
+    }
+}
+
+pub fn share_to<R: Runtime>(
+    window: Window<R>,
+    options: ShareOptions,
+    _service: &str,
+    _service_options: &HashMap<String, String>,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    share(window, options, share_files)
+}
+
+/// Shares content like [`share_to`], which never waits for a dialog on Linux.
+pub async fn share_to_async<R: Runtime>(
+    window: Window<R>,
+    options: ShareOptions,
+    service: &str,
+    service_options: &HashMap<String, String>,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    share_to(window, options, service, service_options, share_files)
+}
+
+/// Linux has no sharing services to perform directly, so this is not supported.
+pub fn perform_service<R: Runtime>(
     _window: Window<R>,
     _options: ShareOptions,
-    _state: State<'_, PluginTempFileManager>,
-) -> Result<(), Error> {
-    Ok(())
+    _service: &str,
+    _service_options: &HashMap<String, String>,
+    _share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    Err(Error::Unsupported(
+        "performing a sharing service is not supported on Linux".to_string(),
+    ))
 }
 
+/// Printing is not supported on Linux, which has no share dialog to print from.
+pub fn print<R: Runtime>(
+    _window: Window<R>,
+    _file: SharedFile,
+    _share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    Err(Error::Unsupported(
+        "printing is not supported on Linux".to_string(),
+    ))
+}
+
+/// Printing is not supported on Linux, see [`print`].
+pub async fn print_async<R: Runtime>(
+    window: Window<R>,
+    file: SharedFile,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    print(window, file, share_files)
+}
+
+/// Capturing the window is not supported on Linux, which has no share dialog to
+/// send the screenshot to.
+pub fn capture_window<R: Runtime>(_webview: &Webview<R>) -> Result<Vec<u8>, Error> {
+    Err(Error::Unsupported(
+        "capturing the window is not supported on Linux".to_string(),
+    ))
+}
+
+/// Linux has no native share dialog.
 pub fn can_share() -> Result<CanShareResult, Error> {
-    Ok(CanShareResult { value: false })
+    Ok(common::can_share_result(false))
 }
 
-pub fn cleanup() -> Result<(), Error> {
+/// Recent targets are not supported on Linux, which has no share dialog and so no
+/// history of share targets.
+pub fn recent_targets() -> Result<Vec<RecentTarget>, Error> {
+    Ok(Vec::new())
+}
+
+pub fn cleanup(config: &Config) -> Result<(), Error> {
+    common::cleanup_plugin_temp_dir(config)
+}
+
+pub fn temp_dir(config: &Config) -> Result<PathBuf, Error> {
+    common::get_plugin_temp_dir(config)
+}
+
+/// Linux shares write at most the one file opened by the `OpenFile` fallback,
+/// so any share fits.
+pub fn check_disk_space(_required: u64, _config: &Config) -> Result<(), Error> {
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{fallback_file_path, fallback_url, open_error, recent_targets};
+    use crate::models::{ShareFallback, ShareMode, ShareOptions, SharedFile};
+    use crate::platform::common::{self, MemoryTempFiles};
+    use crate::state::PluginTempFileManager;
+    use crate::{Config, Error};
+
+    fn options(fallback: Option<ShareFallback>, url: &str) -> ShareOptions {
+        ShareOptions {
+            url: Some(url.to_string()),
+            fallback,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fallback_url_requires_the_open_url_fallback() {
+        assert!(fallback_url(&options(None, "https://example.com"))
+            .unwrap()
+            .is_none());
+        let url = fallback_url(&options(
+            Some(ShareFallback::OpenUrl),
+            "https://example.com",
+        ));
+        assert_eq!(url.unwrap().unwrap().as_str(), "https://example.com/");
+    }
+
+    #[test]
+    fn fallback_url_only_opens_web_urls() {
+        for url in ["file:///etc/passwd", "not a url"] {
+            assert!(
+                matches!(
+                    fallback_url(&options(Some(ShareFallback::OpenUrl), url)),
+                    Err(Error::InvalidArgs(_))
+                ),
+                "expected '{}' to be rejected",
+                url
+            );
+        }
+    }
+
+    #[test]
+    fn fallback_file_is_written_and_kept_past_the_share() {
+        let manager = PluginTempFileManager::new();
+        let share_files = manager.begin_share();
+        let file = SharedFile::from_reader("notes.txt", "text/plain", &b"hi"[..]);
+
+        let config = Config::default();
+        let temp_files = common::DiskTempFiles::random_names(
+            common::get_plugin_temp_dir(&config).unwrap(),
+            &config,
+        );
+        let path = fallback_file_path(&file, &share_files, &temp_files, None).unwrap();
+        share_files.finish(ShareMode::Copy);
+        share_files.release();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"hi");
+        assert!(path.to_string_lossy().ends_with("-notes.txt"));
+        assert!(manager.is_managed(&path));
+        assert!(manager.release_file(&path));
+    }
+
+    #[test]
+    fn fallback_file_writes_the_data_of_the_file() {
+        let manager = PluginTempFileManager::new();
+        let share_files = manager.begin_share();
+        let temp_files = MemoryTempFiles::default();
+        let file = SharedFile::from_reader("../notes.txt", "text/plain", &b"hi"[..]);
+
+        let path = fallback_file_path(&file, &share_files, &temp_files, None).unwrap();
+
+        assert_eq!(
+            temp_files.written(),
+            vec![("notes.txt".to_string(), b"hi".to_vec())]
+        );
+        assert!(manager.is_managed(&path));
+    }
+
+    #[test]
+    fn missing_xdg_open_is_unsupported() {
+        let not_found = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert!(matches!(
+            open_error("notes.txt".as_ref(), not_found),
+            Error::Unsupported(_)
+        ));
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(matches!(
+            open_error("notes.txt".as_ref(), denied),
+            Error::NativeApi(_)
+        ));
+    }
+
+    #[test]
+    fn recent_targets_is_empty() {
+        assert!(recent_targets().unwrap().is_empty());
+    }
+}
@@ -1,38 +1,90 @@
 use objc2::Message;
 use crate::models::CanShareResult;
-use crate::state::PluginTempFileManager;
-use crate::{Error, ShareOptions, SharedFile};
-use base64::{engine::general_purpose, Engine as _};
-use objc2::runtime::{AnyObject, ProtocolObject};
+use crate::state::ShareFiles;
+use crate::validation::{check_shared_path, link_url, merge_text_url, SharedPath};
+use crate::{
+    Config, Error, PreferredEdge, RecentTarget, ShareOptions, SharePresentation, ShareResult,
+    SharedFile,
+};
+use block2::RcBlock;
+use objc2::runtime::{AnyClass, AnyObject, ProtocolObject};
 use objc2::{
     define_class, msg_send,
     rc::{autoreleasepool, Retained},
     AnyThread, DefinedClass, MainThreadOnly,
 };
 use objc2_app_kit::{
+    NSBackingStoreType, NSBitmapImageFileType, NSBitmapImageRep, NSColor, NSCompositingOperation,
+    NSImage, NSImageRep, NSPDFImageRep, NSPrintOperation, NSResponder, NSScreen,
     NSSharingService, NSSharingServiceDelegate, NSSharingServicePicker,
-    NSSharingServicePickerDelegate, NSView,
+    NSSharingServicePickerDelegate, NSView, NSWindow, NSWindowStyleMask,
 };
 use objc2_core_foundation::{CGPoint, CGRect, CGSize};
 use objc2_foundation::{
-    NSArray, NSError, NSObject, NSObjectProtocol, NSString, NSURL, MainThreadMarker,
+    NSArray, NSData, NSDictionary, NSError, NSInteger, NSItemProvider,
+    NSItemProviderCompletionHandler, NSObject, NSObjectProtocol, NSRange, NSSecureCoding,
+    NSString, NSURL, MainThreadMarker,
 };
+use objc2_web_kit::WKWebView;
 use raw_window_handle::{HasWindowHandle, RawWindowHandle, WindowHandle};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::time::Duration;
-use std::{io::Write, path::Path, sync::mpsc};
-use tauri::{Runtime, State, Window};
-use tempfile::{Builder, NamedTempFile};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use tauri::{AppHandle, Manager, Runtime, Webview, Window};
+use zeroize::Zeroize;
+
+use tokio::sync::oneshot;
+
+use super::common;
 
 const SHARE_COMPLETION_TIMEOUT: Duration = Duration::from_secs(60);
 
+/// Uniform Type Identifiers of common MIME types, used for files without an extension.
+const MIME_TYPE_UTIS: &[(&str, &str)] = &[
+    ("text/plain", "public.plain-text"),
+    ("text/html", "public.html"),
+    ("text/csv", "public.comma-separated-values-text"),
+    ("application/json", "public.json"),
+    ("application/xml", "public.xml"),
+    ("application/pdf", "com.adobe.pdf"),
+    ("application/zip", "public.zip-archive"),
+    ("image/png", "public.png"),
+    ("image/jpeg", "public.jpeg"),
+    ("image/gif", "com.compuserve.gif"),
+    ("image/heic", "public.heic"),
+    ("image/svg+xml", "public.svg-image"),
+    ("audio/mpeg", "public.mp3"),
+    ("video/mp4", "public.mpeg-4"),
+    ("video/quicktime", "com.apple.quicktime-movie"),
+];
+
+/// Short names accepted by `share_to`, and the sharing services they stand for.
+const SERVICE_IDENTIFIERS: &[(&str, &str)] = &[
+    ("mail", crate::SERVICE_MAIL),
+    ("messages", crate::SERVICE_MESSAGES),
+    ("airdrop", crate::SERVICE_AIRDROP),
+    ("notes", crate::SERVICE_NOTES),
+    ("reminders", crate::SERVICE_REMINDERS),
+];
+
 thread_local! {
-    static ACTIVE_DELEGATES: RefCell<Vec<Retained<SharePickerDelegate>>> = RefCell::new(Vec::new());
+    static ACTIVE_DELEGATES: common::WindowShares<Retained<SharePickerDelegate>> =
+        const { common::WindowShares::new() };
 }
 
 #[derive(Default)]
 struct ShareDelegateIvars {
-    completion: RefCell<Option<mpsc::Sender<Result<(), Error>>>>,
+    completion: RefCell<Option<oneshot::Sender<Result<ShareResult, Error>>>>,
+    target: RefCell<Option<String>>,
+    /// The sheet the picker is anchored in, when presented as a sheet, or the
+    /// window anchoring it on a requested screen.
+    sheet: RefCell<Option<Retained<NSWindow>>>,
+    /// The id the delegate is kept alive under in `ACTIVE_DELEGATES`.
+    share_id: Cell<Option<u64>>,
+    /// The items of the shared files, with the identifier reported once accepted.
+    shared_files: RefCell<Vec<(Retained<NSObject>, String)>>,
 }
 
 define_class!(
@@ -59,8 +111,15 @@ define_class!(
             _picker: &NSSharingServicePicker,
             service: Option<&NSSharingService>,
         ) {
-            if service.is_none() {
-                self.complete(Ok(()));
+            // The window is blocked only while the picker is shown, the service
+            // presents its own UI.
+            self.end_sheet();
+            match service {
+                Some(service) => {
+                    let title = unsafe { service.title() }.to_string();
+                    *self.ivars().target.borrow_mut() = Some(title);
+                }
+                None => self.complete(Ok(ShareResult::Cancelled)),
             }
         }
     }
@@ -70,9 +129,22 @@ define_class!(
         fn sharing_service_did_share_items(
             &self,
             _service: &NSSharingService,
-            _items: &NSArray,
+            items: &NSArray,
         ) {
-            self.complete(Ok(()));
+            let target = self.ivars().target.borrow_mut().take();
+            let accepted: Vec<*const AnyObject> =
+                items.iter().map(|item| Retained::as_ptr(&item)).collect();
+            let files = self.ivars().shared_files.take();
+            let shared_files: Vec<_> = files
+                .iter()
+                .map(|(item, file)| (Retained::as_ptr(item).cast::<AnyObject>(), file.clone()))
+                .collect();
+            self.complete(Ok(ShareResult::Completed {
+                target,
+                failed_files: Vec::new(),
+                accepted_files: common::accepted_files(&shared_files, &accepted),
+                duplicates_removed: 0,
+            }));
         }
 
         #[unsafe(method(sharingService:didFailToShareItems:error:))]
@@ -94,97 +166,295 @@ define_class!(
 );
 
 impl SharePickerDelegate {
-    fn new(mtm: MainThreadMarker, completion: mpsc::Sender<Result<(), Error>>) -> Retained<Self> {
+    fn new(
+        mtm: MainThreadMarker,
+        completion: oneshot::Sender<Result<ShareResult, Error>>,
+    ) -> Retained<Self> {
         let ivars = ShareDelegateIvars {
             completion: RefCell::new(Some(completion)),
+            target: RefCell::new(None),
+            sheet: RefCell::new(None),
+            share_id: Cell::new(None),
+            shared_files: RefCell::new(Vec::new()),
         };
         let this = Self::alloc(mtm).set_ivars(ivars);
         unsafe { msg_send![super(this), init] }
     }
 
-    fn complete(&self, result: Result<(), Error>) {
+    fn complete(&self, result: Result<ShareResult, Error>) {
         if let Some(tx) = self.ivars().completion.borrow_mut().take() {
             let _ = tx.send(result);
         }
+        self.end_sheet();
         remove_active_delegate(self);
     }
+
+    /// Releases the delegate of a picker that failed to show, whose share reports
+    /// the failure instead of an outcome.
+    fn abandon(&self) {
+        self.ivars().completion.borrow_mut().take();
+        self.end_sheet();
+        remove_active_delegate(self);
+    }
+
+    /// Dismisses the sheet the picker was anchored in, if any.
+    fn end_sheet(&self) {
+        if let Some(sheet) = self.ivars().sheet.borrow_mut().take() {
+            match sheet.sheetParent() {
+                Some(parent) => parent.endSheet(&sheet),
+                None => sheet.orderOut(None),
+            }
+        }
+    }
 }
 
 fn remove_active_delegate(delegate: &SharePickerDelegate) {
-    let delegate_ptr = delegate as *const SharePickerDelegate;
-    ACTIVE_DELEGATES.with(|delegates| {
-        let mut list = delegates.borrow_mut();
-        if let Some(pos) = list
-            .iter()
-            .position(|item| Retained::as_ptr(item) == delegate_ptr)
-        {
-            list.remove(pos);
+    if let Some(id) = delegate.ivars().share_id.take() {
+        ACTIVE_DELEGATES.with(|delegates| delegates.finish(id));
+    }
+}
+
+/// Dismisses the share UI of the window `label` when it is destroyed mid-share.
+///
+/// The picker is anchored to a view of the window, so its delegate is completed as
+/// cancelled and released, along with the sheet the picker may be shown in, rather
+/// than being left pointing at the closed window.
+pub fn window_destroyed<R: Runtime>(app: &AppHandle<R>, label: &str) {
+    let label = label.to_string();
+    let result = app.run_on_main_thread(move || {
+        let delegates = ACTIVE_DELEGATES.with(|delegates| delegates.window_destroyed(&label));
+        for delegate in delegates {
+            delegate.complete(Ok(ShareResult::Cancelled));
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to release the share of the destroyed window: {}", e);
+    }
+}
+
+/// Ends every share in progress, e.g. when the app cancels them all.
+///
+/// Each delegate is completed as cancelled, which dismisses the sheet its picker
+/// is shown in. A picker popover is owned by AppKit and cannot be closed from the
+/// app, so it stays open until the user dismisses it, without reporting a result.
+/// Returns once the delegates are completed, or the setup timeout elapsed.
+pub fn cancel_all<R: Runtime>(app: &AppHandle<R>) {
+    let timeout = app.state::<Config>().setup_timeout();
+    let result = common::run_on_main_thread_and_wait(app, timeout, || {
+        let delegates = ACTIVE_DELEGATES.with(|delegates| delegates.take_all());
+        for delegate in delegates {
+            delegate.complete(Ok(ShareResult::Cancelled));
         }
     });
+    if let Err(e) = result {
+        eprintln!("Failed to release the shares in progress: {}", e);
+    }
 }
 
-pub fn cleanup() -> Result<(), Error> {
-    // macOS file cleanup is handled by the shared `PluginTempFileManager` in desktop::Share::cleanup
-    // and on plugin drop. Nothing additional is required here.
-    Ok(())
+pub fn cleanup(config: &Config) -> Result<(), Error> {
+    common::cleanup_plugin_temp_dir(config)
 }
 
+/// On macOS, we can always share as long as the sharing service is available.
 pub fn can_share() -> Result<CanShareResult, Error> {
-    // On macOS, we can always share as long as the sharing service is available.
-    Ok(CanShareResult { value: true })
+    Ok(common::can_share_result(true))
+}
+
+/// `NSSharingServicePicker` orders its services itself and does not expose which
+/// were used recently, so no recent targets are reported.
+pub fn recent_targets() -> Result<Vec<RecentTarget>, Error> {
+    Ok(Vec::new())
+}
+
+pub fn temp_dir(config: &Config) -> Result<PathBuf, Error> {
+    common::get_plugin_temp_dir(config)
+}
+
+/// Fails with `TempFile` when `required` bytes do not fit in the temp dir.
+pub fn check_disk_space(required: u64, config: &Config) -> Result<(), Error> {
+    let dir = common::get_plugin_temp_dir(config)?;
+    let dir = std::ffi::CString::new(dir.as_os_str().as_encoded_bytes())
+        .map_err(|e| Error::TempFile(format!("Invalid temp dir path: {}", e)))?;
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(dir.as_ptr(), &mut stats) } != 0 {
+        return Err(Error::TempFile(format!(
+            "Failed to query the free disk space: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    let available = stats.f_bavail as u64 * stats.f_frsize as u64;
+    common::check_disk_space(required, available)
 }
 
 /// Shares content using the native macOS sharing service.
 pub fn share<R: Runtime>(
     window: Window<R>,
     options: ShareOptions,
-    state: State<'_, PluginTempFileManager>,
-) -> Result<(), Error> {
-    let (setup_tx, setup_rx) = mpsc::channel();
-    let (completion_tx, completion_rx) = mpsc::channel();
-    let window_clone = window.clone();
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    common::block_on(share_async(window, options, share_files))?
+}
 
-    let managed_files = state.inner().managed_files.clone();
+/// Shares content like [`share`], without blocking a thread until the share completes.
+pub async fn share_async<R: Runtime>(
+    window: Window<R>,
+    options: ShareOptions,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    present_share(window, options, share_files, None).await
+}
+
+/// Shares content directly to the named sharing service, skipping the picker.
+///
+/// Falls back to the picker when the service is unknown or cannot handle the items.
+pub fn share_to<R: Runtime>(
+    window: Window<R>,
+    options: ShareOptions,
+    service: &str,
+    service_options: &HashMap<String, String>,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    common::block_on(share_to_async(
+        window,
+        options,
+        service,
+        service_options,
+        share_files,
+    ))?
+}
+
+/// Shares content like [`share_to`], without blocking a thread until the share completes.
+pub async fn share_to_async<R: Runtime>(
+    window: Window<R>,
+    options: ShareOptions,
+    service: &str,
+    service_options: &HashMap<String, String>,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    let target = ServiceTarget {
+        service: service.to_string(),
+        options: service_options.clone(),
+        picker_fallback: true,
+    };
+    present_share(window, options, share_files, Some(target)).await
+}
 
-    if let Err(e) = window.run_on_main_thread(move || {
-        let result = (|| -> Result<(), Error> {
+/// Performs the named sharing service with the items of `options`, without a picker.
+///
+/// Unlike [`share_to`], this fails with [`Error::NativeApi`] when the service is
+/// unknown or cannot perform the items. It resolves with the outcome the service
+/// reports to its delegate.
+pub fn perform_service<R: Runtime>(
+    window: Window<R>,
+    options: ShareOptions,
+    service: &str,
+    service_options: &HashMap<String, String>,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    let target = ServiceTarget {
+        service: service.to_string(),
+        options: service_options.clone(),
+        picker_fallback: false,
+    };
+    common::block_on(present_share(window, options, share_files, Some(target)))?
+}
+
+/// The sharing service requested by `share_to` or `perform_service`.
+struct ServiceTarget {
+    service: String,
+    options: HashMap<String, String>,
+    /// Whether the picker is shown when the service cannot perform the items.
+    picker_fallback: bool,
+}
+
+async fn present_share<R: Runtime>(
+    window: Window<R>,
+    mut options: ShareOptions,
+    share_files: ShareFiles,
+    target: Option<ServiceTarget>,
+) -> Result<ShareResult, Error> {
+    let config = window.state::<Config>().inner().clone();
+    check_disk_space(crate::validation::estimate_disk_usage(&options), &config)?;
+    let temp_dir = common::get_plugin_temp_dir(&config)?;
+    if let Some(files) = options.files.as_mut() {
+        common::resolve_name_collisions(files, options.name_collision.unwrap_or_default())?;
+    }
+    let preview_image = options
+        .preview_image
+        .take()
+        .map(|preview| common::read_file_data(&preview, None))
+        .transpose()?;
+    let (setup_tx, mut setup_rx) = oneshot::channel();
+    let (completion_tx, completion_rx) = oneshot::channel();
+    let window_clone = window.clone();
+    let zeroize_data = options.zeroize_data.unwrap_or(false);
+    let link = link_url(&options)?;
+    let preferred_edge = rect_edge(options.preferred_edge.unwrap_or_default());
+    let presentation = options.presentation.unwrap_or_default();
+    let screen = common::requested_screen_for(&window, options.screen);
+    let share_files_for_release = share_files.clone();
+
+    let dispatched = window.run_on_main_thread(move || {
+        let temp_files = common::DiskTempFiles::random_names(temp_dir, &config);
+        let result = common::catch_panic(|| -> Result<(), Error> {
             let ns_view = get_ns_view(&window_clone)?;
             let mut items_to_share: Vec<Retained<NSObject>> = Vec::new();
 
-            let temp_file_manager_clone = managed_files.clone();
+            let combined_text = merge_text_url(&options);
 
-            let combined_text = match (options.text, options.url) {
-                (Some(t), Some(u)) => format!("{}\n{}", t, u),
-                (Some(t), None) => t,
-                (None, Some(u)) => u,
-                (None, None) => String::new(),
+            // A URL shared without text is a first-class link item, so targets can
+            // show a rich preview of it.
+            let link_item =
+                link.and_then(|link| NSURL::URLWithString(&NSString::from_str(link.as_str())));
+            let text_item = |text: &str| -> Retained<NSObject> {
+                let text = NSString::from_str(text);
+                if options.text_is_plain == Some(true) {
+                    plain_text_item(&text)
+                } else {
+                    unsafe { Retained::cast_unchecked(text) }
+                }
             };
-
-            if !combined_text.is_empty() {
-                items_to_share
-                    .push(unsafe { Retained::cast_unchecked(NSString::from_str(&combined_text)) });
+            if let Some(url) = link_item {
+                items_to_share.push(link_with_preview(url, preview_image.as_deref()));
+            } else if !combined_text.is_empty() {
+                items_to_share.push(text_item(&combined_text));
+            }
+            // Each text item is a separate item, so receivers can handle them apart.
+            for text in options.text_items.iter().flatten().filter(|text| !text.is_empty()) {
+                items_to_share.push(text_item(text));
             }
 
+            let mut shared_files = Vec::new();
             if let Some(files) = options.files {
-                for file in files {
-                    let temp_file_named = create_temp_file_for_data(&file)?;
-                    let temp_path = temp_file_named.into_temp_path();
-                    let path_buf = temp_path.keep()?;
+                for (index, mut file) in files.into_iter().enumerate() {
+                    // Files shared by path are used in place and are not ours to clean up.
+                    if let Some(path) = &file.path {
+                        let is_dir = check_shared_path(path)? == SharedPath::Directory;
+                        let path = NSString::from_str(path);
+                        let url = unsafe { NSURL::fileURLWithPath_isDirectory(&path, is_dir) };
+                        let item = file_item(url, &file, common::image_dimensions(&file, None));
+                        shared_files.push((item.clone(), common::file_identifier(&file)));
+                        items_to_share.push(item);
+                        continue;
+                    }
+
+                    share_files.check_cancelled()?;
+                    let image_max_dimension = config.image_max_dimension;
+                    let path_buf =
+                        common::write_temp_file(&temp_files, &file, index, image_max_dimension)?;
+                    if zeroize_data {
+                        file.data.zeroize();
+                    }
 
                     let path_str = path_buf.to_string_lossy().to_string();
                     let url = unsafe { NSURL::fileURLWithPath(&NSString::from_str(&path_str)) };
-                    items_to_share.push(unsafe { Retained::cast_unchecked(url) });
-
-                    if let Err(e) = temp_file_manager_clone
-                        .lock()
-                        .map_err(|e| format!("Failed to lock mutex: {}", e))
-                        .and_then(|mut files| {
-                            files.push(path_buf);
-                            Ok(())
-                        })
-                    {
-                        eprintln!("Failed to add file to managed list: {}", e);
-                    }
+                    // The written image is measured, as it may have been downscaled.
+                    let dimensions = common::image_dimensions(&file, Some(path_buf.as_path()));
+                    let item = file_item(url, &file, dimensions);
+                    shared_files.push((item.clone(), common::file_identifier(&file)));
+                    items_to_share.push(item);
+
+                    share_files.add_file(path_buf);
                 }
             }
 
@@ -193,97 +463,588 @@ pub fn share<R: Runtime>(
                     "No content provided to share.".to_string(),
                 ));
             }
+            share_files.check_cancelled()?;
 
-            autoreleasepool(|_pool| {
+            autoreleasepool(|_pool| -> Result<(), Error> {
                 let objects_refs: Vec<&AnyObject> = items_to_share
                     .iter()
                     .map(|obj| obj.as_ref() as &AnyObject)
                     .collect();
                 let items_array = NSArray::from_slice(&objects_refs);
-                let picker = unsafe {
-                    NSSharingServicePicker::initWithItems(
-                        NSSharingServicePicker::alloc(),
-                        &*items_array,
-                    )
-                };
 
-                let mtm = MainThreadMarker::new().expect("Main thread marker");
+                let mtm = MainThreadMarker::new().ok_or_else(|| {
+                    Error::NativeApi("The share UI must be shown on the main thread".to_string())
+                })?;
                 let delegate = SharePickerDelegate::new(mtm, completion_tx);
-                ACTIVE_DELEGATES.with(|delegates| delegates.borrow_mut().push(delegate.retain()));
-                unsafe { picker.setDelegate(Some(ProtocolObject::from_ref(&*delegate))) };
-
-                let bounds = ns_view.bounds();
-                unsafe {
-                    picker.showRelativeToRect_ofView_preferredEdge(
-                        CGRect {
-                            origin: CGPoint {
-                                x: bounds.size.width / 2.0,
-                                y: bounds.size.height / 2.0,
-                            },
-                            size: CGSize {
-                                width: 0.0,
-                                height: 0.0,
-                            },
+                *delegate.ivars().shared_files.borrow_mut() = shared_files;
+                let share_id = ACTIVE_DELEGATES
+                    .with(|delegates| delegates.begin(window_clone.label(), delegate.retain()));
+                delegate.ivars().share_id.set(Some(share_id));
+
+                // A picker that fails to show never calls its delegate, so the
+                // failure rejects the share instead.
+                let present = || -> Result<(), Error> {
+                    let service = match &target {
+                        Some(target) => match requested_service(target, &items_array) {
+                            Ok(service) => Some(service),
+                            Err(e) if !target.picker_fallback => return Err(e),
+                            Err(e) => {
+                                eprintln!("{}, showing the share picker instead", e);
+                                None
+                            }
                         },
-                        &ns_view,
-                        objc2_foundation::NSRectEdge::NSMinYEdge,
-                    );
+                        None => None,
+                    };
+                    if let Some(service) = service {
+                        *delegate.ivars().target.borrow_mut() =
+                            Some(unsafe { service.title() }.to_string());
+                        let service_delegate = ProtocolObject::from_ref(&*delegate);
+                        unsafe { service.setDelegate(Some(service_delegate)) };
+                        return catch_exception("perform the share service", || unsafe {
+                            service.performWithItems(&items_array)
+                        });
+                    }
+
+                    let picker = catch_exception("create the share picker", || unsafe {
+                        NSSharingServicePicker::initWithItems(
+                            NSSharingServicePicker::alloc(),
+                            &*items_array,
+                        )
+                    })?;
+
+                    unsafe { picker.setDelegate(Some(ProtocolObject::from_ref(&*delegate))) };
+
+                    let mut anchor = ns_view.clone();
+                    // A sheet is attached to the window, so a requested screen prevails.
+                    if let Some(screen) = screen {
+                        if let Some(screen_anchor) = show_screen_anchor(mtm, &screen) {
+                            if let Some(content_view) = screen_anchor.contentView() {
+                                anchor = content_view;
+                            }
+                            *delegate.ivars().sheet.borrow_mut() = Some(screen_anchor);
+                        }
+                    } else if presentation == SharePresentation::Sheet {
+                        if let Some(sheet) = begin_picker_sheet(mtm, &ns_view) {
+                            if let Some(content_view) = sheet.contentView() {
+                                anchor = content_view;
+                            }
+                            *delegate.ivars().sheet.borrow_mut() = Some(sheet);
+                        }
+                    }
+
+                    if anchor.window().is_none() {
+                        return Err(Error::NativeApi(
+                            "Failed to show the share picker: the view is not in a window"
+                                .to_string(),
+                        ));
+                    }
+                    let bounds = anchor.bounds();
+                    catch_exception("show the share picker", || unsafe {
+                        picker.showRelativeToRect_ofView_preferredEdge(
+                            CGRect {
+                                origin: CGPoint {
+                                    x: bounds.size.width / 2.0,
+                                    y: bounds.size.height / 2.0,
+                                },
+                                size: CGSize {
+                                    width: 0.0,
+                                    height: 0.0,
+                                },
+                            },
+                            &anchor,
+                            preferred_edge,
+                        )
+                    })
+                };
+                present().inspect_err(|_| delegate.abandon())
+            })
+        });
+        let _ = setup_tx.send(result);
+    });
+    common::check_dispatched(dispatched, &share_files_for_release)?;
+
+    let setup_timeout = window.state::<Config>().setup_timeout();
+    if let Err(err) = common::recv_setup_result(&mut setup_rx, setup_timeout).await {
+        if matches!(err, Error::Timeout(_)) {
+            common::release_after_late_share(share_files_for_release, async move {
+                if let Ok(Ok(())) = setup_rx.await {
+                    let _ = completion_rx.await;
                 }
             });
+        }
+        return Err(err);
+    }
+
+    // Without a delegate callback the outcome is unknown; keep resolving the share as
+    // completed, as before, rather than reporting a cancellation that may not have happened.
+    match common::recv_timeout(completion_rx, SHARE_COMPLETION_TIMEOUT).await {
+        Ok(result) => result,
+        Err(_) => Ok(ShareResult::Completed {
+            target: None,
+            failed_files: Vec::new(),
+            accepted_files: Vec::new(),
+            duplicates_removed: 0,
+        }),
+    }
+}
+
+/// Captures the visible content of the webview as a PNG image.
+///
+/// Web content is rendered out of process, so it is snapshotted through the
+/// `WKWebView` rather than read back from the window.
+pub fn capture_window<R: Runtime>(webview: &Webview<R>) -> Result<Vec<u8>, Error> {
+    let (tx, rx) = mpsc::channel();
+    let setup_tx = tx.clone();
+
+    webview.with_webview(move |platform_webview| {
+        let result = common::catch_panic(|| -> Result<(), Error> {
+            let web_view = unsafe { &*platform_webview.inner().cast::<WKWebView>() };
+            let handler = RcBlock::new(move |image: *mut NSImage, error: *mut NSError| {
+                let result = match unsafe { image.as_ref() } {
+                    Some(image) => png_data(image),
+                    None => Err(Error::NativeApi(format!(
+                        "Failed to snapshot the webview: {}",
+                        unsafe { error.as_ref() }
+                            .map(|error| error.localizedDescription().to_string())
+                            .unwrap_or_default()
+                    ))),
+                };
+                let _ = tx.send(result);
+            });
+            unsafe { web_view.takeSnapshotWithConfiguration_completionHandler(None, &handler) };
             Ok(())
-        })();
-        let _ = setup_tx.send(result);
-    }) {
-        return Err(e.into());
+        });
+        if let Err(e) = result {
+            let _ = setup_tx.send(Err(e));
+        }
+    })?;
+
+    let timeout = webview.state::<Config>().setup_timeout();
+    match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(Error::Timeout(format!(
+            "the webview was not captured within {:?}",
+            timeout
+        ))),
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(mpsc::RecvError.into()),
     }
+}
 
-    let share_result = setup_rx.recv()?;
-    if let Err(err) = share_result {
-        return Err(err);
+/// Encodes a snapshot as PNG data.
+fn png_data(image: &NSImage) -> Result<Vec<u8>, Error> {
+    let encode_error = || Error::NativeApi("Failed to encode the snapshot as PNG".to_string());
+    let tiff = image.TIFFRepresentation().ok_or_else(encode_error)?;
+    let bitmap = NSBitmapImageRep::imageRepWithData(&tiff).ok_or_else(encode_error)?;
+    let properties = NSDictionary::<NSString, AnyObject>::new();
+    let png = unsafe {
+        bitmap.representationUsingType_properties(NSBitmapImageFileType::PNG, &properties)
     }
+    .ok_or_else(encode_error)?;
+    Ok(png.to_vec())
+}
+
+/// Sends a PDF document or an image to the print service, through the print panel.
+///
+/// A file shared by path is printed in place, otherwise it is written to a
+/// temporary file registered for cleanup. The panel runs modally on the main
+/// thread, so its outcome is known once it is dismissed.
+pub fn print<R: Runtime>(
+    window: Window<R>,
+    file: SharedFile,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    common::block_on(print_async(window, file, share_files))?
+}
+
+/// Prints like [`print`], without blocking a thread while the print panel is shown.
+pub async fn print_async<R: Runtime>(
+    window: Window<R>,
+    file: SharedFile,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    let path = match &file.path {
+        Some(path) => PathBuf::from(path),
+        None => {
+            let config = window.state::<Config>();
+            let temp_files =
+                common::DiskTempFiles::random_names(common::get_plugin_temp_dir(&config)?, &config);
+            let path = common::write_temp_file(&temp_files, &file, 0, None)?;
+            share_files.add_file(path.clone());
+            path
+        }
+    };
 
-    match completion_rx.recv_timeout(SHARE_COMPLETION_TIMEOUT) {
+    let (tx, rx) = oneshot::channel();
+    let dispatched = window.run_on_main_thread(move || {
+        let result = common::catch_panic(|| -> Result<ShareResult, Error> {
+            let mtm = MainThreadMarker::new().ok_or_else(|| {
+                Error::NativeApi("The print panel must be shown on the main thread".to_string())
+            })?;
+            let path = NSString::from_str(&path.to_string_lossy());
+            let rep = NSImageRep::imageRepWithContentsOfFile(&path).ok_or_else(|| {
+                Error::NativeApi(format!("Failed to load '{}' for printing", file.name))
+            })?;
+            let view = PrintPagesView::new(mtm, rep);
+            let operation = NSPrintOperation::printOperationWithView(&view);
+            operation.setJobTitle(Some(&NSString::from_str(&file.name)));
+            if operation.runOperation() {
+                Ok(ShareResult::Completed {
+                    target: Some("Print".to_string()),
+                    failed_files: Vec::new(),
+                    accepted_files: vec![common::file_identifier(&file)],
+                    duplicates_removed: 0,
+                })
+            } else {
+                Ok(ShareResult::Cancelled)
+            }
+        });
+        let _ = tx.send(result);
+    });
+    common::check_dispatched(dispatched, &share_files)?;
+
+    match rx.await {
         Ok(result) => result,
-        Err(mpsc::RecvTimeoutError::Timeout) => Ok(()),
-        Err(mpsc::RecvTimeoutError::Disconnected) => Ok(()),
+        Err(_) => Err(mpsc::RecvError.into()),
     }
 }
 
+/// The printed document, and the size and number of its pages.
+struct PrintPagesIvars {
+    rep: Retained<NSImageRep>,
+    page_size: CGSize,
+    page_count: NSInteger,
+}
+
+define_class!(
+    /// Lays the pages of a PDF document, or a single image, out top to bottom so
+    /// `NSPrintOperation` prints one per sheet.
+    #[unsafe(super(NSView, NSResponder, NSObject))]
+    #[thread_kind = MainThreadOnly]
+    #[ivars = PrintPagesIvars]
+    struct PrintPagesView;
+
+    unsafe impl NSObjectProtocol for PrintPagesView {}
+
+    impl PrintPagesView {
+        #[unsafe(method(isFlipped))]
+        fn is_flipped(&self) -> bool {
+            true
+        }
+
+        #[unsafe(method(knowsPageRange:))]
+        fn knows_page_range(&self, range: *mut NSRange) -> bool {
+            if let Some(range) = unsafe { range.as_mut() } {
+                *range = NSRange::new(1, self.ivars().page_count as usize);
+            }
+            true
+        }
+
+        #[unsafe(method(rectForPage:))]
+        fn rect_for_page(&self, page: NSInteger) -> CGRect {
+            self.page_rect(page)
+        }
+
+        #[unsafe(method(drawRect:))]
+        fn draw_rect(&self, dirty_rect: CGRect) {
+            let ivars = self.ivars();
+            let index = (dirty_rect.origin.y / ivars.page_size.height).floor() as NSInteger;
+            let index = index.clamp(0, ivars.page_count - 1);
+            if let Some(pdf) = ivars.rep.downcast_ref::<NSPDFImageRep>() {
+                pdf.setCurrentPage(index);
+            }
+            unsafe {
+                ivars.rep.drawInRect_fromRect_operation_fraction_respectFlipped_hints(
+                    self.page_rect(index + 1),
+                    CGRect::ZERO,
+                    NSCompositingOperation::SourceOver,
+                    1.0,
+                    true,
+                    None,
+                )
+            };
+        }
+    }
+);
+
+impl PrintPagesView {
+    fn new(mtm: MainThreadMarker, rep: Retained<NSImageRep>) -> Retained<Self> {
+        let (page_size, page_count) = match rep.downcast_ref::<NSPDFImageRep>() {
+            Some(pdf) => (pdf.bounds().size, pdf.pageCount().max(1)),
+            None => (rep.size(), 1),
+        };
+        let frame = CGRect {
+            origin: CGPoint { x: 0.0, y: 0.0 },
+            size: CGSize {
+                width: page_size.width,
+                height: page_size.height * page_count as f64,
+            },
+        };
+        let ivars = PrintPagesIvars {
+            rep,
+            page_size,
+            page_count,
+        };
+        let this = Self::alloc(mtm).set_ivars(ivars);
+        unsafe { msg_send![super(this), initWithFrame: frame] }
+    }
+
+    /// The rect of the 1-based `page` within the view.
+    fn page_rect(&self, page: NSInteger) -> CGRect {
+        let size = self.ivars().page_size;
+        CGRect {
+            origin: CGPoint {
+                x: 0.0,
+                y: size.height * (page.max(1) - 1) as f64,
+            },
+            size,
+        }
+    }
+}
+
+/// Wraps text in an `NSItemProvider` declared as `public.plain-text`, so receivers
+/// do not detect a link in it.
+fn plain_text_item(text: &NSString) -> Retained<NSObject> {
+    let provider = unsafe {
+        NSItemProvider::initWithItem_typeIdentifier(
+            NSItemProvider::alloc(),
+            Some(ProtocolObject::from_ref(text)),
+            Some(&NSString::from_str("public.plain-text")),
+        )
+    };
+    unsafe { Retained::cast_unchecked(provider) }
+}
+
+/// Builds the share item for a link, along with its preview image if any.
+///
+/// The preview is handed out by the preview image handler of an `NSItemProvider`
+/// holding the URL, so receivers show it on the link's card rather than as an
+/// attachment. Without a preview, or when it cannot be decoded, the bare URL is
+/// shared.
+fn link_with_preview(url: Retained<NSURL>, preview: Option<&[u8]>) -> Retained<NSObject> {
+    let image = preview
+        .and_then(|bytes| NSImage::initWithData(NSImage::alloc(), &NSData::with_bytes(bytes)));
+    let Some(image) = image else {
+        return unsafe { Retained::cast_unchecked(url) };
+    };
+
+    let provider = unsafe {
+        NSItemProvider::initWithItem_typeIdentifier(
+            NSItemProvider::alloc(),
+            Some(ProtocolObject::from_ref(&*url)),
+            Some(&NSString::from_str("public.url")),
+        )
+    };
+    let handler = RcBlock::new(
+        move |completion: NSItemProviderCompletionHandler,
+              _class: *const AnyClass,
+              _options: *mut NSDictionary| {
+            let image: &ProtocolObject<dyn NSSecureCoding> = ProtocolObject::from_ref(&*image);
+            if let Some(completion) = unsafe { completion.as_ref() } {
+                completion.call((image as *const _ as *mut _, std::ptr::null_mut()));
+            }
+        },
+    );
+    // The provider copies the handler, so it outlives the block created here.
+    unsafe { provider.setPreviewImageHandler(RcBlock::as_ptr(&handler)) };
+    unsafe { Retained::cast_unchecked(provider) }
+}
+
+/// Builds the share item for a file URL.
+///
+/// The URL is wrapped in an `NSItemProvider` declaring the file's UTType when the
+/// caller set one, or when the file has no extension to infer it from but its MIME
+/// type is known. Otherwise receivers infer the type from the extension. An image
+/// of known `dimensions` is wrapped too, so they become the preferred
+/// presentation size of its preview, and so is a file with a display name, which
+/// becomes its suggested name.
+fn file_item(
+    url: Retained<NSURL>,
+    file: &SharedFile,
+    dimensions: Option<(u32, u32)>,
+) -> Retained<NSObject> {
+    let uti = file.uti.as_deref().or_else(|| {
+        if Path::new(&file.name).extension().is_some() {
+            return None;
+        }
+        MIME_TYPE_UTIS
+            .iter()
+            .find(|(mime_type, _)| *mime_type == file.mime_type)
+            .map(|(_, uti)| *uti)
+    });
+
+    let needs_provider = dimensions.is_some() || file.display_name.is_some();
+    let provider = match uti {
+        Some(uti) => unsafe {
+            NSItemProvider::initWithItem_typeIdentifier(
+                NSItemProvider::alloc(),
+                Some(ProtocolObject::from_ref(&*url)),
+                Some(&NSString::from_str(uti)),
+            )
+        },
+        None if needs_provider => {
+            match unsafe { NSItemProvider::initWithContentsOfURL(NSItemProvider::alloc(), &url) } {
+                Some(provider) => provider,
+                None => return unsafe { Retained::cast_unchecked(url) },
+            }
+        }
+        None => return unsafe { Retained::cast_unchecked(url) },
+    };
+    if let Some((width, height)) = dimensions {
+        unsafe {
+            provider.setPreferredPresentationSize(CGSize {
+                width: f64::from(width),
+                height: f64::from(height),
+            })
+        };
+    }
+    // Receivers name the file after the suggested name rather than the temporary file.
+    if let Some(display_name) = &file.display_name {
+        unsafe { provider.setSuggestedName(Some(&NSString::from_str(display_name))) };
+    }
+    unsafe { Retained::cast_unchecked(provider) }
+}
+
+/// Attaches a transparent sheet to the window of `view`, to anchor the picker in.
+///
+/// The sheet makes the picker window-modal: the window does not take input until
+/// the sheet is ended by the picker delegate. Returns `None` when the view is not
+/// in a window, in which case the picker is shown as a popover.
+fn begin_picker_sheet(mtm: MainThreadMarker, view: &NSView) -> Option<Retained<NSWindow>> {
+    let parent = view.window()?;
+    let sheet = transparent_window(mtm, CGPoint { x: 0.0, y: 0.0 });
+    parent.beginSheet_completionHandler(&sheet, None);
+    Some(sheet)
+}
+
+/// Shows a transparent window at the center of `screen`, to anchor the picker in.
+///
+/// Tauri places monitors in physical pixels from the top-left corner of the
+/// primary screen, while Cocoa places windows in points from its bottom-left
+/// corner. The window is ordered out by the picker delegate once the share ends.
+fn show_screen_anchor(
+    mtm: MainThreadMarker,
+    screen: &common::ScreenRect,
+) -> Option<Retained<NSWindow>> {
+    let primary = NSScreen::screens(mtm).firstObject()?;
+    let (x, y) = screen.center();
+    let anchor = transparent_window(
+        mtm,
+        CGPoint {
+            x: f64::from(x) / screen.scale_factor,
+            y: primary.frame().size.height - f64::from(y) / screen.scale_factor,
+        },
+    );
+    anchor.orderFrontRegardless();
+    Some(anchor)
+}
+
+/// Creates a borderless, transparent 1x1 window at `origin`.
+fn transparent_window(mtm: MainThreadMarker, origin: CGPoint) -> Retained<NSWindow> {
+    let window = unsafe {
+        NSWindow::initWithContentRect_styleMask_backing_defer(
+            NSWindow::alloc(mtm),
+            CGRect {
+                origin,
+                size: CGSize {
+                    width: 1.0,
+                    height: 1.0,
+                },
+            },
+            NSWindowStyleMask::Borderless,
+            NSBackingStoreType::Buffered,
+            false,
+        )
+    };
+    unsafe { window.setReleasedWhenClosed(false) };
+    window.setOpaque(false);
+    window.setBackgroundColor(Some(&NSColor::clearColor()));
+    window
+}
+
+/// Runs `f`, turning an Objective-C exception it raises into an error.
+///
+/// AppKit raises exceptions on invalid share items or anchors, which would
+/// otherwise abort the app from the main thread.
+fn catch_exception<T>(action: &str, f: impl FnOnce() -> T) -> Result<T, Error> {
+    objc2::exception::catch(std::panic::AssertUnwindSafe(f)).map_err(|exception| {
+        let reason = exception
+            .map_or_else(|| "unknown exception".to_string(), |e| format!("{:?}", e));
+        Error::NativeApi(format!("Failed to {}: {}", action, reason))
+    })
+}
+
+/// Maps the requested picker edge to its `NSRectEdge`.
+fn rect_edge(edge: PreferredEdge) -> objc2_foundation::NSRectEdge {
+    match edge {
+        PreferredEdge::MinX => objc2_foundation::NSRectEdge::NSMinXEdge,
+        PreferredEdge::MinY => objc2_foundation::NSRectEdge::NSMinYEdge,
+        PreferredEdge::MaxX => objc2_foundation::NSRectEdge::NSMaxXEdge,
+        PreferredEdge::MaxY => objc2_foundation::NSRectEdge::NSMaxYEdge,
+    }
+}
+
+/// Looks up the service requested by `share_to` or `perform_service` and applies
+/// its options.
+///
+/// Fails when the service does not exist on this system or cannot share the
+/// items, so the caller can fall back to the picker.
+fn requested_service(
+    target: &ServiceTarget,
+    items: &NSArray,
+) -> Result<Retained<NSSharingService>, Error> {
+    let identifier = SERVICE_IDENTIFIERS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(&target.service))
+        .map_or(target.service.as_str(), |(_, identifier)| *identifier);
+
+    let service = unsafe { NSSharingService::sharingServiceNamed(&NSString::from_str(identifier)) }
+        .filter(|service| unsafe { service.canPerformWithItems(Some(items)) });
+    let Some(service) = service else {
+        return Err(Error::NativeApi(format!(
+            "Sharing service '{}' is unavailable or cannot share the items",
+            target.service
+        )));
+    };
+
+    for (key, value) in &target.options {
+        match key.as_str() {
+            "subject" => unsafe { service.setSubject(Some(&NSString::from_str(value))) },
+            "recipients" => {
+                let recipients: Vec<Retained<NSString>> = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|recipient| !recipient.is_empty())
+                    .map(NSString::from_str)
+                    .collect();
+                let recipients = NSArray::from_retained_slice(&recipients);
+                unsafe { service.setRecipients(Some(&recipients)) };
+            }
+            // The Reminders extension always asks for the list itself; there is no
+            // public API to preselect one.
+            "list" => eprintln!(
+                "The Reminders list cannot be preselected, ignoring '{}'",
+                value
+            ),
+            _ => eprintln!(
+                "Ignoring unsupported option '{}' for sharing service '{}'",
+                key, target.service
+            ),
+        }
+    }
+
+    Ok(service)
+}
+
 /// Retrieves the native `NSView` pointer from the Tauri window, compatible with `raw-window-handle`.
 fn get_ns_view<R: Runtime>(window: &Window<R>) -> Result<Retained<NSView>, Error> {
     let window_handle: WindowHandle<'_> = window.window_handle()?;
-    if let RawWindowHandle::AppKit(handle) = window_handle.as_raw() {
-        let ns_view_ptr = handle.ns_view.as_ptr();
-        let ns_view: Retained<NSView> = unsafe { Retained::retain(ns_view_ptr.cast()) }.unwrap();
-        Ok(ns_view)
-    } else {
-        Err(Error::NativeApi(
-            "Unsupported window handle type on macOS.".to_string(),
-        ))
+    match window_handle.as_raw() {
+        RawWindowHandle::AppKit(handle) => {
+            let ns_view_ptr = handle.ns_view.as_ptr();
+            let ns_view: Retained<NSView> =
+                unsafe { Retained::retain(ns_view_ptr.cast()) }.unwrap();
+            Ok(ns_view)
+        }
+        other => Err(common::unsupported_window_handle("AppKit", &other)),
     }
 }
-
-/// Creates a secure temporary file from Base64 data.
-fn create_temp_file_for_data(options: &SharedFile) -> Result<NamedTempFile, Error> {
-    let decoded_bytes = general_purpose::STANDARD
-        .decode(&options.data)
-        .map_err(|_| Error::InvalidArgs("Invalid Base64 data.".to_string()))?;
-    // Security: Sanitize the filename to prevent path traversal attacks.
-    let sanitized_name = Path::new(&options.name)
-        .file_name()
-        .ok_or_else(|| Error::InvalidArgs("Invalid file name.".to_string()))?
-        .to_str()
-        .ok_or_else(|| {
-            Error::InvalidArgs("File name contains invalid UTF-8 characters.".to_string())
-        })?;
-    let temp_dir = std::env::temp_dir();
-    let mut temp_file = Builder::new()
-        .prefix(&format!("{}-", uuid::Uuid::new_v4()))
-        .suffix(&format!("-{}", sanitized_name))
-        .tempfile_in(temp_dir)
-        .map_err(|e| Error::TempFile(format!("Failed to create temp file: {}", e)))?;
-    temp_file
-        .write_all(&decoded_bytes)
-        .map_err(|e| Error::TempFile(format!("Failed to write to temp file: {}", e)))?;
-    Ok(temp_file)
-}
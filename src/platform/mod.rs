@@ -1,3 +1,15 @@
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+mod common;
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+pub(crate) use self::common::block_on;
+#[cfg(any(target_os = "windows", target_os = "macos", target_os = "linux"))]
+pub use self::common::cleanup_orphans;
+#[cfg(all(
+    feature = "bench",
+    any(target_os = "windows", target_os = "macos", target_os = "linux")
+))]
+pub(crate) use self::common::write_file_data;
+
 #[cfg(target_os = "windows")]
 mod windows;
 #[cfg(target_os = "windows")]
@@ -1,36 +1,82 @@
-use crate::state::PluginTempFileManager;
-use crate::{CanShareResult, Error, ShareOptions, SharedFile};
-use base64::{engine::general_purpose, Engine as _};
+use crate::state::ShareFiles;
+use crate::validation::{merge_text_url, SharedPath};
+use crate::{
+    CanShareResult, Config, Error, RecentTarget, ShareErrorEvent, ShareOptions, ShareResult, SharedFile, SHARE_ERROR_EVENT,
+};
 use raw_window_handle::{HasWindowHandle, RawWindowHandle};
-use std::cell::RefCell;
-use std::fs::File;
-use std::io::Write;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::mpsc;
-use super::focus;
-use tauri::{Runtime, State, Window};
-use windows::ApplicationModel::DataTransfer::{DataRequestedEventArgs, DataTransferManager};
-use windows::Foundation::Uri;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
+use super::{common, focus};
+use super::focus::FocusWaitEnd;
+use tauri::{AppHandle, Emitter, Manager, Runtime, Webview, Window};
+use windows::ApplicationModel::DataTransfer::{
+    DataRequestDeferral, DataRequestedEventArgs, DataTransferManager,
+    TargetApplicationChosenEventArgs,
+};
+use windows::Foundation::{PropertyValue, Uri};
+use windows::Graphics::Imaging::{BitmapAlphaMode, BitmapEncoder, BitmapPixelFormat};
 use windows::Storage::IStorageItem;
+use windows::Storage::Streams::{
+    DataReader, DataWriter, InMemoryRandomAccessStream, RandomAccessStreamReference,
+};
 use windows::{
-    core::{Interface, HSTRING},
+    core::{w, IInspectable, Interface, HSTRING},
     Foundation::TypedEventHandler,
-    Storage::StorageFile,
+    Storage::{StorageFile, StorageFolder},
     Win32::{
-        Foundation::HWND,
-        System::WinRT::{RoInitialize, RO_INIT_SINGLETHREADED},
-        UI::Shell::IDataTransferManagerInterop,
+        Foundation::{HWND, POINT},
+        Graphics::Gdi::{
+            CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject, GetDC, GetDIBits,
+            ReleaseDC, SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+        },
+        Storage::{
+            FileSystem::GetDiskFreeSpaceExW,
+            Xps::{PrintWindow, PRINT_WINDOW_FLAGS, PW_CLIENTONLY},
+        },
+        System::WinRT::{
+            RoInitialize, RoUninitialize, RO_INIT_MULTITHREADED, RO_INIT_SINGLETHREADED,
+        },
+        UI::{
+            Shell::IDataTransferManagerInterop,
+            WindowsAndMessaging::{
+                CreateWindowExW, DestroyWindow, GetCursorPos, GetForegroundWindow, IsWindow,
+                SetForegroundWindow, ShowWindow, PW_RENDERFULLCONTENT, SW_SHOW, WS_EX_TOOLWINDOW,
+                WS_POPUP,
+            },
+        },
     },
 };
 use windows_collections::IIterable;
+use zeroize::Zeroize;
 
-// This thread-local holds the DataTransferManager and its event registration token, keeping them
-// alive for the duration of the asynchronous share operation. It's only accessible
-// on the main thread, which is safe for these non-thread-safe WinRT types.
+/// How often the foreground window is checked while a transient host is in use.
+const SHARE_HOST_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The custom data package property holding the `expires_at` hint of a share.
+const EXPIRES_AT_PROPERTY: &str = "ExpiresAt";
+
+// This thread-local holds the DataTransferManager and its event registration token, along with
+// the label of the window sharing, keeping them alive for the duration of the asynchronous share
+// operation. It's only accessible on the main thread, which is safe for these non-thread-safe
+// WinRT types.
 thread_local! {
-    static SHARE_STATE: RefCell<Option<(DataTransferManager, i64)>> = RefCell::new(None);
+    static SHARE_STATE: RefCell<Option<(DataTransferManager, i64, String)>> = RefCell::new(None);
+    static TARGET_CHOSEN_STATE: RefCell<Option<(DataTransferManager, i64)>> = RefCell::new(None);
+    static SHARE_HOST: RefCell<Option<HWND>> = RefCell::new(None);
+    static WINRT_INITIALIZED: Cell<bool> = const { Cell::new(false) };
 }
 
+/// The deferrals of data requests whose files are still loading.
+static PENDING_DEFERRALS: common::WindowShares<DataRequestDeferral> = common::WindowShares::new();
+
+/// The thread that initialized WinRT for the share UI, which holds the WinRT
+/// state of the shares in its thread-locals.
+static WINRT_THREAD: Mutex<Option<ThreadId>> = Mutex::new(None);
+
 // A helper to map the detailed windows::core::Error into our plugin's simpler error type.
 impl From<windows::core::Error> for Error {
     fn from(err: windows::core::Error) -> Self {
@@ -38,51 +84,158 @@ impl From<windows::core::Error> for Error {
     }
 }
 
-pub fn cleanup() -> Result<(), Error> {
-    let temp_dir = get_plugin_temp_dir()?;
-    if temp_dir.exists() {
-        std::fs::remove_dir_all(temp_dir)
-            .map_err(|e| Error::TempFile(format!("Failed to cleanup temp dir: {}", e)))?;
-    }
-    Ok(())
+pub fn cleanup(config: &Config) -> Result<(), Error> {
+    common::remove_plugin_temp_dir(config)
 }
 
+/// The share UI is available on every supported Windows version.
 pub fn can_share() -> Result<CanShareResult, Error> {
-    Ok(CanShareResult { value: true })
+    Ok(common::can_share_result(true))
+}
+
+/// Recent targets are not supported on Windows: the share UI keeps its history of
+/// recent targets to itself, and `DataTransferManager` offers no way to read it.
+pub fn recent_targets() -> Result<Vec<RecentTarget>, Error> {
+    Ok(Vec::new())
+}
+
+pub fn temp_dir(config: &Config) -> Result<PathBuf, Error> {
+    common::get_plugin_temp_dir(config)
+}
+
+/// Fails with `TempFile` when `required` bytes do not fit in the temp dir.
+pub fn check_disk_space(required: u64, config: &Config) -> Result<(), Error> {
+    let dir = HSTRING::from(common::get_plugin_temp_dir(config)?.as_os_str());
+    let mut available = 0u64;
+    unsafe { GetDiskFreeSpaceExW(&dir, Some(&mut available), None, None) }
+        .map_err(|e| Error::TempFile(format!("Failed to query the free disk space: {}", e)))?;
+    common::check_disk_space(required, available)
 }
 
 pub fn share<R: Runtime>(
     window: Window<R>,
     options: ShareOptions,
-    state: State<'_, PluginTempFileManager>,
-) -> Result<(), Error> {
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    common::block_on(share_async(window, options, share_files))?
+}
+
+/// Shares content like [`share`], without blocking a thread until the share UI closes.
+pub async fn share_async<R: Runtime>(
+    window: Window<R>,
+    mut options: ShareOptions,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    // The data package has a single text slot, which the text items share.
+    crate::validation::fold_text_items(&mut options);
+    // A URL shared without text becomes a web link, so it must be well-formed.
+    let web_link = crate::validation::link_url(&options)?.map(String::from);
+    let config = window.state::<Config>();
+    check_disk_space(crate::validation::estimate_disk_usage(&options), &config)?;
+    // Files are written under their real name, which the share UI and the targets show.
+    let temp_files = Arc::new(common::DiskTempFiles::exact_names(
+        common::get_plugin_temp_dir(&config)?,
+    ));
+    // The share UI shows the name a file is written under, so a file shared as data
+    // is written under its display name, typed with the extension of its MIME type.
+    // Collisions are resolved on these final names, since two files written under
+    // the same name would overwrite each other.
+    if let Some(files) = options.files.as_mut() {
+        for file in files.iter_mut().filter(|file| file.path.is_none()) {
+            if let Some(display_name) = file.display_name.take() {
+                file.name = display_name;
+            }
+            file.name = typed_file_name(&file.name, &file.mime_type);
+        }
+        common::resolve_name_collisions(files, options.name_collision.unwrap_or_default())?;
+    }
+    let preview_image = match options.preview_image.take() {
+        Some(preview) => Some(common::read_file_data(&preview, None)?),
+        None => image_thumbnail(options.files.as_deref().unwrap_or_default()),
+    };
+    let custom_formats = crate::validation::decode_custom_formats(&options)?;
     let focus_wait = focus::begin_focus_wait(&window)?;
-    let (tx, rx) = mpsc::channel();
+    let (tx, mut rx) = tokio::sync::oneshot::channel();
     let win_clone = window.clone();
+    let (application_name, application_link) = resolve_application_source(&window, &options);
+    // The share UI shows the title, so a share without one is titled after its content.
+    let title = options
+        .title
+        .clone()
+        .or_else(|| crate::validation::default_title(&options));
+    let chosen_target: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    let chosen_target_for_handler = chosen_target.clone();
+    let failed_files: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let failed_files_for_handler = failed_files.clone();
+    // Bounds the deferral of the data request, independently of the focus wait.
+    let file_load_timeout = config.file_load_timeout();
+    let image_max_dimension = config.image_max_dimension;
+    // Tray-only apps may have no visible window to anchor the share UI to, so a
+    // transient host window is created for it instead. The share UI shows on the
+    // monitor of its window, so a share directed to another screen gets a host
+    // there too.
+    let screen_center = requested_screen_center(&window, options.screen);
+    let needs_host = !window.is_visible().unwrap_or(false)
+        || window.is_minimized().unwrap_or(false)
+        || screen_center.is_some();
+    let share_files_for_release = share_files.clone();
 
-    let managed_files_arc = state.inner().managed_files.clone();
-
-    if let Err(e) = window.run_on_main_thread(move || {
-        let options_arc = std::sync::Arc::new(options.clone());
-        let result = (|| -> Result<(), Error> {
+    let dispatched = window.run_on_main_thread(move || {
+        let mut options = options;
+        let zeroize_data = options.zeroize_data.unwrap_or(false);
+        // The handler unregisters itself after its first run, so it takes the files
+        // instead of cloning them, leaving a single copy of their data in memory.
+        let pending_files = Arc::new(Mutex::new(options.files.take()));
+        let options_arc = std::sync::Arc::new(options);
+        let result = common::catch_panic(|| -> Result<Option<isize>, Error> {
             initialize_winrt_thread()?;
-            let hwnd = get_hwnd(&win_clone)?;
+            // Files are loaded once the share UI asks for them, so this is the last
+            // point where a cancelled share is not shown.
+            share_files.check_cancelled()?;
+            let hwnd = if needs_host {
+                create_share_host(screen_center)?
+            } else {
+                get_hwnd(&win_clone)?
+            };
             let (dtm, interop) = get_data_transfer_manager(hwnd)?;
 
             let data_requested_handler = TypedEventHandler::new({
                 let options_clone = options_arc.clone();
-                let managed_files_arc_clone_for_handler = managed_files_arc.clone();
+                let pending_files = pending_files.clone();
+                let share_files_for_handler = share_files.clone();
+                let window_for_handler = win_clone.clone();
+                let failed_files_for_handler = failed_files_for_handler.clone();
                 move |_, args: windows::core::Ref<'_, DataRequestedEventArgs>| -> windows::core::Result<()> {
                     if let Some(request_args) = (*args).as_ref() {
                         let request = request_args.Request()?;
                         let data = request.Data()?;
                         let properties = data.Properties()?;
 
-                        if let Some(title) = &options_clone.title {
+                        if let Some(title) = &title {
                             properties.SetTitle(&HSTRING::from(title))?;
                         }
 
-                        if let (Some(t), Some(u)) = (&options_clone.text, &options_clone.url) {
+                        // Lets receivers attribute the content to this app ("shared from ...").
+                        properties.SetApplicationName(&HSTRING::from(&application_name))?;
+                        if let Some(link) = &application_link {
+                            match Uri::CreateUri(&HSTRING::from(link)) {
+                                Ok(uri) => properties.SetContentSourceApplicationLink(&uri)?,
+                                Err(_) => eprintln!("Warning: Could not parse application link '{}'.", link),
+                            }
+                        }
+                        if let Some(expires_at) = options_clone.expires_at {
+                            let value = PropertyValue::CreateInt64(expires_at)?;
+                            properties.Insert(&HSTRING::from(EXPIRES_AT_PROPERTY), &value)?;
+                        }
+
+                        if options_clone.text_is_plain == Some(true) {
+                            // Everything is set as text, so no part of it becomes a web link.
+                            let text = merge_text_url(&options_clone);
+                            if !text.is_empty() {
+                                data.SetText(&HSTRING::from(text))?;
+                            }
+                        }
+                        else if let (Some(t), Some(u)) = (&options_clone.text, &options_clone.url) {
                             // Set the plain text content.
                             data.SetText(&HSTRING::from(t))?;
 
@@ -100,7 +253,7 @@ pub fn share<R: Runtime>(
                                 eprintln!("Warning: Could not parse URL '{}' for DataPackage::SetWebLink. Setting as part of text.", u);
                                 // Optionally, if it's critical for the URL to be present in some form,
                                 // even if not semantically, it could be appended to the plain text.
-                                let combined_text_fallback = format!("{}\n{}", t, u);
+                                let combined_text_fallback = merge_text_url(&options_clone);
                                 data.SetText(&HSTRING::from(combined_text_fallback))?;
                                 // However, the primary goal remains semantic separation.
                             }
@@ -111,79 +264,123 @@ pub fn share<R: Runtime>(
                                 data.SetText(&HSTRING::from(t))?;
                             }
                         }
-                        // If only a URL is provided, attempt to set it semantically.
-                        else if let Some(u) = &options_clone.url {
-                            if let Ok(uri) = Uri::CreateUri(&HSTRING::from(u)) {
-                                data.SetWebLink(&uri)?;
-                            } else {
-                                // If URL parsing fails, fall back to setting it as plain text.
-                                // This ensures the URL string is still transferred, even without its semantic type.
-                                eprintln!("Warning: Could not parse URL '{}' for DataPackage::SetWebLink. Setting as plain text.", u);
-                                data.SetText(&HSTRING::from(u))?;
-                            }
+                        // If only a URL is provided, it is set as the web link validated
+                        // before the share started.
+                        else if let Some(link) = &web_link {
+                            data.SetWebLink(&Uri::CreateUri(&HSTRING::from(link))?)?;
+                        }
+
+                        // Receivers show the thumbnail on the card of the shared link, and
+                        // the share UI on the card of a shared image.
+                        if let Some(preview) = &preview_image {
+                            properties.SetThumbnail(&stream_reference(preview)?)?;
                         }
 
-                        if let Some(files) = &options_clone.files {
+                        // Receivers that know a custom format read it with `GetDataAsync`.
+                        for (format_id, bytes) in &custom_formats {
+                            let value = stream_reference(bytes)?.cast::<IInspectable>()?;
+                            data.SetData(&HSTRING::from(format_id), &value)?;
+                        }
+
+                        let files = pending_files.lock().ok().and_then(|mut files| files.take());
+                        if let Some(files) = files {
                             let deferral = request.GetDeferral()?;
+                            let deferral_id =
+                                PENDING_DEFERRALS.begin(window_for_handler.label(), deferral);
                             let data_clone = data.clone();
 
                             tauri::async_runtime::spawn({
-                                let files = files.clone(); 
-                                let managed_files_arc_for_async = managed_files_arc_clone_for_handler.clone();
+                                let share_files_for_async = share_files_for_handler.clone();
+                                let window = window_for_handler.clone();
+                                let temp_files = temp_files.clone();
+                                let failed_files = failed_files_for_handler.clone();
                                 async move {
-                                    let mut storage_items: Vec<IStorageItem> = Vec::new();
-
-                                    for file in files {
-                                        match create_temp_file_for_data(&file) {
-                                            Ok(path_buf) => {
-                                                let path_str = path_buf.to_string_lossy().to_string();
-                                                if let Err(e) = managed_files_arc_for_async.lock().map_err(|e| format!("Failed to lock mutex: {}", e)).and_then(|mut files| {
-                                                    files.push(path_buf.clone());
-                                                    Ok(())
-                                                }) {
-                                                    eprintln!("Failed to update temp file manager: {}", e);
-                                                }
+                                    let file_names: Vec<String> = files.iter().map(|file| file.name.clone()).collect();
 
-                                                match StorageFile::GetFileFromPathAsync(&HSTRING::from(path_str)) {
-                                                    Ok(op) => match op.get() {
-                                                        Ok(storage_file) => {
-                                                            if let Ok(item) = storage_file.cast() {
-                                                                storage_items.push(item);
-                                                            }
-                                                        }, 
-                                                        Err(e) => eprintln!("Failed to get storage file: {}", e),
-                                                    }, 
-                                                    Err(e) => eprintln!("Failed to get file from path: {}", e),
+                                    // Loading a storage file can hang (e.g. while an antivirus scans it), so the
+                                    // files are loaded in parallel on separate threads and the deferral is bounded
+                                    // by a deadline. The storage items keep the order of the shared files.
+                                    let deadline = Instant::now() + file_load_timeout;
+                                    let results = common::load_in_parallel(
+                                        files.into_iter().enumerate().collect(),
+                                        WinRtWorker::enter,
+                                        move |(index, mut file): (usize, SharedFile)| {
+                                            let result = load_storage_item(
+                                                &file,
+                                                index,
+                                                &share_files_for_async,
+                                                &*temp_files,
+                                                image_max_dimension,
+                                            );
+                                            if zeroize_data {
+                                                file.data.zeroize();
+                                            }
+                                            result
+                                        },
+                                        deadline,
+                                    )
+                                    .await;
+
+                                    // The threads of the async runtime are not initialized for WinRT, so the
+                                    // items are attached from a thread joining the multithreaded apartment.
+                                    let attached = tauri::async_runtime::spawn_blocking(move || -> windows::core::Result<()> {
+                                        let _winrt = WinRtWorker::enter();
+                                        let mut storage_items: Vec<IStorageItem> = Vec::new();
+                                        let mut loaded_names: Vec<&String> = Vec::new();
+                                        let mut failures: Vec<String> = Vec::new();
+                                        for (name, result) in file_names.iter().zip(results) {
+                                            match result {
+                                                Some(Ok(item)) => {
+                                                    storage_items.push(item);
+                                                    loaded_names.push(name);
                                                 }
-                                            },
-                                            Err(e) => eprintln!("Failed to create temp file: {}", e),
+                                                Some(Err(e)) => failures.push(format!("{}: {}", name, e)),
+                                                None => failures.push(format!("{}: timed out while loading the file", name)),
+                                            }
                                         }
-                                    }
 
-                                    if !storage_items.is_empty() {
-                                        let options_items = storage_items.into_iter().map(Some).collect::<Vec<_>>();
-                                        let iterable_items: Result<IIterable<IStorageItem>, _> = options_items.try_into();
-                                        
-                                        match iterable_items {
-                                            Ok(items) => {
-                                                if let Err(e) = data_clone.SetStorageItemsReadOnly(&items) {
-                                                    println!("Failed to set storage items on data package: {}", e);
+                                        if !storage_items.is_empty() {
+                                            let options_items = storage_items.into_iter().map(Some).collect::<Vec<_>>();
+                                            let iterable_items: Result<IIterable<IStorageItem>, _> = options_items.try_into();
+                                            // Some targets refuse read-only items, so the files are
+                                            // attached read-write rather than silently dropped.
+                                            let attached = match iterable_items {
+                                                Ok(items) => common::attach_with_fallback(
+                                                    || data_clone.SetStorageItemsReadOnly(&items),
+                                                    || data_clone.SetStorageItems(&items, false),
+                                                ),
+                                                Err(e) => Err(e.to_string()),
+                                            };
+                                            if let Err(e) = attached {
+                                                for name in loaded_names {
+                                                    failures.push(format!("{}: {}", name, e));
                                                 }
-                                            },
-                                            Err(e) => {
-                                                println!("Failed to convert Vec to IIterable: {}", e);
                                             }
                                         }
+
+                                        if !failures.is_empty() {
+                                            if let Ok(mut failed_files) = failed_files.lock() {
+                                                failed_files.extend(failures.iter().cloned());
+                                            }
+                                            emit_share_error(&window, "Some files could not be attached to the share.", failures);
+                                        }
+                                        // Completed already if the window was destroyed meanwhile.
+                                        if let Some(deferral) = PENDING_DEFERRALS.finish(deferral_id) {
+                                            deferral.Complete()?;
+                                        }
+                                        Ok(())
+                                    })
+                                    .await;
+                                    if let Ok(Err(e)) = attached {
+                                        eprintln!("Failed to complete the deferral of the share: {}", e);
                                     }
-                                    deferral.Complete()?;
-                                    Ok::<(), windows::core::Error>(())
                                 }
 
                             });
                         }
 
                         SHARE_STATE.with(|state| {
-                            if let Some((manager, token)) = state.borrow_mut().take() {
+                            if let Some((manager, token, _)) = state.borrow_mut().take() {
                                 let _ = manager.RemoveDataRequested(token);
                             }
                         });
@@ -192,46 +389,610 @@ pub fn share<R: Runtime>(
                 }
             });
 
+            // A registration left over from a share where no target was chosen must not
+            // report its result into this one.
+            remove_target_chosen_registration();
+            let target_chosen_handler = TypedEventHandler::new({
+                let chosen_target_clone = chosen_target_for_handler.clone();
+                move |_, args: windows::core::Ref<'_, TargetApplicationChosenEventArgs>| -> windows::core::Result<()> {
+                    if let Some(chosen_args) = (*args).as_ref() {
+                        let name = chosen_args.ApplicationName()?.to_string();
+                        if let Ok(mut target) = chosen_target_clone.lock() {
+                            *target = Some(name);
+                        }
+                    }
+                    remove_target_chosen_registration();
+                    Ok(())
+                }
+            });
+            let target_token = dtm.TargetApplicationChosen(&target_chosen_handler)?;
+            TARGET_CHOSEN_STATE.with(|state| {
+                *state.borrow_mut() = Some((dtm.clone(), target_token));
+            });
+
             let token = dtm.DataRequested(&data_requested_handler)?;
             
+            let label = win_clone.label().to_string();
             SHARE_STATE.with(|state| {
-                *state.borrow_mut() = Some((dtm, token));
+                *state.borrow_mut() = Some((dtm, token, label));
             });
 
             // Best-effort note: ShowShareUIForWindow doesn't provide a reliable completion callback
             // for desktop apps. Consider making resolution behavior configurable for end developers
             // (immediate vs. on-focus vs. delayed).
             unsafe { interop.ShowShareUIForWindow(hwnd) }?;
-            Ok(())
-        })();
-        tx.send(result).ok();
-    }) {
+            Ok(needs_host.then_some(hwnd.0 as isize))
+        });
+        if result.is_err() {
+            destroy_share_host();
+        }
+        let _ = tx.send(result);
+    });
+    if let Err(e) = common::check_dispatched(dispatched, &share_files_for_release) {
         focus_wait.cancel();
-        return Err(e.into());
+        return Err(e);
     }
 
-    let share_result = match rx.recv() {
-        Ok(result) => result,
+    let setup_timeout = window.state::<Config>().setup_timeout();
+    let host = match common::recv_setup_result(&mut rx, setup_timeout).await {
+        Ok(host) => host,
         Err(err) => {
             focus_wait.cancel();
-            return Err(err.into());
+            if matches!(err, Error::Timeout(_)) {
+                let window = window.clone();
+                common::release_after_late_share(share_files_for_release, async move {
+                    if let Ok(Ok(host)) = rx.await {
+                        wait_for_late_share_ui(&window, host, &chosen_target).await;
+                    }
+                });
+            }
+            return Err(err);
         }
     };
-    if let Err(err) = share_result {
-        focus_wait.cancel();
-        return Err(err);
+
+    let focus_end = match host {
+        Some(host) => {
+            // The app window is hidden, so its focus says nothing about the share UI.
+            focus_wait.cancel();
+            let timeout = window.state::<Config>().share_host_timeout();
+            wait_for_share_host(host, &chosen_target, timeout).await;
+            if let Err(e) = window.run_on_main_thread(destroy_share_host) {
+                eprintln!("Failed to destroy the share host window: {}", e);
+            }
+            None
+        }
+        None => Some(focus_wait.wait().await?),
+    };
+
+    // The target is only reported when the user picks one, so its absence means the
+    // share UI was dismissed, or never appeared if the window kept its focus.
+    let target = match chosen_target.lock() {
+        Ok(mut target) => target.take(),
+        Err(poisoned) => poisoned.into_inner().take(),
+    };
+    let failed_files = match failed_files.lock() {
+        Ok(mut failed_files) => std::mem::take(&mut *failed_files),
+        Err(poisoned) => std::mem::take(&mut *poisoned.into_inner()),
+    };
+    Ok(match (target, focus_end) {
+        (Some(name), _) => ShareResult::Completed {
+            target: Some(name),
+            failed_files,
+            accepted_files: Vec::new(),
+            duplicates_removed: 0,
+        },
+        (None, Some(FocusWaitEnd::NoFocusLoss)) => ShareResult::NotShown,
+        (None, _) => ShareResult::Cancelled,
+    })
+}
+
+/// Targeted sharing is not available on Windows, so this shows the regular share UI.
+pub fn share_to<R: Runtime>(
+    window: Window<R>,
+    options: ShareOptions,
+    _service: &str,
+    _service_options: &HashMap<String, String>,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    share(window, options, share_files)
+}
+
+/// Shows the regular share UI like [`share_to`], without blocking a thread until it closes.
+pub async fn share_to_async<R: Runtime>(
+    window: Window<R>,
+    options: ShareOptions,
+    _service: &str,
+    _service_options: &HashMap<String, String>,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    share_async(window, options, share_files).await
+}
+
+/// Windows has no sharing services to perform directly, so this is not supported.
+pub fn perform_service<R: Runtime>(
+    _window: Window<R>,
+    _options: ShareOptions,
+    _service: &str,
+    _service_options: &HashMap<String, String>,
+    _share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    Err(Error::Unsupported(
+        "performing a sharing service is not supported on Windows".to_string(),
+    ))
+}
+
+/// The center of the monitor `ShareOptions::screen` directs the share UI to, in
+/// physical pixels, unless it is the monitor of the window.
+fn requested_screen_center<R: Runtime>(window: &Window<R>, index: Option<u32>) -> Option<POINT> {
+    let (x, y) = common::requested_screen_for(window, index)?.center();
+    Some(POINT { x, y })
+}
+
+/// Creates a hidden top-level window at `position`, or at the cursor, to host the
+/// share UI.
+///
+/// The window has no size and is kept out of the taskbar. It is brought to the
+/// foreground so the share UI can take focus, and stays alive until
+/// [`destroy_share_host`] runs on the main thread.
+fn create_share_host(position: Option<POINT>) -> Result<HWND, Error> {
+    destroy_share_host();
+    let position = position.unwrap_or_else(|| {
+        let mut cursor = POINT::default();
+        let _ = unsafe { GetCursorPos(&mut cursor) };
+        cursor
+    });
+    let hwnd = unsafe {
+        CreateWindowExW(
+            WS_EX_TOOLWINDOW,
+            w!("STATIC"),
+            w!(""),
+            WS_POPUP,
+            position.x,
+            position.y,
+            0,
+            0,
+            None,
+            None,
+            None,
+            None,
+        )
+    }?;
+    unsafe {
+        let _ = ShowWindow(hwnd, SW_SHOW);
+        let _ = SetForegroundWindow(hwnd);
     }
+    SHARE_HOST.with(|host| *host.borrow_mut() = Some(hwnd));
+    Ok(hwnd)
+}
 
-    focus_wait.wait()?;
-    Ok(())
+/// Destroys the transient host window of the current share, if any.
+fn destroy_share_host() {
+    if let Some(hwnd) = SHARE_HOST.with(|host| host.borrow_mut().take()) {
+        if let Err(e) = unsafe { DestroyWindow(hwnd) } {
+            eprintln!("Failed to destroy the share host window: {}", e);
+        }
+    }
+}
+
+/// Waits until a share UI shown after its setup timed out is done, so the files
+/// it reads can be released.
+///
+/// The focus wait of the share already ended, so a share UI over the app window
+/// is given as long as a focus wait would have given it, unless a target is
+/// chosen first.
+async fn wait_for_late_share_ui<R: Runtime>(
+    window: &Window<R>,
+    host: Option<isize>,
+    chosen_target: &Mutex<Option<String>>,
+) {
+    let config = window.state::<Config>();
+    match host {
+        Some(host) => {
+            wait_for_share_host(host, chosen_target, config.share_host_timeout()).await;
+            if let Err(e) = window.run_on_main_thread(destroy_share_host) {
+                eprintln!("Failed to destroy the share host window: {}", e);
+            }
+        }
+        None => {
+            let deadline = Instant::now() + config.focus_wait_timeout();
+            while Instant::now() < deadline && !target_chosen(chosen_target) {
+                tokio::time::sleep(SHARE_HOST_POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// Whether the user picked a target in the share UI.
+fn target_chosen(chosen_target: &Mutex<Option<String>>) -> bool {
+    match chosen_target.lock() {
+        Ok(target) => target.is_some(),
+        Err(poisoned) => poisoned.into_inner().is_some(),
+    }
+}
+
+/// Waits until the share UI anchored to a transient host window is done.
+///
+/// The share UI is owned by the host, so Windows gives the foreground back to the
+/// host when the UI is dismissed. The wait ends once the host lost and regained
+/// the foreground, a target was chosen, the host was destroyed by [`cancel_all`],
+/// or `timeout` elapsed.
+async fn wait_for_share_host(
+    host: isize,
+    chosen_target: &Mutex<Option<String>>,
+    timeout: Duration,
+) {
+    let deadline = Instant::now() + timeout;
+    let mut lost_foreground = false;
+    while Instant::now() < deadline {
+        if !unsafe { IsWindow(Some(HWND(host as *mut std::ffi::c_void))) }.as_bool() {
+            return;
+        }
+
+        if target_chosen(chosen_target) {
+            return;
+        }
+
+        let is_foreground = unsafe { GetForegroundWindow() }.0 as isize == host;
+        if !is_foreground {
+            lost_foreground = true;
+        } else if lost_foreground {
+            return;
+        }
+        tokio::time::sleep(SHARE_HOST_POLL_INTERVAL).await;
+    }
+}
+
+/// Removes the `TargetApplicationChosen` registration of the previous share, if any.
+fn remove_target_chosen_registration() {
+    TARGET_CHOSEN_STATE.with(|state| {
+        if let Some((manager, token)) = state.borrow_mut().take() {
+            let _ = manager.RemoveTargetApplicationChosen(token);
+        }
+    });
+}
+
+/// Resolves the app name and link shown by receivers, preferring the per-share overrides.
+fn resolve_application_source<R: Runtime>(
+    window: &Window<R>,
+    options: &ShareOptions,
+) -> (String, Option<String>) {
+    let name = options
+        .application_name
+        .clone()
+        .unwrap_or_else(|| window.package_info().name.clone());
+    let link = options
+        .application_link
+        .clone()
+        .or_else(|| window.state::<Config>().application_link.clone());
+    (name, link)
+}
+
+/// Writes a shared file to disk, registers it for cleanup and loads it as a storage item.
+fn load_storage_item(
+    file: &SharedFile,
+    index: usize,
+    share_files: &ShareFiles,
+    temp_files: &impl common::TempFileWriter,
+    image_max_dimension: Option<u32>,
+) -> Result<IStorageItem, Error> {
+    // Files shared by path are used in place and are not ours to clean up.
+    if let Some(path) = &file.path {
+        let path_str = HSTRING::from(path.as_str());
+        let item: IStorageItem = match crate::validation::check_shared_path(path)? {
+            SharedPath::Directory => {
+                StorageFolder::GetFolderFromPathAsync(&path_str)?.get()?.cast()?
+            }
+            SharedPath::File(_) => StorageFile::GetFileFromPathAsync(&path_str)?.get()?.cast()?,
+        };
+        return Ok(item);
+    }
+
+    share_files.check_cancelled()?;
+    let path_buf = common::write_temp_file(temp_files, file, index, image_max_dimension)?;
+    let path_str = path_buf.to_string_lossy().to_string();
+    share_files.add_file(path_buf);
+
+    let storage_file = StorageFile::GetFileFromPathAsync(&HSTRING::from(path_str))?.get()?;
+    // `ContentType` is read-only, so a type Windows derived differently is only reported.
+    if file.mime_type != crate::validation::DEFAULT_MIME_TYPE {
+        match storage_file.ContentType() {
+            Ok(content_type) if !content_type.to_string().eq_ignore_ascii_case(&file.mime_type) => {
+                eprintln!(
+                    "Windows shares '{}' as '{}' instead of '{}'",
+                    file.name, content_type, file.mime_type
+                );
+            }
+            _ => {}
+        }
+    }
+    Ok(storage_file.cast()?)
+}
+
+/// Returns the name a shared file is written under.
+///
+/// Windows derives the `ContentType` of a storage file from its extension and
+/// offers no way to set it, so a name without an extension gets the extension
+/// of its MIME type, when known, for receivers to see the right type.
+fn typed_file_name(name: &str, mime_type: &str) -> String {
+    if Path::new(name).extension().is_some() {
+        return name.to_string();
+    }
+    match crate::validation::extension_for_mime_type(mime_type) {
+        Some(extension) => format!("{}.{}", name, extension),
+        None => name.to_string(),
+    }
+}
+
+/// The Windows share UI has no print target, so printing is not supported.
+pub fn print<R: Runtime>(
+    _window: Window<R>,
+    _file: SharedFile,
+    _share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    Err(Error::Unsupported(
+        "printing is not supported on Windows".to_string(),
+    ))
+}
+
+/// Printing is not supported on Windows, see [`print`].
+pub async fn print_async<R: Runtime>(
+    window: Window<R>,
+    file: SharedFile,
+    share_files: ShareFiles,
+) -> Result<ShareResult, Error> {
+    print(window, file, share_files)
+}
+
+/// Captures the client area of the webview's window as a PNG image.
+///
+/// `PrintWindow` renders the window into an off-screen bitmap, so the capture
+/// includes the WebView2 content even when the window is partly covered.
+pub fn capture_window<R: Runtime>(webview: &Webview<R>) -> Result<Vec<u8>, Error> {
+    let window = webview.window();
+    let hwnd = get_hwnd(&window)?;
+    let size = window.inner_size()?;
+    if size.width == 0 || size.height == 0 {
+        return Err(Error::NativeApi(
+            "The window has no visible area to capture".to_string(),
+        ));
+    }
+
+    let pixels = unsafe { capture_client_pixels(hwnd, size.width as i32, size.height as i32) }?;
+    encode_png(&pixels, size.width, size.height)
+}
+
+/// Renders the client area of `hwnd` into a top-down BGRA buffer.
+unsafe fn capture_client_pixels(hwnd: HWND, width: i32, height: i32) -> Result<Vec<u8>, Error> {
+    let window_dc = GetDC(Some(hwnd));
+    let memory_dc = CreateCompatibleDC(Some(window_dc));
+    let bitmap = CreateCompatibleBitmap(window_dc, width, height);
+    let previous = SelectObject(memory_dc, bitmap.into());
+
+    let flags = PRINT_WINDOW_FLAGS(PW_CLIENTONLY.0 | PW_RENDERFULLCONTENT);
+    let printed = PrintWindow(hwnd, memory_dc, flags).as_bool();
+
+    let mut info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // A negative height asks for the rows from top to bottom.
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    let copied_lines = if printed {
+        GetDIBits(
+            memory_dc,
+            bitmap,
+            0,
+            height as u32,
+            Some(pixels.as_mut_ptr().cast()),
+            &mut info,
+            DIB_RGB_COLORS,
+        )
+    } else {
+        0
+    };
+
+    SelectObject(memory_dc, previous);
+    let _ = DeleteObject(bitmap.into());
+    let _ = DeleteDC(memory_dc);
+    ReleaseDC(Some(hwnd), window_dc);
+
+    if !printed || copied_lines == 0 {
+        return Err(Error::NativeApi("Failed to capture the window".to_string()));
+    }
+    Ok(pixels)
+}
+
+/// Encodes a top-down BGRA buffer as a PNG image.
+fn encode_png(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, Error> {
+    let stream = InMemoryRandomAccessStream::new()?;
+    let encoder = BitmapEncoder::CreateAsync(BitmapEncoder::PngEncoderId()?, &stream)?.get()?;
+    encoder.SetPixelData(
+        BitmapPixelFormat::Bgra8,
+        BitmapAlphaMode::Ignore,
+        width,
+        height,
+        96.0,
+        96.0,
+        pixels,
+    )?;
+    encoder.FlushAsync()?.get()?;
+
+    let size = stream.Size()? as u32;
+    let reader = DataReader::CreateDataReader(&stream.GetInputStreamAt(0)?)?;
+    reader.LoadAsync(size)?.get()?;
+    let mut png = vec![0u8; size as usize];
+    reader.ReadBytes(&mut png)?;
+    Ok(png)
+}
+
+/// Notifies the frontend that part of a share could not be completed.
+fn emit_share_error<R: Runtime>(window: &Window<R>, message: &str, files: Vec<String>) {
+    let payload = ShareErrorEvent {
+        message: message.to_string(),
+        files,
+    };
+    if let Err(e) = window.emit(SHARE_ERROR_EVENT, payload) {
+        eprintln!("Failed to emit share error event: {}", e);
+    }
 }
 
 /// Initializes the Windows Runtime on the current thread.
+///
+/// Only the first call on a thread initializes it, so that [`teardown`] balances it
+/// with a single `RoUninitialize`.
 fn initialize_winrt_thread() -> Result<(), Error> {
-    // RoInitialize can be called multiple times on the same thread.
-    // It will return S_FALSE if already initialized, which is not an error.
+    if WINRT_INITIALIZED.with(Cell::get) {
+        return Ok(());
+    }
+    // RoInitialize returns S_FALSE if the thread is already initialized, e.g. by
+    // WebView2, which is not an error but must be balanced all the same.
     unsafe { RoInitialize(RO_INIT_SINGLETHREADED) }
-        .map_err(|e| Error::NativeApi(format!("Failed to initialize WinRT: {}", e)))
+        .map_err(|e| Error::NativeApi(format!("Failed to initialize WinRT: {}", e)))?;
+    WINRT_INITIALIZED.with(|initialized| initialized.set(true));
+    *lock_winrt_thread() = Some(std::thread::current().id());
+    Ok(())
+}
+
+/// Keeps the Windows Runtime initialized on a worker thread until dropped.
+///
+/// The threads loading storage items are not the main thread, so they join the
+/// multithreaded apartment, and leave it on the same thread once done.
+struct WinRtWorker {
+    initialized: bool,
+}
+
+impl WinRtWorker {
+    fn enter() -> Self {
+        // S_FALSE, for a thread already initialized, must be balanced all the same.
+        let initialized = match unsafe { RoInitialize(RO_INIT_MULTITHREADED) } {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("Failed to initialize WinRT on a loader thread: {}", e);
+                false
+            }
+        };
+        Self { initialized }
+    }
+}
+
+impl Drop for WinRtWorker {
+    fn drop(&mut self) {
+        if self.initialized {
+            unsafe { RoUninitialize() };
+        }
+    }
+}
+
+/// Releases the share of the window `label` when it is destroyed mid-share.
+///
+/// The focus wait of the share already ends with the window. This completes the
+/// deferral of files still loading, so the share UI is not left waiting on them,
+/// and removes the event registrations of the share, which would otherwise
+/// outlive the window until the next share.
+pub fn window_destroyed<R: Runtime>(app: &AppHandle<R>, label: &str) {
+    for deferral in PENDING_DEFERRALS.window_destroyed(label) {
+        if let Err(e) = deferral.Complete() {
+            eprintln!("Failed to complete the deferral of the share: {}", e);
+        }
+    }
+    let label = label.to_string();
+    let result = app.run_on_main_thread(move || {
+        let registration = SHARE_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            let is_destroyed = state
+                .as_ref()
+                .is_some_and(|(_, _, share_label)| *share_label == label);
+            if is_destroyed {
+                state.take()
+            } else {
+                None
+            }
+        });
+        if let Some((manager, token, _)) = registration {
+            let _ = manager.RemoveDataRequested(token);
+            remove_target_chosen_registration();
+        }
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to release the share of the destroyed window: {}", e);
+    }
+}
+
+/// Ends every share in progress, e.g. when the app cancels them all.
+///
+/// Like [`window_destroyed`], this completes the deferrals of files still loading and
+/// removes the event registrations of the share. It also destroys the transient host
+/// window, which takes a share UI anchored to it along, and ends the focus waits, so
+/// every share resolves as cancelled. A share UI shown for an app window is owned by
+/// Windows and stays open until the user dismisses it. Returns once the main thread
+/// released the share, or the setup timeout elapsed.
+pub fn cancel_all<R: Runtime>(app: &AppHandle<R>) {
+    for deferral in PENDING_DEFERRALS.take_all() {
+        if let Err(e) = deferral.Complete() {
+            eprintln!("Failed to complete the deferral of the share: {}", e);
+        }
+    }
+    let timeout = app.state::<Config>().setup_timeout();
+    let result = common::run_on_main_thread_and_wait(app, timeout, || {
+        if let Some((manager, token, _)) = SHARE_STATE.with(|state| state.borrow_mut().take()) {
+            let _ = manager.RemoveDataRequested(token);
+        }
+        remove_target_chosen_registration();
+        destroy_share_host();
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to release the shares in progress: {}", e);
+    }
+    focus::end_all_waits();
+}
+
+/// Releases the WinRT state left by the shares, typically when the app exits.
+///
+/// The lingering event registrations, the cached `DataTransferManager` and the
+/// transient host window live in thread-locals of the thread that initialized
+/// WinRT, so they are released there, before its WinRT initialization is balanced.
+/// The app is dropped on the main thread as it exits, when the event loop no
+/// longer runs dispatched closures, so there the state is released right away.
+/// From any other thread, it is dispatched to the main thread.
+pub fn teardown<R: Runtime>(app: &AppHandle<R>) {
+    let on_winrt_thread = match *lock_winrt_thread() {
+        Some(thread) => thread == std::thread::current().id(),
+        None => true,
+    };
+    if on_winrt_thread {
+        release_winrt_state();
+    } else if let Err(e) = app.run_on_main_thread(release_winrt_state) {
+        eprintln!("Failed to release the WinRT share state: {}", e);
+    }
+}
+
+fn release_winrt_state() {
+    SHARE_STATE.with(|state| {
+        if let Some((manager, token, _)) = state.borrow_mut().take() {
+            let _ = manager.RemoveDataRequested(token);
+        }
+    });
+    remove_target_chosen_registration();
+    destroy_share_host();
+    if WINRT_INITIALIZED.with(|initialized| initialized.replace(false)) {
+        unsafe { RoUninitialize() };
+        *lock_winrt_thread() = None;
+    }
+}
+
+fn lock_winrt_thread() -> MutexGuard<'static, Option<ThreadId>> {
+    match WINRT_THREAD.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
 }
 
 /// Retrieves the native window handle (HWND) from the Tauri window.
@@ -242,9 +1003,7 @@ fn get_hwnd<R: Runtime>(window: &Window<R>) -> Result<HWND, Error> {
 
     match handle.as_raw() {
         RawWindowHandle::Win32(handle) => Ok(HWND(handle.hwnd.get() as *mut std::ffi::c_void)),
-        _ => Err(Error::NativeApi(
-            "Unsupported window handle type".to_string(),
-        )),
+        other => Err(common::unsupported_window_handle("Win32", &other)),
     }
 }
 
@@ -258,40 +1017,32 @@ fn get_data_transfer_manager(
     Ok((dtm, interop))
 }
 
-/// Returns the path to a dedicated, secure directory for this plugin's temporary files.
-fn get_plugin_temp_dir() -> Result<PathBuf, Error> {
-    let dir = std::env::temp_dir().join("tauri-plugin-share");
-    if !dir.exists() {
-        std::fs::create_dir_all(&dir)
-            .map_err(|e| Error::TempFile(format!("Failed to create temp dir: {}", e)))?;
-    }
-    Ok(dir)
+/// Picks the thumbnail of a share without a preview image: a thumbnail of its
+/// first image file that can be decoded, see [`common::image_thumbnail`].
+fn image_thumbnail(files: &[SharedFile]) -> Option<Vec<u8>> {
+    files.iter().find_map(common::image_thumbnail)
 }
 
-/// Creates a secure temporary file from Base64 data.
-fn create_temp_file_for_data(file: &SharedFile) -> Result<PathBuf, Error> {
-    let decoded_bytes = general_purpose::STANDARD
-        .decode(&file.data)
-        .map_err(|_| Error::InvalidArgs("Invalid Base64 data provided".to_string()))?;
-
-    // Security: Sanitize the filename to prevent path traversal attacks.
-    // We only use the filename part and ignore any directory structure.
-    let sanitized_name = Path::new(&file.name)
-        .file_name()
-        .ok_or_else(|| Error::InvalidArgs("Invalid file name provided".to_string()))?
-        .to_str()
-        .ok_or_else(|| Error::InvalidArgs("File name contains invalid UTF-8".to_string()))?;
-
-    let temp_dir = get_plugin_temp_dir()?;
-    let temp_path = temp_dir.join(sanitized_name);
-
-    let mut file_handle = File::create(&temp_path)
-        .map_err(|e| Error::TempFile(format!("Failed to create temp file: {}", e)))?;
+/// Wraps bytes in a stream reference, as used for a share thumbnail or the payload
+/// of a custom data format.
+fn stream_reference(bytes: &[u8]) -> windows::core::Result<RandomAccessStreamReference> {
+    let stream = InMemoryRandomAccessStream::new()?;
+    let writer = DataWriter::CreateDataWriter(&stream)?;
+    writer.WriteBytes(bytes)?;
+    writer.StoreAsync()?.get()?;
+    writer.DetachStream()?;
+    stream.Seek(0)?;
+    RandomAccessStreamReference::CreateFromStream(&stream)
+}
 
-    // For now we will keep the real file name, we may introduce a way allow the end dev decide later.
-    file_handle
-        .write_all(&decoded_bytes)
-        .map_err(|e| Error::TempFile(format!("Failed to write to temp file: {}", e)))?;
+#[cfg(test)]
+mod tests {
+    use super::typed_file_name;
 
-    Ok(temp_path)
+    #[test]
+    fn files_without_extension_get_the_extension_of_their_type() {
+        assert_eq!(typed_file_name("report", "application/pdf"), "report.pdf");
+        assert_eq!(typed_file_name("photo.png", "image/jpeg"), "photo.png");
+        assert_eq!(typed_file_name("blob", "application/octet-stream"), "blob");
+    }
 }
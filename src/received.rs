@@ -0,0 +1,89 @@
+use std::sync::{Mutex, MutexGuard};
+
+#[cfg(mobile)]
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::ReceivedShare;
+#[cfg(mobile)]
+use crate::SHARE_RECEIVED_EVENT;
+
+/// The shares received from other apps that the frontend has not seen yet.
+///
+/// A share can arrive before the frontend listens for it, most notably the one
+/// the app was launched with. Such shares are queued until the frontend takes
+/// them, after which incoming shares are only emitted as events. Managed as a
+/// Tauri state.
+#[derive(Default)]
+pub(crate) struct ReceivedShares(Mutex<Queue>);
+
+#[derive(Default)]
+struct Queue {
+    pending: Vec<ReceivedShare>,
+    taken: bool,
+}
+
+impl ReceivedShares {
+    /// Queues `share` if the frontend has not taken the pending shares yet.
+    ///
+    /// Returns the share back when it should be emitted instead.
+    #[cfg(any(mobile, test))]
+    fn push(&self, share: ReceivedShare) -> Option<ReceivedShare> {
+        let mut queue = self.lock();
+        if queue.taken {
+            return Some(share);
+        }
+        queue.pending.push(share);
+        None
+    }
+
+    /// Returns the queued shares, in the order they were received.
+    ///
+    /// Shares received afterwards are emitted as events instead of being queued.
+    pub(crate) fn take(&self) -> Vec<ReceivedShare> {
+        let mut queue = self.lock();
+        queue.taken = true;
+        std::mem::take(&mut queue.pending)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        match self.0.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Forwards a share received from another app to the frontend.
+#[cfg(mobile)]
+pub(crate) fn deliver<R: Runtime>(app: &AppHandle<R>, share: ReceivedShare) {
+    let Some(share) = app.state::<ReceivedShares>().push(share) else {
+        return;
+    };
+    if let Err(e) = app.emit(SHARE_RECEIVED_EVENT, share) {
+        eprintln!("Failed to emit share received event: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn share(text: &str) -> ReceivedShare {
+        ReceivedShare {
+            text: Some(text.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shares_are_queued_until_taken() {
+        let shares = ReceivedShares::default();
+
+        assert_eq!(shares.push(share("launch")), None);
+        assert_eq!(shares.push(share("second")), None);
+        assert_eq!(shares.take(), vec![share("launch"), share("second")]);
+
+        assert_eq!(shares.push(share("resumed")), Some(share("resumed")));
+        assert!(shares.take().is_empty());
+    }
+}
@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use zeroize::Zeroizing;
+
+use crate::state::PluginTempFileManager;
+use crate::{Error, Result, ShareOptions, ShareResult, SharedFile};
+
+/// The options of the last share of each window that did not complete, keyed by
+/// window label, so it can be presented again.
+///
+/// Only used when the `cacheLastShare` config is set. The files sent as data are
+/// written to temporary files, which the cached options share by path instead
+/// of keeping their Base64 data in memory. The cache holds a reference to these
+/// files and to the handed-off files of the share, so they outlive the share
+/// until the entry is discarded, after which they are deleted once the
+/// `retainAfterShareMs` grace period ends. Managed as a Tauri state.
+#[derive(Default)]
+pub(crate) struct LastShareOptions(Mutex<HashMap<String, CachedShare>>);
+
+struct CachedShare {
+    options: ShareOptions,
+    files: Vec<PathBuf>,
+}
+
+impl LastShareOptions {
+    /// Caches `options` as the last share of `label`, replacing the previous one.
+    /// The files sent as data are written to `dir`.
+    ///
+    /// A share with a streamed file cannot be presented again, since its reader is
+    /// consumed by the share, so it discards the previous entry instead, as does a
+    /// share whose files cannot be written.
+    pub(crate) fn store(
+        &self,
+        label: &str,
+        options: &ShareOptions,
+        manager: &PluginTempFileManager,
+        dir: &Path,
+    ) {
+        if !options.files.iter().flatten().all(is_replayable) {
+            self.discard(label, manager);
+            return;
+        }
+        let mut handoffs = Vec::new();
+        let mut written = Vec::new();
+        let mut files = Vec::new();
+        for file in options.files.iter().flatten() {
+            if let Some(path) = &file.path {
+                // The cache keeps the handed-off file instead of the share, so it is
+                // shared again like any other file of the temp dir.
+                if file.handoff == Some(true) {
+                    handoffs.push(PathBuf::from(path));
+                }
+                files.push(SharedFile {
+                    handoff: None,
+                    ..file.clone()
+                });
+                continue;
+            }
+            match write_file(file, dir) {
+                Ok(path) => {
+                    files.push(cached_file(file, &path));
+                    written.push(path);
+                }
+                Err(e) => {
+                    eprintln!("Failed to cache the last share of '{}': {}", label, e);
+                    for path in &written {
+                        let _ = std::fs::remove_file(path);
+                    }
+                    self.discard(label, manager);
+                    return;
+                }
+            }
+        }
+        let cached = CachedShare {
+            options: ShareOptions {
+                files: options.files.as_ref().map(|_| files),
+                ..options.clone()
+            },
+            files: handoffs.into_iter().chain(written).collect(),
+        };
+        for path in &cached.files {
+            manager.add_file(path.clone());
+        }
+        let previous = self.lock().insert(label.to_string(), cached);
+        if let Some(previous) = previous {
+            release(previous, manager);
+        }
+    }
+
+    /// Discards the entry of `label` once its share completed with a target.
+    pub(crate) fn finish(
+        &self,
+        label: &str,
+        result: &Result<ShareResult>,
+        manager: &PluginTempFileManager,
+    ) {
+        if let Ok(ShareResult::Completed { .. }) = result {
+            self.discard(label, manager);
+        }
+    }
+
+    /// Returns the options of the last share of `label` that did not complete.
+    pub(crate) fn get(&self, label: &str) -> Option<ShareOptions> {
+        self.lock().get(label).map(|cached| cached.options.clone())
+    }
+
+    /// Discards the entry of `label`, returning whether there was one.
+    pub(crate) fn discard(&self, label: &str, manager: &PluginTempFileManager) -> bool {
+        let cached = self.lock().remove(label);
+        let discarded = cached.is_some();
+        if let Some(cached) = cached {
+            release(cached, manager);
+        }
+        discarded
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, CachedShare>> {
+        match self.0.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+/// Whether `file` can be shared again from the cached options.
+///
+/// On mobile, handed-off files are deleted as soon as they are read for the
+/// native side, so they cannot be shared again either.
+fn is_replayable(file: &SharedFile) -> bool {
+    file.reader.is_none() && (cfg!(desktop) || file.handoff != Some(true))
+}
+
+/// Writes the data of `file` to a new file in `dir`, named after it.
+fn write_file(file: &SharedFile, dir: &Path) -> Result<PathBuf> {
+    let name = crate::validation::sanitize_file_name(&file.name)?;
+    let data = Zeroizing::new(crate::validation::decode_file_data(file, None)?);
+    let (mut handle, path) = tempfile::Builder::new()
+        .prefix("resume-")
+        .suffix(&format!("-{}", name))
+        .tempfile_in(dir)
+        .and_then(|file| file.keep().map_err(|e| e.error))
+        .map_err(|e| Error::TempFile(format!("Failed to create temp file: {}", e)))?;
+    if let Err(e) = handle.write_all(&data) {
+        drop(handle);
+        let _ = std::fs::remove_file(&path);
+        return Err(Error::TempFile(format!("Failed to write temp file: {}", e)));
+    }
+    Ok(path)
+}
+
+/// The cached copy of `file`, shared from its data written at `path`.
+fn cached_file(file: &SharedFile, path: &Path) -> SharedFile {
+    SharedFile {
+        data: String::new(),
+        path: Some(path.to_string_lossy().into_owned()),
+        handoff: None,
+        ..file.clone()
+    }
+}
+
+fn release(cached: CachedShare, manager: &PluginTempFileManager) {
+    for path in cached.files {
+        manager.release_file(&path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    fn options(text: &str) -> ShareOptions {
+        ShareOptions {
+            text: Some(text.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn data_file(data: &str) -> SharedFile {
+        SharedFile {
+            data: data.to_string(),
+            path: None,
+            ..SharedFile::from_path("notes.txt", "text/plain")
+        }
+    }
+
+    #[test]
+    fn completed_share_discards_the_cached_options() {
+        let cache = LastShareOptions::default();
+        let manager = PluginTempFileManager::new();
+
+        cache.store("main", &options("first"), &manager, &std::env::temp_dir());
+        cache.finish("main", &Ok(ShareResult::Cancelled), &manager);
+        cache.finish("main", &Err(Error::Cancelled), &manager);
+        assert_eq!(cache.get("main").unwrap().text.as_deref(), Some("first"));
+        assert!(cache.get("other").is_none());
+
+        cache.store("main", &options("second"), &manager, &std::env::temp_dir());
+        assert_eq!(cache.get("main").unwrap().text.as_deref(), Some("second"));
+        let completed = ShareResult::Completed {
+            target: None,
+            failed_files: Vec::new(),
+            accepted_files: Vec::new(),
+            duplicates_removed: 0,
+        };
+        cache.finish("main", &Ok(completed), &manager);
+        assert!(cache.get("main").is_none());
+        assert!(!cache.discard("main", &manager));
+    }
+
+    #[test]
+    fn streamed_share_is_not_cached() {
+        let cache = LastShareOptions::default();
+        let manager = PluginTempFileManager::new();
+        cache.store("main", &options("first"), &manager, &std::env::temp_dir());
+
+        let streamed = ShareOptions {
+            files: Some(vec![SharedFile::from_reader("notes.txt", "", &b"hi"[..])]),
+            ..Default::default()
+        };
+        cache.store("main", &streamed, &manager, &std::env::temp_dir());
+        assert!(cache.get("main").is_none());
+    }
+
+    #[test]
+    fn cached_handoff_files_outlive_the_share() {
+        let cache = LastShareOptions::default();
+        let manager = PluginTempFileManager::new();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("large.bin");
+        std::fs::write(&path, b"hi").unwrap();
+        let mut file = SharedFile::from_path(&path, "");
+        file.handoff = Some(true);
+        let options = ShareOptions {
+            files: Some(vec![file]),
+            ..Default::default()
+        };
+
+        // The share adopts the handed-off file and releases it once done.
+        manager.add_file(path.clone());
+        cache.store("main", &options, &manager, dir.path());
+        manager.release_file(&path);
+        assert!(path.exists());
+        let cached = cache.get("main").unwrap().files.unwrap();
+        assert_eq!(cached[0].handoff, None);
+
+        assert!(cache.discard("main", &manager));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn file_data_is_cached_as_a_temp_file() {
+        let cache = LastShareOptions::default();
+        let manager = PluginTempFileManager::new();
+        let dir = tempfile::tempdir().unwrap();
+        let options = ShareOptions {
+            files: Some(vec![data_file("aGk=")]),
+            ..Default::default()
+        };
+
+        cache.store("main", &options, &manager, dir.path());
+        let cached = cache.get("main").unwrap().files.unwrap();
+        assert!(cached[0].data.is_empty());
+        assert_eq!(cached[0].name, "notes.txt");
+        let path = PathBuf::from(cached[0].path.as_deref().unwrap());
+        assert!(path.starts_with(dir.path()));
+        assert!(path.to_string_lossy().ends_with("-notes.txt"));
+        assert_eq!(std::fs::read(&path).unwrap(), b"hi");
+
+        assert!(cache.discard("main", &manager));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn file_that_cannot_be_cached_discards_the_share() {
+        let cache = LastShareOptions::default();
+        let manager = PluginTempFileManager::new();
+        let dir = tempfile::tempdir().unwrap();
+        cache.store("main", &options("first"), &manager, dir.path());
+
+        let invalid = ShareOptions {
+            files: Some(vec![data_file("not base64!")]),
+            ..Default::default()
+        };
+        cache.store("main", &invalid, &manager, dir.path());
+        assert!(cache.get("main").is_none());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::state::PluginTempFileManager;
+use crate::validation::is_within_dir;
+use crate::{Config, Error, ShareExt, ShareOptions};
+
+/// The name of the subdirectory of the plugin temp dir holding the files the
+/// frontend writes to hand them off.
+const HANDOFF_DIR_NAME: &str = "handoff";
+
+/// Returns the directory within `temp_dir` where the frontend writes the files it
+/// hands off, creating it if needed.
+///
+/// The plugin never writes its own temporary files there, so handing off a file
+/// cannot delete one that another share still uses.
+pub(crate) fn handoff_dir(temp_dir: &Path) -> Result<PathBuf, Error> {
+    let dir = temp_dir.join(HANDOFF_DIR_NAME);
+    if !dir.exists() {
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| Error::TempFile(format!("Failed to create handoff dir: {}", e)))?;
+    }
+    Ok(dir)
+}
+
+/// Whether `path` is a file the plugin may delete once it has been handed off.
+///
+/// Only files in the handoff dir qualify, and only while no share manages them
+/// yet, so a file cannot be handed off twice.
+pub(crate) fn is_handoff_file(
+    path: &Path,
+    handoff_dir: &Path,
+    manager: &PluginTempFileManager,
+) -> bool {
+    path.is_file() && is_within_dir(path, handoff_dir) && !manager.is_managed(path)
+}
+
+/// Rejects files shared by path from the frontend that the app has not allowed.
+///
+/// Files in the plugin temp dir are always allowed, since the app writes them
+/// there for a share. Every other path must be allowed by the `tauri-plugin-fs`
+/// scope, unless the `allowUnscopedPaths` option is set in the plugin
+/// configuration. Handed-off files, which the plugin deletes after the share,
+/// must be in the handoff dir whatever the scope.
+pub(crate) fn check_paths<R: Runtime>(
+    app: &AppHandle<R>,
+    options: &ShareOptions,
+) -> Result<(), Error> {
+    let mut files = options
+        .files
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|file| Some((Path::new(file.path.as_deref()?), file.handoff == Some(true))))
+        .peekable();
+
+    if files.peek().is_none() {
+        return Ok(());
+    }
+
+    let temp_dir = app.share().temp_dir().ok();
+    let allow_unscoped_paths = app.state::<Config>().allow_unscoped_paths;
+    for (path, handoff) in files {
+        if handoff {
+            check_handoff_path(app, path)?;
+            continue;
+        }
+        if temp_dir
+            .as_deref()
+            .is_some_and(|temp_dir| is_within_dir(path, temp_dir))
+        {
+            continue;
+        }
+        if !allow_unscoped_paths {
+            check_path(app, path)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_handoff_path<R: Runtime>(app: &AppHandle<R>, path: &Path) -> Result<(), Error> {
+    if path.is_dir() {
+        return Err(Error::InvalidArgs(format!(
+            "Cannot hand off '{}': only files can be handed off, not directories",
+            path.display()
+        )));
+    }
+    let handoff_dir = app.share().handoff_dir()?;
+    if !is_handoff_file(path, &handoff_dir, &app.state::<PluginTempFileManager>()) {
+        return Err(Error::InvalidArgs(format!(
+            "Cannot hand off '{}': handed-off files must be in the handoff dir and not already shared",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "fs-scope")]
+fn check_path<R: Runtime>(app: &AppHandle<R>, path: &Path) -> Result<(), Error> {
+    use tauri_plugin_fs::FsExt;
+
+    let allowed = app
+        .try_fs_scope()
+        .map(|scope| scope.is_allowed(path))
+        .unwrap_or(false);
+    if allowed {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgs(format!(
+            "Path '{}' is not allowed by the file system scope",
+            path.display()
+        )))
+    }
+}
+
+#[cfg(not(feature = "fs-scope"))]
+fn check_path<R: Runtime>(_app: &AppHandle<R>, path: &Path) -> Result<(), Error> {
+    Err(Error::InvalidArgs(format!(
+        "Cannot share '{}': sharing paths from the frontend requires the `fs-scope` feature or the `allowUnscopedPaths` option",
+        path.display()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{handoff_dir, is_handoff_file};
+    use crate::state::PluginTempFileManager;
+
+    #[test]
+    fn only_unmanaged_files_in_the_handoff_dir_can_be_handed_off() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let handoff_dir = handoff_dir(temp_dir.path()).unwrap();
+        let manager = PluginTempFileManager::new();
+        let written = handoff_dir.join("export.zip");
+        std::fs::write(&written, b"zip").unwrap();
+        let temp_file = temp_dir.path().join("share.txt");
+        std::fs::write(&temp_file, b"hi").unwrap();
+
+        assert!(is_handoff_file(&written, &handoff_dir, &manager));
+        assert!(!is_handoff_file(&temp_file, &handoff_dir, &manager));
+        assert!(!is_handoff_file(
+            &handoff_dir.join("../share.txt"),
+            &handoff_dir,
+            &manager
+        ));
+        assert!(!is_handoff_file(&handoff_dir, &handoff_dir, &manager));
+
+        let share_files = manager.begin_share();
+        share_files.add_file(written.clone());
+        assert!(!is_handoff_file(&written, &handoff_dir, &manager));
+        share_files.release();
+    }
+}
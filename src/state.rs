@@ -1,44 +1,1091 @@
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::{CleanupResult, Error, ShareMode, ShareResult};
+
+/// Receives the files deleted to stay under the high-water mark.
+type EvictionSink = Arc<dyn Fn(Vec<PathBuf>) + Send + Sync>;
+
+/// Tells the current time, so tests can move it forward.
+type Clock = Arc<dyn Fn() -> Instant + Send + Sync>;
 
 /// Manages the lifecycle of temporary files created by the plugin.
 ///
-/// This struct holds a thread-safe list of `PathBuf` for all temporary files
-/// that have been created and need to be cleaned up. It's intended to be
-/// managed as a Tauri state.
+/// This struct holds a thread-safe map from the `PathBuf` of every temporary file
+/// that has been created and needs to be cleaned up to the number of shares using
+/// it. A file shared by overlapping shares is only deleted once every one of them
+/// has released it. It's intended to be managed as a Tauri state; clones share
+/// the same underlying map.
+///
+/// Receivers may read a shared file after the share completed, so the files of a
+/// completed share can be kept on disk for a grace period, see
+/// [`Self::with_retain_after_share`]. Releasing such a file within the grace
+/// period defers its deletion until the period ends.
+///
+/// The files can also be bounded by a high-water mark, see
+/// [`Self::with_high_water_mark`].
+#[derive(Clone)]
 pub struct PluginTempFileManager {
-    /// A thread-safe vector to store the paths of temporary files.
-    pub managed_files: Arc<Mutex<Vec<PathBuf>>>,
+    /// A thread-safe map of temporary file paths to their reference counts.
+    pub managed_files: Arc<Mutex<HashMap<PathBuf, usize>>>,
+    /// The time until which each file of a completed share is kept on disk.
+    retained: Arc<Mutex<HashMap<PathBuf, Instant>>>,
+    /// How long the files of a completed share are kept on disk.
+    retain_after_share: Duration,
+    /// The bytes the files may take before the oldest released ones are deleted,
+    /// and the sink receiving the deleted files.
+    high_water_mark: Option<(u64, EvictionSink)>,
+    /// Incremented by [`Self::cancel_all`], which cancels the shares begun before.
+    generation: Arc<AtomicU64>,
+    /// The size of every managed or retained file, recorded when it was added, so
+    /// the high-water mark is enforced without reading the files again. Only
+    /// recorded when there is a high-water mark.
+    sizes: Arc<Mutex<HashMap<PathBuf, TrackedSize>>>,
+    /// The number of files added so far, ordering the tracked files by age.
+    added: Arc<AtomicU64>,
+    /// Deletes the released files once their grace period ends.
+    reaper: Arc<Reaper>,
+    clock: Clock,
+}
+
+/// The size of a temporary file when it was added, and when it was added.
+#[derive(Clone, Copy)]
+struct TrackedSize {
+    bytes: u64,
+    added: u64,
 }
 
+/// The released files waiting for the end of their grace period.
+///
+/// A single thread deletes them, running only while a deletion is pending.
+#[derive(Default)]
+struct Reaper {
+    queue: Mutex<ReaperQueue>,
+    /// Wakes the thread when a file with an earlier deadline is queued.
+    wake: Condvar,
+}
+
+#[derive(Default)]
+struct ReaperQueue {
+    /// The released files by the time they are deleted at, earliest first.
+    deadlines: BinaryHeap<Reverse<(Instant, PathBuf)>>,
+    /// Whether the thread deleting the files is running.
+    running: bool,
+}
+
+/// The paths of the temporary files created by a single share.
+type SharePaths = Arc<Mutex<Vec<PathBuf>>>;
+
 impl PluginTempFileManager {
     pub fn new() -> Self {
         Self {
-            managed_files: Arc::new(Mutex::new(Vec::new())),
+            managed_files: Arc::new(Mutex::new(HashMap::new())),
+            retained: Arc::new(Mutex::new(HashMap::new())),
+            retain_after_share: Duration::ZERO,
+            high_water_mark: None,
+            generation: Arc::new(AtomicU64::new(0)),
+            sizes: Arc::new(Mutex::new(HashMap::new())),
+            added: Arc::new(AtomicU64::new(0)),
+            reaper: Arc::new(Reaper::default()),
+            clock: Arc::new(Instant::now),
         }
     }
 
-    /// Cleans up all files currently managed by this instance.
+    /// Uses `clock` instead of the system clock to time the grace periods.
+    #[cfg(test)]
+    fn with_clock(mut self, clock: impl Fn() -> Instant + Send + Sync + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Keeps the files of a completed share on disk for `grace` after it completed,
+    /// even when they are released earlier. Files are not kept by default.
+    pub fn with_retain_after_share(mut self, grace: Duration) -> Self {
+        self.retain_after_share = grace;
+        self
+    }
+
+    /// Deletes the oldest files no share uses anymore, i.e. released files within
+    /// their grace period, whenever a share adds a file while the files take more
+    /// than `bytes` on disk, until they take at most `bytes`. The deleted files are
+    /// passed to `on_evict`. Files are not bounded by default.
+    ///
+    /// Files still used by a share are never deleted, so the files may stay above
+    /// the mark until they are released. Each file counts with its size when it
+    /// was added, and files are ordered by when they were added.
+    pub fn with_high_water_mark(
+        mut self,
+        bytes: u64,
+        on_evict: impl Fn(Vec<PathBuf>) + Send + Sync + 'static,
+    ) -> Self {
+        self.high_water_mark = Some((bytes, Arc::new(on_evict)));
+        self
+    }
+
+    /// Registers a reference to a temporary file.
+    ///
+    /// Adding a path that is already managed increments its reference count
+    /// instead of tracking it twice. A poisoned lock is recovered rather than
+    /// failing, so the file is never left untracked.
+    pub fn add_file(&self, path: PathBuf) {
+        if self.high_water_mark.is_some() {
+            self.track_size(&path);
+        }
+        *lock(&self.managed_files).entry(path).or_insert(0) += 1;
+    }
+
+    /// Records the size of `path` for the high-water mark. The file is read before
+    /// taking any lock, and keeps its age when it is added again.
+    fn track_size(&self, path: &Path) {
+        let bytes = std::fs::metadata(path).map_or(0, |metadata| metadata.len());
+        let added = self.added.fetch_add(1, Ordering::SeqCst);
+        lock(&self.sizes)
+            .entry(path.to_path_buf())
+            .and_modify(|tracked| tracked.bytes = bytes)
+            .or_insert(TrackedSize { bytes, added });
+    }
+
+    /// Whether `path` is a temporary file still used by a share.
+    pub fn is_managed(&self, path: &Path) -> bool {
+        lock(&self.managed_files).contains_key(path)
+    }
+
+    /// Starts recording the temporary files created by a single share.
+    pub fn begin_share(&self) -> ShareFiles {
+        ShareFiles {
+            manager: self.clone(),
+            paths: Arc::new(Mutex::new(Vec::new())),
+            cancel: None,
+            generation: self.generation.load(Ordering::SeqCst),
+        }
+    }
+
+    /// Cancels every share begun so far, as if each had its own cancelled
+    /// [`ShareCancelToken`].
+    ///
+    /// The shares stop writing temporary files, and a file one of them registers
+    /// afterwards is deleted right away. Shares begun later are not affected.
+    pub(crate) fn cancel_all(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Starts recording the temporary files created by a share that is aborted
+    /// once `cancel` is cancelled.
+    pub fn begin_cancellable_share(&self, cancel: ShareCancelToken) -> ShareFiles {
+        ShareFiles {
+            cancel: Some(cancel),
+            ..self.begin_share()
+        }
+    }
+
+    /// Releases one reference to a temporary file.
+    ///
+    /// The file is deleted once its last reference is released. Returns `true`
+    /// when the file was removed from disk.
+    pub fn release_file(&self, path: &Path) -> bool {
+        match self.release(path) {
+            Some(Ok(())) => true,
+            Some(Err(e)) => {
+                eprintln!("Failed to delete file {}: {}", path.display(), e);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Releases one reference to a temporary file, returning the outcome of the
+    /// deletion when it was the last one and the file is not retained.
+    fn release(&self, path: &Path) -> Option<std::io::Result<()>> {
+        let mut files = lock(&self.managed_files);
+        match files.get_mut(path) {
+            Some(count) if *count > 1 => {
+                *count -= 1;
+                None
+            }
+            Some(_) => {
+                files.remove(path);
+                match self.retained_until(path) {
+                    Some(deadline) => {
+                        self.delete_after(path.to_path_buf(), deadline);
+                        None
+                    }
+                    None => {
+                        lock(&self.sizes).remove(path);
+                        Some(std::fs::remove_file(path))
+                    }
+                }
+            }
+            None => None,
+        }
+    }
+
+    /// Returns the end of the grace period of `path`, if it has not ended yet.
+    fn retained_until(&self, path: &Path) -> Option<Instant> {
+        let mut retained = lock(&self.retained);
+        match retained.get(path) {
+            Some(deadline) if *deadline > (self.clock)() => Some(*deadline),
+            Some(_) => {
+                retained.remove(path);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Deletes the released file `path` once `deadline` has passed, unless it was
+    /// shared again in the meantime.
+    ///
+    /// The file is queued for the reaper thread, which is started when it is not
+    /// running yet.
+    fn delete_after(&self, path: PathBuf, deadline: Instant) {
+        let mut queue = lock(&self.reaper.queue);
+        queue.deadlines.push(Reverse((deadline, path)));
+        if queue.running {
+            self.reaper.wake.notify_one();
+            return;
+        }
+        queue.running = true;
+        let manager = self.clone();
+        std::thread::spawn(move || manager.run_reaper());
+    }
+
+    /// Deletes the queued files as their deadlines pass, until none is left.
+    fn run_reaper(&self) {
+        let mut queue = lock(&self.reaper.queue);
+        loop {
+            let Some(Reverse((deadline, _))) = queue.deadlines.peek() else {
+                queue.running = false;
+                return;
+            };
+            let wait = deadline.saturating_duration_since((self.clock)());
+            if wait.is_zero() {
+                drop(queue);
+                self.reap_expired();
+                queue = lock(&self.reaper.queue);
+            } else {
+                queue = match self.reaper.wake.wait_timeout(queue, wait) {
+                    Ok((guard, _)) => guard,
+                    Err(poisoned) => poisoned.into_inner().0,
+                };
+            }
+        }
+    }
+
+    /// Deletes the queued files whose deadline has passed.
+    fn reap_expired(&self) {
+        let now = (self.clock)();
+        let mut expired = Vec::new();
+        {
+            let mut queue = lock(&self.reaper.queue);
+            while queue
+                .deadlines
+                .peek()
+                .is_some_and(|Reverse((deadline, _))| *deadline <= now)
+            {
+                if let Some(Reverse((_, path))) = queue.deadlines.pop() {
+                    expired.push(path);
+                }
+            }
+        }
+        for path in expired {
+            self.delete_expired(&path, now);
+        }
+    }
+
+    /// Deletes the released file `path` if its grace period ended by `now`.
+    ///
+    /// The file is kept when it was shared again, or its grace period was extended
+    /// by a later share, which queued it again.
+    fn delete_expired(&self, path: &Path, now: Instant) {
+        let files = lock(&self.managed_files);
+        if files.contains_key(path) {
+            return;
+        }
+        let mut retained = lock(&self.retained);
+        match retained.get(path) {
+            Some(deadline) if *deadline <= now => {
+                retained.remove(path);
+            }
+            // Already deleted, e.g. to stay under the high-water mark.
+            _ => return,
+        }
+        lock(&self.sizes).remove(path);
+        if let Err(e) = std::fs::remove_file(path) {
+            eprintln!("Failed to delete file {}: {}", path.display(), e);
+        }
+    }
+
+    /// Deletes the oldest released files while the files take more than the
+    /// high-water mark, if any, and passes them to its sink.
+    fn enforce_high_water_mark(&self) {
+        let Some((bytes, on_evict)) = &self.high_water_mark else {
+            return;
+        };
+        let evicted = self.evict_oldest(*bytes);
+        if !evicted.is_empty() {
+            on_evict(evicted);
+        }
+    }
+
+    /// Deletes the released files within their grace period, oldest first, until
+    /// the managed and retained files take at most `bytes`. Returns the deleted
+    /// paths.
     ///
-    /// This method iterates through the list of file paths, attempts to
-    /// delete each file, and clears the list. It also handles a poisoned
-    /// mutex gracefully by recovering the inner data and continuing the cleanup.
-    pub fn cleanup_all_managed_files(&self) {
-        let mut files = match self.managed_files.lock() {
-            Ok(guard) => guard,
-            Err(poisoned) => {
-                eprintln!("Mutex was poisoned during cleanup: {:?}", poisoned);
-                poisoned.into_inner()
+    /// The files are chosen from the sizes tracked when they were added, and
+    /// deleted once the locks are released.
+    fn evict_oldest(&self, bytes: u64) -> Vec<PathBuf> {
+        let candidates = {
+            let files = lock(&self.managed_files);
+            let mut retained = lock(&self.retained);
+            let mut sizes = lock(&self.sizes);
+            let mut total: u64 = sizes.values().map(|tracked| tracked.bytes).sum();
+            let mut released: Vec<(u64, PathBuf, u64)> = retained
+                .keys()
+                .filter(|path| !files.contains_key(*path))
+                .filter_map(|path| {
+                    let tracked = sizes.get(path)?;
+                    Some((tracked.added, path.clone(), tracked.bytes))
+                })
+                .collect();
+            released.sort();
+
+            let mut candidates = Vec::new();
+            for (_, path, size) in released {
+                if total <= bytes {
+                    break;
+                }
+                retained.remove(&path);
+                sizes.remove(&path);
+                total -= size;
+                candidates.push(path);
             }
+            candidates
         };
+
+        candidates
+            .into_iter()
+            .filter(|path| match std::fs::remove_file(path) {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!("Failed to delete file {}: {}", path.display(), e);
+                    false
+                }
+            })
+            .collect()
+    }
+
+    /// Releases one reference to every managed file.
+    ///
+    /// Files whose last reference is released are deleted, while files still used
+    /// by an overlapping share are kept until that share releases them too, and
+    /// files within their grace period are deleted once it ends. The result counts
+    /// the files deleted right away and lists those that could not be deleted.
+    pub fn release_all_managed_files(&self) -> CleanupResult {
+        let paths: Vec<PathBuf> = lock(&self.managed_files).keys().cloned().collect();
+        let mut result = CleanupResult::default();
+        for path in paths {
+            match self.release(&path) {
+                Some(Ok(())) => result.removed += 1,
+                Some(Err(e)) => {
+                    eprintln!("Failed to delete file {}: {}", path.display(), e);
+                    result.failed.push(path.display().to_string());
+                }
+                None => {}
+            }
+        }
+        result
+    }
+
+    /// Deletes the temporary file `path` right away and stops managing it, whatever
+    /// its reference count or grace period.
+    ///
+    /// Returns `None` when `path` is not managed by the plugin, in which case it is
+    /// left alone.
+    pub fn remove_and_delete_file(&self, path: &Path) -> Option<std::io::Result<()>> {
+        let managed = lock(&self.managed_files).remove(path).is_some();
+        let retained = lock(&self.retained).remove(path).is_some();
+        lock(&self.sizes).remove(path);
+        (managed || retained).then(|| std::fs::remove_file(path))
+    }
+
+    /// Deletes the temporary files at `paths` with [`Self::remove_and_delete_file`].
+    ///
+    /// Paths that do not resolve inside `dir`, the plugin's temp dir, or that are
+    /// not managed by the plugin are skipped, so no other file can be deleted.
+    /// The result lists the removed and the skipped paths, and the skipped ones
+    /// are reported together as a warning.
+    pub fn remove_files(&self, paths: &[String], dir: &Path) -> CleanupResult {
+        let mut result = CleanupResult::default();
+        let mut warnings = Vec::new();
+        for path in paths {
+            let reason = if !crate::validation::is_within_dir(Path::new(path), dir) {
+                "not inside the plugin temp dir"
+            } else {
+                match self.remove_and_delete_file(Path::new(path)) {
+                    Some(Ok(())) => {
+                        result.removed += 1;
+                        result.removed_paths.push(path.clone());
+                        continue;
+                    }
+                    Some(Err(e)) => {
+                        eprintln!("Failed to delete file {}: {}", path, e);
+                        result.failed.push(path.clone());
+                        continue;
+                    }
+                    None => "not managed by the plugin",
+                }
+            };
+            warnings.push(format!("{}: {}", path, reason));
+            result.skipped.push(path.clone());
+        }
+        if !warnings.is_empty() {
+            eprintln!("Skipped paths during cleanup: {:?}", warnings);
+        }
+        result
+    }
+
+    /// Cleans up all files currently managed by this instance.
+    ///
+    /// This method iterates through the managed file paths, attempts to
+    /// delete each file regardless of its reference count, and clears the map.
+    /// Released files still within their grace period are deleted as well, since
+    /// this runs when the app exits. It also handles a poisoned mutex gracefully
+    /// by recovering the inner data and continuing the cleanup. The result counts
+    /// the deleted files and lists those that could not be deleted.
+    pub fn cleanup_all_managed_files(&self) -> CleanupResult {
+        let mut files = lock(&self.managed_files);
+        let mut result = CleanupResult::default();
         let mut errors = Vec::new();
-        for path in files.drain(..) {
-            if let Err(e) = std::fs::remove_file(&path) {
-                errors.push(format!("Failed to delete file {}: {}", path.display(), e));
+        for (path, _) in files.drain() {
+            match std::fs::remove_file(&path) {
+                Ok(()) => result.removed += 1,
+                Err(e) => {
+                    errors.push(format!("Failed to delete file {}: {}", path.display(), e));
+                    result.failed.push(path.display().to_string());
+                }
+            }
+        }
+        for (path, _) in lock(&self.retained).drain() {
+            match std::fs::remove_file(&path) {
+                Ok(()) => result.removed += 1,
+                // Deleted above, or once its grace period ended.
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    errors.push(format!("Failed to delete file {}: {}", path.display(), e));
+                    result.failed.push(path.display().to_string());
+                }
             }
         }
+        lock(&self.sizes).clear();
         if !errors.is_empty() {
             eprintln!("Errors during cleanup: {:?}", errors);
         }
+        result
+    }
+}
+
+/// The temporary files created by a single share.
+///
+/// Files added here are registered with the [`PluginTempFileManager`] it was
+/// created from, and also remembered so the share can release exactly its own
+/// files later. Clones record into the same list.
+#[derive(Clone)]
+pub struct ShareFiles {
+    manager: PluginTempFileManager,
+    paths: SharePaths,
+    cancel: Option<ShareCancelToken>,
+    /// The generation of the manager when the share began.
+    generation: u64,
+}
+
+impl ShareFiles {
+    /// Registers a temporary file created by this share.
+    ///
+    /// Like [`PluginTempFileManager::add_file`], this recovers from a poisoned lock.
+    /// Older released files are then deleted if the files exceed the high-water
+    /// mark of the manager.
+    ///
+    /// Once all shares were cancelled, the file is deleted right away instead, as
+    /// the cleanup of the cancelled shares may already have run.
+    pub fn add_file(&self, path: PathBuf) {
+        self.manager.add_file(path.clone());
+        if self.is_cancelled_by_manager() {
+            self.manager.release_file(&path);
+            return;
+        }
+        lock(&self.paths).push(path);
+        self.manager.enforce_high_water_mark();
+    }
+
+    /// Registers a temporary file of this share that outlives it, e.g. a file
+    /// opened in another app, which may read it for as long as it stays open.
+    ///
+    /// The file is managed like any other, but not remembered as a file of the
+    /// share: it is not retained or released along with the share, and is only
+    /// deleted by a cleanup, such as the one when the app exits.
+    pub(crate) fn add_lasting_file(&self, path: PathBuf) {
+        self.manager.add_file(path.clone());
+        if self.is_cancelled_by_manager() {
+            self.manager.release_file(&path);
+        }
+    }
+
+    /// Returns the paths of the files registered so far.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        lock(&self.paths).clone()
+    }
+
+    /// Fails with [`Error::Cancelled`] once the share's cancel token is cancelled,
+    /// or all shares were cancelled through [`PluginTempFileManager::cancel_all`].
+    ///
+    /// Platforms call this before writing each temporary file and before showing
+    /// the share UI.
+    pub(crate) fn check_cancelled(&self) -> Result<(), Error> {
+        let token_cancelled = self.cancel.as_ref().is_some_and(|c| c.is_cancelled());
+        if token_cancelled || self.is_cancelled_by_manager() {
+            return Err(Error::Cancelled);
+        }
+        Ok(())
+    }
+
+    fn is_cancelled_by_manager(&self) -> bool {
+        self.manager.generation.load(Ordering::SeqCst) != self.generation
+    }
+
+    /// Marks the share as completed, starting the grace period of its files
+    /// unless they were only shared by [`ShareMode::Reference`].
+    pub(crate) fn finish(&self, mode: ShareMode) {
+        let grace = self.manager.retain_after_share;
+        if grace.is_zero() || mode == ShareMode::Reference {
+            return;
+        }
+        let deadline = (self.manager.clock)() + grace;
+        let mut retained = lock(&self.manager.retained);
+        for path in self.paths() {
+            retained.insert(path, deadline);
+        }
+    }
+
+    /// Releases the files registered so far, e.g. after the share was cancelled.
+    pub(crate) fn release(&self) {
+        for path in self.paths() {
+            self.manager.release_file(&path);
+        }
+    }
+}
+
+/// Aborts a share started with `Share::share_cancellable` while it is prepared.
+///
+/// Cancelling stops the creation of temporary files, skips showing the share UI
+/// and deletes the files already written. A share UI that is already shown is
+/// not dismissed. Clones cancel the same share.
+#[derive(Debug, Clone, Default)]
+pub struct ShareCancelToken(Arc<AtomicBool>);
+
+impl ShareCancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the share. Has no effect once the share UI is shown.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether [`Self::cancel`] was called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// A completed share whose temporary files live as long as the session.
+///
+/// Returned by `Share::begin_share`, it releases the share's files when dropped,
+/// so a share tied to a scope is cleaned up deterministically instead of when
+/// the app exits.
+///
+/// The frontend `cleanup` command releases one reference of every managed file,
+/// including the files of live sessions. If it runs while a session is alive,
+/// the session's files may be deleted before the session ends.
+///
+/// While the session is alive, [`Self::files_exist`] reports whether its files
+/// still exist, e.g. before sharing them again. The files are read from the share
+/// as it goes, so a file the share writes after it resolved, e.g. one still
+/// loading in the background on Windows, belongs to the session too.
+///
+/// On mobile the native side manages its own files, so `files` is always empty.
+pub struct ShareSession {
+    result: ShareResult,
+    files: Option<ShareFiles>,
+    #[cfg(desktop)]
+    _permit: Option<crate::limit::SharePermit>,
+}
+
+impl ShareSession {
+    pub(crate) fn new(result: ShareResult, files: ShareFiles) -> Self {
+        Self {
+            result,
+            files: Some(files),
+            #[cfg(desktop)]
+            _permit: None,
+        }
+    }
+
+    /// Keeps `permit` until the session is dropped, so the share stays in flight
+    /// for the limiter as long as its files are kept.
+    #[cfg(desktop)]
+    pub(crate) fn holding(mut self, permit: crate::limit::SharePermit) -> Self {
+        self._permit = Some(permit);
+        self
+    }
+
+    #[cfg(mobile)]
+    pub(crate) fn detached(result: ShareResult) -> Self {
+        Self {
+            result,
+            files: None,
+        }
+    }
+
+    /// The outcome of the share.
+    pub fn result(&self) -> &ShareResult {
+        &self.result
+    }
+
+    /// The temporary files created for the share so far.
+    pub fn files(&self) -> Vec<PathBuf> {
+        self.files.as_ref().map(ShareFiles::paths).unwrap_or_default()
+    }
+
+    /// Reports whether each file of the share still exists on disk, e.g. after the
+    /// OS or another process removed some of them.
+    pub fn files_exist(&self) -> Vec<(PathBuf, bool)> {
+        self.files()
+            .into_iter()
+            .map(|path| {
+                let exists = path.exists();
+                (path, exists)
+            })
+            .collect()
+    }
+}
+
+impl Drop for ShareSession {
+    fn drop(&mut self) {
+        if let Some(files) = &self.files {
+            files.release();
+        }
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PluginTempFileManager, ShareCancelToken, ShareSession};
+    use crate::{Error, ShareMode, ShareResult};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn file_shared_twice_is_deleted_after_both_releases() {
+        let manager = PluginTempFileManager::new();
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.into_temp_path().keep().unwrap();
+
+        manager.add_file(path.clone());
+        manager.add_file(path.clone());
+
+        assert!(!manager.release_file(&path));
+        assert!(path.exists());
+        assert!(manager.release_file(&path));
+        assert!(!path.exists());
+        assert!(!manager.release_file(&path));
+    }
+
+    #[test]
+    fn release_all_keeps_files_with_remaining_references() {
+        let manager = PluginTempFileManager::new();
+        let shared = tempfile::NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        let single = tempfile::NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+
+        manager.add_file(shared.clone());
+        manager.add_file(shared.clone());
+        manager.add_file(single.clone());
+        let released = manager.release_all_managed_files();
+
+        assert_eq!(released.removed, 1);
+        assert!(released.failed.is_empty());
+        assert!(shared.exists());
+        assert!(!single.exists());
+
+        let cleaned = manager.cleanup_all_managed_files();
+        assert_eq!(cleaned.removed, 1);
+        assert!(!shared.exists());
+    }
+
+    #[test]
+    fn remove_files_only_deletes_managed_files_in_the_temp_dir() {
+        let manager = PluginTempFileManager::new();
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::NamedTempFile::new().unwrap();
+        let managed = dir.path().join("managed.txt");
+        let unmanaged = dir.path().join("unmanaged.txt");
+        std::fs::write(&managed, b"hi").unwrap();
+        std::fs::write(&unmanaged, b"hi").unwrap();
+        manager.add_file(managed.clone());
+        manager.add_file(managed.clone());
+        manager.add_file(outside.path().to_path_buf());
+
+        let paths: Vec<String> = [&managed, &unmanaged, &outside.path().to_path_buf()]
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        let result = manager.remove_files(&paths, dir.path());
+
+        assert_eq!(result.removed, 1);
+        assert_eq!(result.removed_paths, paths[..1]);
+        assert_eq!(result.skipped, paths[1..]);
+        assert!(!managed.exists() && !manager.is_managed(&managed));
+        assert!(unmanaged.exists());
+        assert!(outside.path().exists());
+    }
+
+    #[test]
+    fn cleanup_reports_files_that_could_not_be_deleted() {
+        let manager = PluginTempFileManager::new();
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing.txt");
+
+        manager.add_file(missing.clone());
+        let result = manager.cleanup_all_managed_files();
+
+        assert_eq!(result.removed, 0);
+        assert_eq!(result.failed, vec![missing.display().to_string()]);
+    }
+
+    #[test]
+    fn session_reports_which_of_its_files_exist() {
+        let manager = PluginTempFileManager::new();
+        let files = manager.begin_share();
+        let kept = tempfile::NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        let removed = tempfile::NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        files.add_file(kept.clone());
+        files.add_file(removed.clone());
+
+        let session = ShareSession::new(ShareResult::Cancelled, files);
+        std::fs::remove_file(&removed).unwrap();
+
+        assert_eq!(
+            session.files_exist(),
+            vec![(kept.clone(), true), (removed.clone(), false)]
+        );
+
+        drop(session);
+        assert!(!kept.exists());
+        assert!(!manager.is_managed(&kept));
+    }
+
+    #[test]
+    fn session_owns_the_files_written_after_it_began() {
+        let manager = PluginTempFileManager::new();
+        let files = manager.begin_share();
+        let session = ShareSession::new(ShareResult::Cancelled, files.clone());
+        let late = tempfile::NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        files.add_file(late.clone());
+
+        assert_eq!(session.files(), vec![late.clone()]);
+        drop(session);
+        assert!(!late.exists());
+        assert!(!manager.is_managed(&late));
+    }
+
+    #[test]
+    fn lasting_file_outlives_the_share() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path().to_path_buf();
+        let manager = PluginTempFileManager::new();
+        let files = manager.begin_share();
+        files.add_lasting_file(path.clone());
+
+        files.finish(ShareMode::Copy);
+        files.release();
+        assert!(path.exists());
+        assert!(manager.is_managed(&path));
+
+        manager.release_all_managed_files();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn cancel_all_cancels_only_the_shares_begun_before() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = PluginTempFileManager::new();
+        let files = manager.begin_share();
+        manager.cancel_all();
+
+        assert!(matches!(files.check_cancelled(), Err(Error::Cancelled)));
+        let late = dir.path().join("late.txt");
+        std::fs::write(&late, b"late").unwrap();
+        files.add_file(late.clone());
+        assert!(!late.exists());
+        assert!(!manager.is_managed(&late));
+        assert!(files.paths().is_empty());
+
+        assert!(manager.begin_share().check_cancelled().is_ok());
+    }
+
+    #[test]
+    fn cancelled_share_releases_its_files() {
+        let manager = PluginTempFileManager::new();
+        let cancel = ShareCancelToken::new();
+        let files = manager.begin_cancellable_share(cancel.clone());
+        let path = tempfile::NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        files.add_file(path.clone());
+
+        assert!(files.check_cancelled().is_ok());
+        cancel.cancel();
+        assert!(matches!(files.check_cancelled(), Err(Error::Cancelled)));
+        assert!(manager.begin_share().check_cancelled().is_ok());
+
+        files.release();
+        assert!(!path.exists());
+        assert!(!manager.is_managed(&path));
+    }
+
+    #[test]
+    fn released_files_are_kept_during_the_grace_period() {
+        let manager =
+            PluginTempFileManager::new().with_retain_after_share(Duration::from_millis(200));
+        let files = manager.begin_share();
+        let path = tempfile::NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        files.add_file(path.clone());
+        files.finish(ShareMode::Copy);
+
+        let released = manager.release_all_managed_files();
+        assert_eq!(released.removed, 0);
+        assert!(path.exists());
+        assert!(!manager.is_managed(&path));
+
+        std::thread::sleep(Duration::from_millis(500));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn released_files_are_deleted_once_their_grace_period_ends() {
+        let now = Arc::new(Mutex::new(Instant::now()));
+        let manager = PluginTempFileManager::new()
+            .with_retain_after_share(Duration::from_secs(60))
+            .with_clock({
+                let now = now.clone();
+                move || *now.lock().unwrap()
+            });
+        let dir = tempfile::tempdir().unwrap();
+        let first = manager.begin_share();
+        let early = dir.path().join("early.txt");
+        std::fs::write(&early, b"early").unwrap();
+        first.add_file(early.clone());
+        first.finish(ShareMode::Copy);
+        first.release();
+
+        *now.lock().unwrap() += Duration::from_secs(30);
+        let second = manager.begin_share();
+        let late = dir.path().join("late.txt");
+        std::fs::write(&late, b"late").unwrap();
+        second.add_file(late.clone());
+        second.finish(ShareMode::Copy);
+        second.release();
+
+        manager.reap_expired();
+        assert!(early.exists() && late.exists());
+
+        *now.lock().unwrap() += Duration::from_secs(31);
+        manager.reap_expired();
+        assert!(!early.exists() && late.exists());
+
+        *now.lock().unwrap() += Duration::from_secs(30);
+        manager.reap_expired();
+        assert!(!late.exists());
+    }
+
+    #[test]
+    fn file_shared_again_outlives_its_first_grace_period() {
+        let now = Arc::new(Mutex::new(Instant::now()));
+        let manager = PluginTempFileManager::new()
+            .with_retain_after_share(Duration::from_secs(60))
+            .with_clock({
+                let now = now.clone();
+                move || *now.lock().unwrap()
+            });
+        let path = tempfile::NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        let first = manager.begin_share();
+        first.add_file(path.clone());
+        first.finish(ShareMode::Copy);
+        first.release();
+
+        let second = manager.begin_share();
+        second.add_file(path.clone());
+        *now.lock().unwrap() += Duration::from_secs(61);
+        manager.reap_expired();
+        assert!(path.exists() && manager.is_managed(&path));
+
+        second.finish(ShareMode::Copy);
+        second.release();
+        *now.lock().unwrap() += Duration::from_secs(61);
+        manager.reap_expired();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn referenced_files_are_deleted_without_a_grace_period() {
+        let manager = PluginTempFileManager::new().with_retain_after_share(Duration::from_secs(60));
+        let files = manager.begin_share();
+        let path = tempfile::NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        files.add_file(path.clone());
+        files.finish(ShareMode::Reference);
+
+        assert_eq!(manager.release_all_managed_files().removed, 1);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn oldest_released_files_are_evicted_above_the_high_water_mark() {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let manager = PluginTempFileManager::new()
+            .with_retain_after_share(Duration::from_secs(60))
+            .with_high_water_mark(10, {
+                let evicted = evicted.clone();
+                move |paths| evicted.lock().unwrap().extend(paths)
+            });
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, b"12345").unwrap();
+            path
+        };
+
+        let released = manager.begin_share();
+        let (oldest, older) = (write("oldest.txt"), write("older.txt"));
+        released.add_file(oldest.clone());
+        released.add_file(older.clone());
+        released.finish(ShareMode::Copy);
+        released.release();
+        let active = manager.begin_share();
+        let used = write("used.txt");
+        active.add_file(used.clone());
+        assert_eq!(*evicted.lock().unwrap(), vec![oldest.clone()]);
+        assert!(!oldest.exists() && older.exists());
+
+        // Files still used by a share are kept, even above the mark.
+        let newest = write("newest.txt");
+        active.add_file(newest.clone());
+        assert_eq!(*evicted.lock().unwrap(), vec![oldest, older.clone()]);
+        assert!(!older.exists() && used.exists() && newest.exists());
+    }
+
+    #[test]
+    fn deleted_files_no_longer_count_towards_the_high_water_mark() {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let manager = PluginTempFileManager::new()
+            .with_retain_after_share(Duration::from_secs(60))
+            .with_high_water_mark(10, {
+                let evicted = evicted.clone();
+                move |paths| evicted.lock().unwrap().extend(paths)
+            });
+        let dir = tempfile::tempdir().unwrap();
+        let write = |name: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, b"12345").unwrap();
+            path
+        };
+
+        let referenced = manager.begin_share();
+        let deleted = write("deleted.txt");
+        referenced.add_file(deleted.clone());
+        referenced.finish(ShareMode::Reference);
+        referenced.release();
+        assert!(!deleted.exists());
+
+        let retained = manager.begin_share();
+        let kept = write("kept.txt");
+        retained.add_file(kept.clone());
+        retained.finish(ShareMode::Copy);
+        retained.release();
+
+        manager.begin_share().add_file(write("new.txt"));
+        assert!(evicted.lock().unwrap().is_empty());
+        assert!(kept.exists());
+    }
+
+    #[test]
+    fn poisoned_lock_does_not_lose_files() {
+        let manager = PluginTempFileManager::new();
+        let files = manager.begin_share();
+        let poisoner = manager.clone();
+        std::thread::spawn(move || {
+            let _guard = poisoner.managed_files.lock().unwrap();
+            panic!("poison the managed files");
+        })
+        .join()
+        .unwrap_err();
+        assert!(manager.managed_files.is_poisoned());
+
+        let path = tempfile::NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        files.add_file(path.clone());
+        assert!(manager.is_managed(&path));
+        assert_eq!(files.paths(), vec![path.clone()]);
+
+        assert_eq!(manager.release_all_managed_files().removed, 1);
+        assert!(!path.exists());
+        assert_eq!(manager.cleanup_all_managed_files().removed, 0);
     }
 }
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::{Error, Result, ShareOptions, UploadProgressEvent};
+
+type UrlFuture = Pin<Box<dyn Future<Output = Result<String>> + Send>>;
+type UrlProvider = Arc<dyn Fn(UploadProgress) -> UrlFuture + Send + Sync>;
+type ProgressSink = Arc<dyn Fn(UploadProgressEvent) + Send + Sync>;
+type UrlTransformer = Arc<dyn Fn(String) -> UrlFuture + Send + Sync>;
+
+/// How long the URL transformer may take before the original URL is shared.
+///
+/// A transformer usually calls a remote service, e.g. a link shortener, which may
+/// never answer. The share dialog waits for it, so it is bounded like a failure.
+const URL_TRANSFORMER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The URL providers registered from Rust, by id.
+///
+/// A share whose `url_provider_id` is set gets its `url` from the matching
+/// provider right before it is performed, so links are only minted for shares
+/// that actually happen. The URL is then passed through the URL transformer, if
+/// one is set. Clones share the same registry.
+#[derive(Clone)]
+pub(crate) struct UrlProviders {
+    providers: Arc<Mutex<HashMap<String, UrlProvider>>>,
+    /// Rewrites the URL of every share, e.g. to shorten it.
+    transformer: Arc<Mutex<Option<UrlTransformer>>>,
+    /// Receives the progress reported by uploaders, e.g. to emit it as an event.
+    on_progress: Option<ProgressSink>,
+    /// How long the transformer may take, see [`URL_TRANSFORMER_TIMEOUT`].
+    transformer_timeout: Duration,
+}
+
+impl Default for UrlProviders {
+    fn default() -> Self {
+        Self {
+            providers: Default::default(),
+            transformer: Default::default(),
+            on_progress: None,
+            transformer_timeout: URL_TRANSFORMER_TIMEOUT,
+        }
+    }
+}
+
+/// Reports the progress of an uploader registered with `register_uploader`.
+///
+/// Each report is emitted as the `vnidrop-share:upload-progress` event. Clones
+/// report for the same upload.
+#[derive(Clone)]
+pub struct UploadProgress {
+    uploader_id: String,
+    on_progress: Option<ProgressSink>,
+}
+
+impl UploadProgress {
+    /// Reports that `sent` bytes were uploaded so far, out of `total` when known.
+    pub fn report(&self, sent: u64, total: Option<u64>) {
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(UploadProgressEvent {
+                uploader_id: self.uploader_id.clone(),
+                sent,
+                total,
+            });
+        }
+    }
+}
+
+impl UrlProviders {
+    /// Creates a registry passing the progress reported by uploaders to `on_progress`.
+    pub(crate) fn with_progress_sink(
+        on_progress: impl Fn(UploadProgressEvent) + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            on_progress: Some(Arc::new(on_progress)),
+            ..Default::default()
+        }
+    }
+
+    /// Registers `provider` under `id`, replacing any provider with the same id.
+    pub(crate) fn register<F, Fut>(&self, id: String, provider: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        let provider: UrlProvider = Arc::new(move |_| Box::pin(provider()));
+        lock(&self.providers).insert(id, provider);
+    }
+
+    /// Registers `uploader` under `id`, like a provider that reports its progress.
+    pub(crate) fn register_uploader<F, Fut>(&self, id: String, uploader: F)
+    where
+        F: Fn(UploadProgress) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        let provider: UrlProvider = Arc::new(move |progress| Box::pin(uploader(progress)));
+        lock(&self.providers).insert(id, provider);
+    }
+
+    /// Sets `transformer` as the function rewriting the URL of every share,
+    /// replacing the previous one.
+    pub(crate) fn set_transformer<F, Fut>(&self, transformer: F)
+    where
+        F: Fn(String) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String>> + Send + 'static,
+    {
+        let transformer: UrlTransformer = Arc::new(move |url| Box::pin(transformer(url)));
+        *lock(&self.transformer) = Some(transformer);
+    }
+
+    /// Replaces `url_provider_id` with the URL minted by the matching provider,
+    /// then passes the URL through the transformer.
+    ///
+    /// An unknown id, or a provider that fails, fails the share before any native
+    /// UI is shown. A transformer that fails, or takes longer than
+    /// [`URL_TRANSFORMER_TIMEOUT`], only leaves the URL as it was, so the original
+    /// link is shared. Options without a URL are returned unchanged.
+    pub(crate) async fn resolve(&self, mut options: ShareOptions) -> Result<ShareOptions> {
+        if let Some(id) = options.url_provider_id.take() {
+            let provider = lock(&self.providers).get(&id).cloned();
+            let provider = provider.ok_or_else(|| {
+                Error::InvalidArgs(format!("No URL provider is registered with id '{}'", id))
+            })?;
+            let progress = UploadProgress {
+                uploader_id: id,
+                on_progress: self.on_progress.clone(),
+            };
+            options.url = Some(provider(progress).await?);
+        }
+        let transformer = lock(&self.transformer).clone();
+        if let (Some(transformer), Some(url)) = (transformer, options.url.as_ref()) {
+            match tokio::time::timeout(self.transformer_timeout, transformer(url.clone())).await {
+                Ok(Ok(transformed)) => options.url = Some(transformed),
+                Ok(Err(e)) => eprintln!(
+                    "Failed to transform the shared URL, sharing it as is: {}",
+                    e
+                ),
+                Err(_) => eprintln!(
+                    "The URL transformer did not finish within {:?}, sharing the URL as is",
+                    self.transformer_timeout
+                ),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tauri::async_runtime::block_on;
+
+    #[test]
+    fn uploader_progress_reaches_the_sink() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let providers = UrlProviders::with_progress_sink({
+            let events = events.clone();
+            move |event| events.lock().unwrap().push(event)
+        });
+        providers.register_uploader("upload".to_string(), |progress| async move {
+            progress.report(512, Some(1024));
+            progress.report(1024, Some(1024));
+            Ok("https://example.com/file".to_string())
+        });
+        let options = ShareOptions {
+            url_provider_id: Some("upload".to_string()),
+            ..Default::default()
+        };
+
+        let options = block_on(providers.resolve(options)).unwrap();
+
+        assert_eq!(options.url.as_deref(), Some("https://example.com/file"));
+        let sent: Vec<u64> = events.lock().unwrap().iter().map(|e| e.sent).collect();
+        assert_eq!(sent, vec![512, 1024]);
+        assert_eq!(events.lock().unwrap()[0].uploader_id, "upload");
+    }
+
+    #[test]
+    fn transformer_rewrites_the_url_or_leaves_it_on_failure() {
+        let providers = UrlProviders::default();
+        providers.register("upload".to_string(), || async {
+            Ok("https://example.com/file".to_string())
+        });
+        providers.set_transformer(|url| async move {
+            if url.contains("fail") {
+                return Err(Error::NativeApi("shortener unavailable".to_string()));
+            }
+            Ok(url.replace("https://example.com", "https://ex.co"))
+        });
+        let resolve = |options| block_on(providers.resolve(options)).unwrap().url;
+
+        let minted = ShareOptions {
+            url_provider_id: Some("upload".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(resolve(minted).as_deref(), Some("https://ex.co/file"));
+        let failing = ShareOptions {
+            url: Some("https://example.com/fail".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve(failing).as_deref(),
+            Some("https://example.com/fail")
+        );
+        assert_eq!(resolve(ShareOptions::default()), None);
+    }
+
+    #[test]
+    fn hanging_transformer_leaves_the_url_once_it_times_out() {
+        let providers = UrlProviders {
+            transformer_timeout: Duration::from_millis(20),
+            ..Default::default()
+        };
+        providers.set_transformer(|_| std::future::pending());
+        let options = ShareOptions {
+            url: Some("https://example.com/file".to_string()),
+            ..Default::default()
+        };
+
+        let options = block_on(providers.resolve(options)).unwrap();
+
+        assert_eq!(options.url.as_deref(), Some("https://example.com/file"));
+    }
+}
@@ -0,0 +1,1565 @@
+use base64::{engine::general_purpose, Engine as _};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+use tauri::Url;
+use zeroize::Zeroizing;
+
+use crate::models::{ShareOptions, SharedFile, ValidatedFile, ValidatedShare};
+use crate::{Config, Error};
+
+/// The MIME type used when nothing more specific can be determined.
+pub(crate) const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
+
+/// File names that Windows reserves for devices, regardless of extension.
+const RESERVED_FILE_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Known file extensions and their MIME types, used when the caller does not provide one.
+const EXTENSION_MIME_TYPES: &[(&str, &str)] = &[
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("json", "application/json"),
+    ("xml", "application/xml"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+    ("heic", "image/heic"),
+    ("mp3", "audio/mpeg"),
+    ("wav", "audio/wav"),
+    ("mp4", "video/mp4"),
+    ("mov", "video/quicktime"),
+];
+
+/// The largest decoded payload of a single custom data format, in bytes.
+#[cfg(any(target_os = "windows", test))]
+pub(crate) const MAX_CUSTOM_FORMAT_BYTES: usize = 4 * 1024 * 1024;
+
+/// The standard formats of a Windows data package, which the plugin sets itself.
+#[cfg(any(target_os = "windows", test))]
+const STANDARD_DATA_FORMATS: &[&str] = &[
+    "Text",
+    "Uri",
+    "WebLink",
+    "ApplicationLink",
+    "Html",
+    "Rtf",
+    "Bitmap",
+    "StorageItems",
+];
+
+/// The Base64 variants accepted for file data, in the order they are tried.
+const BASE64_ENGINES: &[&general_purpose::GeneralPurpose] = &[
+    &general_purpose::STANDARD,
+    &general_purpose::URL_SAFE,
+    &general_purpose::STANDARD_NO_PAD,
+    &general_purpose::URL_SAFE_NO_PAD,
+];
+
+/// Decodes the Base64 content of a shared file.
+///
+/// Standard Base64 is tried first, then the URL-safe alphabet, then both without
+/// padding, since frontends commonly produce any of them.
+///
+/// The error names the file, and its `index` among the files of the share when
+/// given, so the failing file of a multi-file share can be found.
+pub(crate) fn decode_file_data(file: &SharedFile, index: Option<usize>) -> Result<Vec<u8>, Error> {
+    BASE64_ENGINES
+        .iter()
+        .find_map(|engine| engine.decode(&file.data).ok())
+        .ok_or_else(|| {
+            Error::InvalidArgs(match index {
+                Some(index) => format!("Invalid Base64 in file '{}' (index {})", file.name, index),
+                None => format!("Invalid Base64 in file '{}'", file.name),
+            })
+        })
+}
+
+/// Decodes the payloads of the custom data formats of a share, by format id.
+///
+/// A format id must not be empty or name a standard data format, compared
+/// ignoring case, and each payload must decode to at most
+/// [`MAX_CUSTOM_FORMAT_BYTES`]. Payloads whose Base64 is already too long for
+/// that are rejected without being decoded.
+#[cfg(any(target_os = "windows", test))]
+pub(crate) fn decode_custom_formats(
+    options: &ShareOptions,
+) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let Some(formats) = options.custom_formats.as_ref() else {
+        return Ok(Vec::new());
+    };
+    formats
+        .iter()
+        .map(|(format_id, data)| {
+            if format_id.trim().is_empty() {
+                return Err(Error::InvalidArgs(
+                    "A custom data format id cannot be empty".to_string(),
+                ));
+            }
+            if STANDARD_DATA_FORMATS
+                .iter()
+                .any(|standard| standard.eq_ignore_ascii_case(format_id))
+            {
+                return Err(Error::InvalidArgs(format!(
+                    "'{}' is a standard data format and cannot be set as a custom one",
+                    format_id
+                )));
+            }
+            let too_large = |len: usize| {
+                Error::InvalidArgs(format!(
+                    "Custom format '{}' is {} bytes, more than the {} bytes allowed",
+                    format_id, len, MAX_CUSTOM_FORMAT_BYTES
+                ))
+            };
+            if data.len() > MAX_CUSTOM_FORMAT_BYTES.div_ceil(3) * 4 {
+                return Err(too_large(data.len() / 4 * 3));
+            }
+            let bytes = BASE64_ENGINES
+                .iter()
+                .find_map(|engine| engine.decode(data).ok())
+                .ok_or_else(|| {
+                    Error::InvalidArgs(format!("Invalid Base64 in custom format '{}'", format_id))
+                })?;
+            if bytes.len() > MAX_CUSTOM_FORMAT_BYTES {
+                return Err(too_large(bytes.len()));
+            }
+            Ok((format_id.clone(), bytes))
+        })
+        .collect()
+}
+
+/// What a path shared by reference points at.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum SharedPath {
+    /// A regular file, with its size in bytes.
+    File(u64),
+    /// A readable directory, shared as a folder item.
+    Directory,
+}
+
+/// Checks that a path shared by reference points at an existing file or a
+/// readable directory.
+///
+/// Relative paths are rejected, since their meaning depends on the working
+/// directory of the app.
+pub(crate) fn check_shared_path(path: &str) -> Result<SharedPath, Error> {
+    let path = Path::new(path);
+    if !path.is_absolute() {
+        return Err(Error::InvalidArgs(format!(
+            "File path '{}' must be absolute",
+            path.display()
+        )));
+    }
+    let metadata = std::fs::metadata(path)
+        .map_err(|e| Error::InvalidArgs(format!("Cannot read file '{}': {}", path.display(), e)))?;
+    if metadata.is_dir() {
+        std::fs::read_dir(path).map_err(|e| {
+            Error::InvalidArgs(format!("Cannot read directory '{}': {}", path.display(), e))
+        })?;
+        return Ok(SharedPath::Directory);
+    }
+    if !metadata.is_file() {
+        return Err(Error::InvalidArgs(format!(
+            "'{}' is neither a file nor a directory",
+            path.display()
+        )));
+    }
+    Ok(SharedPath::File(metadata.len()))
+}
+
+/// Whether `path` resolves to a location inside `dir`.
+///
+/// Both are canonicalized first, so `..` components and symbolic links cannot
+/// escape `dir`. A path that does not exist is never inside it.
+pub(crate) fn is_within_dir(path: &Path, dir: &Path) -> bool {
+    match (path.canonicalize(), dir.canonicalize()) {
+        (Ok(path), Ok(dir)) => path.starts_with(dir),
+        _ => false,
+    }
+}
+
+/// Characters that Windows does not allow in file names.
+const INVALID_FILE_NAME_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*'];
+
+/// Reduces a caller-provided file name to a single, safe path component.
+///
+/// Any directory structure is discarded, whether it uses `/` or `\`, and names
+/// reserved by the operating system are rejected. Characters Windows cannot hold
+/// in a file name, and control characters, are replaced with `_`, and trailing
+/// dots and spaces are dropped. The result is the same on every platform.
+pub(crate) fn sanitize_file_name(name: &str) -> Result<String, Error> {
+    let last_component = name
+        .split(['/', '\\'])
+        .rfind(|component| !component.is_empty() && *component != ".")
+        .filter(|component| *component != "..")
+        .ok_or_else(|| Error::InvalidArgs("Invalid file name provided".to_string()))?;
+
+    let file_name: String = last_component
+        .chars()
+        .map(|c| {
+            if c.is_control() || INVALID_FILE_NAME_CHARS.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let file_name = file_name.trim_end_matches(['.', ' ']);
+    if file_name.is_empty() {
+        return Err(Error::InvalidArgs(format!(
+            "File name '{}' has no usable characters",
+            name
+        )));
+    }
+
+    let stem = file_name.split('.').next().unwrap_or_default();
+    if RESERVED_FILE_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem.trim_end()))
+    {
+        return Err(Error::InvalidArgs(format!(
+            "File name '{}' is reserved by the operating system",
+            file_name
+        )));
+    }
+
+    Ok(file_name.to_string())
+}
+
+/// Infers a MIME type from the extension of a file name.
+pub(crate) fn infer_mime_type(name: &str) -> Option<&'static str> {
+    let extension = Path::new(name).extension()?.to_str()?;
+    EXTENSION_MIME_TYPES
+        .iter()
+        .find(|(ext, _)| ext.eq_ignore_ascii_case(extension))
+        .map(|(_, mime)| *mime)
+}
+
+/// Looks up the MIME type the app maps the extension of a file name to, through
+/// the `extensionMimeOverrides` config.
+///
+/// Extensions are matched case-insensitively: the keys of `overrides` are
+/// normalized once the config is loaded, see [`crate::Config::normalized`].
+fn override_mime_type<'a>(name: &str, overrides: &'a HashMap<String, String>) -> Option<&'a str> {
+    let extension = Path::new(name).extension()?.to_str()?;
+    overrides
+        .get(&crate::config::normalize_extension(extension))
+        .map(String::as_str)
+}
+
+/// Returns the usual extension of a MIME type, the reverse of [`infer_mime_type`].
+#[cfg(any(target_os = "windows", test))]
+pub(crate) fn extension_for_mime_type(mime_type: &str) -> Option<&'static str> {
+    EXTENSION_MIME_TYPES
+        .iter()
+        .find(|(_, mime)| mime.eq_ignore_ascii_case(mime_type))
+        .map(|(extension, _)| *extension)
+}
+
+/// Normalizes a MIME type to its lowercase `type/subtype` form.
+///
+/// Parameters such as `; charset=utf-8` are dropped, since native share targets
+/// only match on the essence of the type. Values that are not a well-formed
+/// `type/subtype` pair are rejected.
+pub(crate) fn normalize_mime_type(mime_type: &str) -> Result<String, Error> {
+    let essence = mime_type.split(';').next().unwrap_or_default().trim();
+    let invalid = || Error::InvalidArgs(format!("Invalid MIME type '{}'", mime_type));
+
+    let (kind, subtype) = essence.split_once('/').ok_or_else(invalid)?;
+    if !is_mime_token(kind) || !is_mime_token(subtype) {
+        return Err(invalid());
+    }
+
+    Ok(essence.to_ascii_lowercase())
+}
+
+/// Normalizes an entry of the `blockedMimeTypes` config: a MIME type, or `type/*`
+/// for every subtype of a type.
+pub(crate) fn normalize_mime_pattern(pattern: &str) -> Result<String, Error> {
+    match pattern.trim().strip_suffix("/*") {
+        Some(kind) if is_mime_token(kind) => Ok(format!("{}/*", kind.to_ascii_lowercase())),
+        Some(_) => Err(Error::InvalidArgs(format!(
+            "Invalid MIME type '{}'",
+            pattern
+        ))),
+        None => normalize_mime_type(pattern),
+    }
+}
+
+/// Rejects the share when the resolved MIME type of one of its files, or of its
+/// preview image, matches the app's `blocked` types, e.g. executables and scripts.
+///
+/// Types are resolved like [`normalize_share_options`] does, so a type inferred
+/// from the file name is blocked as well. Each entry is a MIME type, or `type/*`
+/// for every subtype of a type, normalized by [`normalize_mime_pattern`] when the
+/// config is loaded.
+pub(crate) fn check_blocked_mime_types(
+    options: &ShareOptions,
+    overrides: &HashMap<String, String>,
+    blocked: &[String],
+) -> Result<(), Error> {
+    options
+        .files
+        .iter()
+        .flatten()
+        .chain(&options.preview_image)
+        .try_for_each(|file| check_blocked_mime_type(file, overrides, blocked))
+}
+
+/// Rejects a single file, e.g. a file to print, like [`check_blocked_mime_types`].
+pub(crate) fn check_blocked_mime_type(
+    file: &SharedFile,
+    overrides: &HashMap<String, String>,
+    blocked: &[String],
+) -> Result<(), Error> {
+    if blocked.is_empty() {
+        return Ok(());
+    }
+    let mime_type = resolve_mime_type(file, overrides)?;
+    let kind = mime_type.split('/').next().unwrap_or_default();
+    let is_blocked = blocked
+        .iter()
+        .any(|pattern| *pattern == mime_type || pattern.strip_suffix("/*") == Some(kind));
+    if is_blocked {
+        return Err(Error::InvalidArgs(format!(
+            "Cannot share '{}': files of type {} are blocked",
+            file.name, mime_type
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that a MIME type or subtype only uses the characters allowed by RFC 6838.
+fn is_mime_token(token: &str) -> bool {
+    !token.is_empty()
+        && token.len() <= 127
+        && token
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+}
+
+/// Resolves the MIME type that will be attached to a shared file.
+///
+/// The caller's value is normalized and used unless it is empty or the generic
+/// octet-stream type, in which case the type is inferred from the file name: from
+/// the app's `overrides` first, then from the built-in table.
+pub(crate) fn resolve_mime_type(
+    file: &SharedFile,
+    overrides: &HashMap<String, String>,
+) -> Result<String, Error> {
+    let provided = file.mime_type.trim();
+    if !provided.is_empty() {
+        let normalized = normalize_mime_type(provided)?;
+        if normalized != DEFAULT_MIME_TYPE {
+            return Ok(normalized);
+        }
+    }
+    if let Some(mime_type) = override_mime_type(&file.name, overrides) {
+        return normalize_mime_type(mime_type);
+    }
+    Ok(infer_mime_type(&file.name)
+        .unwrap_or(DEFAULT_MIME_TYPE)
+        .to_string())
+}
+
+/// Resolves the MIME type of a file sent to the print service.
+///
+/// Only PDF documents and images can be printed, other types fail with
+/// `InvalidArgs`.
+#[cfg(not(target_os = "android"))]
+pub(crate) fn print_mime_type(
+    file: &SharedFile,
+    overrides: &HashMap<String, String>,
+) -> Result<String, Error> {
+    let mime_type = resolve_mime_type(file, overrides)?;
+    if mime_type != "application/pdf" && !mime_type.starts_with("image/") {
+        return Err(Error::InvalidArgs(format!(
+            "Cannot print '{}': only PDF documents and images can be printed, not {}",
+            file.name, mime_type
+        )));
+    }
+    Ok(mime_type)
+}
+
+/// Fills the options a share leaves unset with the app's `defaults`.
+///
+/// Each option set by the share takes precedence over its default, as a whole: a
+/// share with `text_items` replaces the default list rather than extending it.
+/// Files and URL providers are specific to a share, so they are never defaulted.
+pub(crate) fn apply_default_options(
+    options: ShareOptions,
+    defaults: Option<&ShareOptions>,
+) -> ShareOptions {
+    let Some(defaults) = defaults.cloned() else {
+        return options;
+    };
+    ShareOptions {
+        text: options.text.or(defaults.text),
+        title: options.title.or(defaults.title),
+        url: options.url.or(defaults.url),
+        files: options.files,
+        application_name: options.application_name.or(defaults.application_name),
+        application_link: options.application_link.or(defaults.application_link),
+        wait_for_dismissal: options.wait_for_dismissal.or(defaults.wait_for_dismissal),
+        url_provider_id: options.url_provider_id,
+        zeroize_data: options.zeroize_data.or(defaults.zeroize_data),
+        text_is_plain: options.text_is_plain.or(defaults.text_is_plain),
+        fallback: options.fallback.or(defaults.fallback),
+        preferred_edge: options.preferred_edge.or(defaults.preferred_edge),
+        presentation: options.presentation.or(defaults.presentation),
+        name_collision: options.name_collision.or(defaults.name_collision),
+        text_url_separator: options.text_url_separator.or(defaults.text_url_separator),
+        url_first: options.url_first.or(defaults.url_first),
+        text_items: options.text_items.or(defaults.text_items),
+        text_items_separator: options
+            .text_items_separator
+            .or(defaults.text_items_separator),
+        preview_image: options.preview_image.or(defaults.preview_image),
+        share_mode: options.share_mode.or(defaults.share_mode),
+        return_url: options.return_url.or(defaults.return_url),
+        expires_at: options.expires_at.or(defaults.expires_at),
+        screen: options.screen.or(defaults.screen),
+        dedupe_files: options.dedupe_files.or(defaults.dedupe_files),
+        custom_formats: options.custom_formats.or(defaults.custom_formats),
+        cc: options.cc.or(defaults.cc),
+        reply_to: options.reply_to.or(defaults.reply_to),
+    }
+}
+
+/// Leaves out the files whose content is identical to an earlier file of the
+/// share when `dedupe_files` is set, returning how many were left out.
+///
+/// Files are compared by the BLAKE3 hash of their decoded data, or of the content
+/// of the file at their path. Streamed files and folders are always kept, as their
+/// content cannot be read ahead of the share.
+///
+/// Also returns the handed-off files that were left out, except those whose
+/// path is still shared by a kept file. The caller releases them, since the share
+/// will not.
+pub(crate) fn dedupe_files(options: &mut ShareOptions) -> Result<(u32, Vec<SharedFile>), Error> {
+    if options.dedupe_files != Some(true) {
+        return Ok((0, Vec::new()));
+    }
+    let Some(files) = options.files.as_mut() else {
+        return Ok((0, Vec::new()));
+    };
+    let mut seen = HashSet::new();
+    let mut removed = Vec::new();
+    for (index, file) in std::mem::take(files).into_iter().enumerate() {
+        match content_hash(&file, index)? {
+            Some(hash) if !seen.insert(hash) => removed.push(file),
+            _ => files.push(file),
+        }
+    }
+    let count = removed.len() as u32;
+    let handoffs = removed
+        .into_iter()
+        .filter(|file| file.handoff == Some(true))
+        .filter(|file| {
+            let path = file.path.as_deref();
+            path.is_some() && files.iter().all(|kept| kept.path.as_deref() != path)
+        })
+        .collect();
+    Ok((count, handoffs))
+}
+
+/// Hashes the content of `file`, or `None` for a streamed file or a folder.
+fn content_hash(file: &SharedFile, index: usize) -> Result<Option<blake3::Hash>, Error> {
+    if file.reader.is_some() {
+        return Ok(None);
+    }
+    let Some(path) = file.path.as_deref() else {
+        let bytes = Zeroizing::new(decode_file_data(file, Some(index))?);
+        return Ok(Some(blake3::hash(&bytes)));
+    };
+    if check_shared_path(path)? == SharedPath::Directory {
+        return Ok(None);
+    }
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(std::fs::File::open(path)?)?;
+    Ok(Some(hasher.finalize()))
+}
+
+/// Replaces the MIME type of every file with its resolved, normalized value, and
+/// the placeholders of the title with their values.
+///
+/// This is applied before the options reach a platform implementation so the
+/// native share APIs always receive a well-formed type. The preview image must
+/// resolve to an image type and carry its content, since it is never read by path.
+/// The mail addresses must not be empty, but are otherwise left to the mail app.
+pub(crate) fn normalize_share_options(
+    mut options: ShareOptions,
+    overrides: &HashMap<String, String>,
+) -> Result<ShareOptions, Error> {
+    let addresses = options.cc.iter().flatten().chain(&options.reply_to);
+    if addresses
+        .into_iter()
+        .any(|address| address.trim().is_empty())
+    {
+        return Err(Error::InvalidArgs(
+            "Mail addresses cannot be empty".to_string(),
+        ));
+    }
+    options.title = resolve_title(&options);
+    if let Some(files) = options.files.as_mut() {
+        for file in files.iter_mut() {
+            file.mime_type = resolve_mime_type(file, overrides)?;
+        }
+    }
+    if let Some(preview) = options.preview_image.as_mut() {
+        if preview.path.is_some() {
+            return Err(Error::InvalidArgs(
+                "A preview image cannot be shared by path".to_string(),
+            ));
+        }
+        preview.mime_type = resolve_mime_type(preview, overrides)?;
+        if !preview.mime_type.starts_with("image/") {
+            return Err(Error::InvalidArgs(format!(
+                "The preview image '{}' is not an image: {}",
+                preview.name, preview.mime_type
+            )));
+        }
+    }
+    Ok(options)
+}
+
+/// Estimates the bytes the temporary files of a share take on disk, without
+/// decoding their data.
+///
+/// Base64 `data` decodes to three bytes for every four characters, less its
+/// padding. Files shared by path are used in place and take no space, and the
+/// size of streamed files is unknown, so neither is counted.
+pub(crate) fn estimate_disk_usage(options: &ShareOptions) -> u64 {
+    options
+        .files
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .filter(|file| file.path.is_none() && file.reader.is_none())
+        .map(|file| file.data.trim_end_matches('=').len() as u64 * 3 / 4)
+        .sum()
+}
+
+/// Fills in the placeholders of the title of a share.
+///
+/// `{fileCount}` is replaced with the number of shared files, and `{fileName}`
+/// with the name of the first one, or nothing when there is none. Other text is
+/// kept as is.
+pub(crate) fn resolve_title(options: &ShareOptions) -> Option<String> {
+    let title = options.title.as_deref()?;
+    let files = options.files.as_deref().unwrap_or_default();
+    let first_name = files.first().map_or("", |file| file.name.as_str());
+    Some(
+        title
+            .replace("{fileCount}", &files.len().to_string())
+            .replace("{fileName}", first_name),
+    )
+}
+
+/// Derives a title for a share without one, for platforms that show a title.
+///
+/// A single file is titled with its name and several files with their count.
+/// Otherwise the host of the shared URL is used, if any.
+#[cfg(any(target_os = "windows", test))]
+pub(crate) fn default_title(options: &ShareOptions) -> Option<String> {
+    match options.files.as_deref().unwrap_or_default() {
+        [file] => return Some(file.name.clone()),
+        [] => {}
+        files => return Some(format!("{} files", files.len())),
+    }
+    let url = Url::parse(options.url.as_deref()?).ok()?;
+    url.host_str().map(str::to_string)
+}
+
+/// Joins the optional text and URL into the single text payload used by the desktop platforms.
+///
+/// Shared by every platform, so that a share reads the same wherever it is shown.
+/// The text comes first and the parts are separated by a newline, unless
+/// `url_first` or `text_url_separator` say otherwise.
+pub(crate) fn merge_text_url(options: &ShareOptions) -> String {
+    match (options.text.as_deref(), options.url.as_deref()) {
+        (Some(t), Some(u)) => {
+            let separator = options.text_url_separator.as_deref().unwrap_or("\n");
+            if options.url_first == Some(true) {
+                format!("{}{}{}", u, separator, t)
+            } else {
+                format!("{}{}{}", t, separator, u)
+            }
+        }
+        (Some(t), None) => t.to_string(),
+        (None, Some(u)) => u.to_string(),
+        (None, None) => String::new(),
+    }
+}
+
+/// Appends the `text_items` of a share to its `text`, for platforms with a single
+/// text slot.
+///
+/// The parts are joined by `text_items_separator`, a blank line by default, and
+/// empty ones are skipped.
+#[cfg(any(target_os = "windows", test))]
+pub(crate) fn fold_text_items(options: &mut ShareOptions) {
+    let Some(items) = options.text_items.take() else {
+        return;
+    };
+    let separator = options.text_items_separator.as_deref().unwrap_or("\n\n");
+    let parts: Vec<String> = options
+        .text
+        .take()
+        .into_iter()
+        .chain(items)
+        .filter(|part| !part.is_empty())
+        .collect();
+    options.text = (!parts.is_empty()).then(|| parts.join(separator));
+}
+
+/// Decodes a percent-encoded header value, as produced by `encodeURIComponent`.
+///
+/// Header values can only carry visible ASCII, so the metadata of raw data shares
+/// is percent-encoded by the frontend.
+pub(crate) fn percent_decode(value: &str) -> Result<String, Error> {
+    let invalid = || Error::InvalidArgs(format!("Invalid percent-encoded value '{}'", value));
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail.get(..2).ok_or_else(invalid)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return Err(invalid());
+            }
+            let hex = std::str::from_utf8(hex).map_err(|_| invalid())?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+/// Returns the URL of a share without text, which the desktop platforms share as a
+/// link rather than as plain text.
+///
+/// A malformed URL is rejected, since there is no text it could be merged into.
+/// Shares marked `text_is_plain` never have a link.
+#[cfg(any(target_os = "windows", target_os = "macos", test))]
+pub(crate) fn link_url(options: &ShareOptions) -> Result<Option<Url>, Error> {
+    if options.text_is_plain == Some(true) {
+        return Ok(None);
+    }
+    let (None | Some(""), Some(url)) = (options.text.as_deref(), options.url.as_deref()) else {
+        return Ok(None);
+    };
+    Url::parse(url)
+        .map(Some)
+        .map_err(|e| Error::InvalidArgs(format!("Invalid URL '{}': {}", url, e)))
+}
+
+fn validate_file(
+    file: &SharedFile,
+    overrides: &HashMap<String, String>,
+    blocked: &[String],
+) -> ValidatedFile {
+    let result = sanitize_file_name(&file.name).and_then(|name| {
+        let mime_type = resolve_mime_type(file, overrides)?;
+        check_blocked_mime_type(file, overrides, blocked)?;
+        let byte_length = match (&file.path, &file.reader) {
+            (Some(path), _) => match check_shared_path(path)? {
+                SharedPath::File(len) => Some(len),
+                SharedPath::Directory => None,
+            },
+            // The size of a streamed file is unknown until it has been read.
+            (None, Some(_)) => None,
+            (None, None) => Some(decode_file_data(file, None)?.len() as u64),
+        };
+        Ok((name, mime_type, byte_length))
+    });
+
+    match result {
+        Ok((resolved_name, resolved_mime, byte_length)) => ValidatedFile {
+            name: file.name.clone(),
+            resolved_name: Some(resolved_name),
+            resolved_mime: Some(resolved_mime),
+            byte_length,
+            error: None,
+        },
+        Err(err) => ValidatedFile {
+            name: file.name.clone(),
+            resolved_name: None,
+            resolved_mime: None,
+            byte_length: None,
+            error: Some(err.to_string()),
+        },
+    }
+}
+
+/// Describes what a share would contain without writing any file or showing any UI.
+///
+/// The `defaultOptions` of `config` are applied first, as for a share, and a file
+/// whose type `config` blocks reports the error the share would fail with.
+pub(crate) fn validate_share(options: &ShareOptions, config: &Config) -> ValidatedShare {
+    let options = &apply_default_options(options.clone(), config.default_options.as_ref());
+    let overrides = &config.extension_mime_overrides;
+    let blocked = &config.blocked_mime_types;
+    let text = merge_text_url(options);
+    let files = options
+        .files
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .map(|file| validate_file(file, overrides, blocked))
+        .collect();
+
+    ValidatedShare {
+        title: resolve_title(options),
+        text: (!text.is_empty()).then_some(text),
+        files,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_with_mime(name: &str, mime_type: &str) -> SharedFile {
+        SharedFile {
+            data: String::new(),
+            name: name.to_string(),
+            mime_type: mime_type.to_string(),
+            path: None,
+            handoff: None,
+            uti: None,
+            width: None,
+            height: None,
+            display_name: None,
+            reader: None,
+        }
+    }
+
+    fn file_with_data(data: &str) -> SharedFile {
+        SharedFile {
+            data: data.to_string(),
+            ..file_with_mime("data.bin", "")
+        }
+    }
+
+    #[test]
+    fn decode_file_data_accepts_standard_base64() {
+        assert_eq!(
+            decode_file_data(&file_with_data("+/8="), None).unwrap(),
+            [0xfb, 0xff]
+        );
+    }
+
+    #[test]
+    fn decode_file_data_accepts_url_safe_base64() {
+        assert_eq!(
+            decode_file_data(&file_with_data("-_8="), None).unwrap(),
+            [0xfb, 0xff]
+        );
+        assert_eq!(
+            decode_file_data(&file_with_data("-_8"), None).unwrap(),
+            [0xfb, 0xff]
+        );
+    }
+
+    #[test]
+    fn decode_file_data_accepts_missing_padding() {
+        assert_eq!(
+            decode_file_data(&file_with_data("aGk"), None).unwrap(),
+            b"hi"
+        );
+        assert!(matches!(
+            decode_file_data(&file_with_data("a!b"), None),
+            Err(Error::InvalidArgs(_))
+        ));
+    }
+
+    #[test]
+    fn decode_file_data_names_the_failing_file() {
+        let file = SharedFile {
+            name: "doc.pdf".to_string(),
+            ..file_with_data("a!b")
+        };
+        let message = |index| match decode_file_data(&file, index) {
+            Err(Error::InvalidArgs(message)) => message,
+            other => panic!("unexpected result: {:?}", other),
+        };
+        assert_eq!(
+            message(Some(1)),
+            "Invalid Base64 in file 'doc.pdf' (index 1)"
+        );
+        assert_eq!(message(None), "Invalid Base64 in file 'doc.pdf'");
+    }
+
+    #[test]
+    fn normalize_mime_type_lowercases_mixed_case() {
+        assert_eq!(normalize_mime_type("Image/PNG").unwrap(), "image/png");
+        assert_eq!(
+            normalize_mime_type(" Application/VND.MS-Excel ").unwrap(),
+            "application/vnd.ms-excel"
+        );
+    }
+
+    #[test]
+    fn normalize_mime_type_strips_parameters() {
+        assert_eq!(
+            normalize_mime_type("text/plain; charset=utf-8").unwrap(),
+            "text/plain"
+        );
+        assert_eq!(
+            normalize_mime_type("Text/HTML;charset=UTF-8;format=flowed").unwrap(),
+            "text/html"
+        );
+    }
+
+    #[test]
+    fn normalize_mime_type_rejects_garbage() {
+        for garbage in [
+            "",
+            "png",
+            "image/",
+            "/png",
+            "image/png/extra",
+            "image /png",
+            "ima ge/png",
+            "image/*",
+            "; charset=utf-8",
+        ] {
+            assert!(
+                matches!(normalize_mime_type(garbage), Err(Error::InvalidArgs(_))),
+                "expected '{}' to be rejected",
+                garbage
+            );
+        }
+    }
+
+    #[test]
+    fn resolve_mime_type_infers_from_name_when_generic() {
+        let file = file_with_mime("photo.JPG", "application/octet-stream");
+        assert_eq!(
+            resolve_mime_type(&file, &HashMap::new()).unwrap(),
+            "image/jpeg"
+        );
+        let file = file_with_mime("notes.txt", "");
+        assert_eq!(
+            resolve_mime_type(&file, &HashMap::new()).unwrap(),
+            "text/plain"
+        );
+        let file = file_with_mime("archive.unknown", "");
+        assert_eq!(
+            resolve_mime_type(&file, &HashMap::new()).unwrap(),
+            DEFAULT_MIME_TYPE
+        );
+    }
+
+    #[test]
+    fn resolve_mime_type_prefers_normalized_caller_value() {
+        let file = file_with_mime("photo.jpg", "Image/PNG; q=1");
+        assert_eq!(
+            resolve_mime_type(&file, &HashMap::new()).unwrap(),
+            "image/png"
+        );
+    }
+
+    #[test]
+    fn resolve_mime_type_consults_the_overrides_first() {
+        let overrides = HashMap::from([
+            ("myapp".to_string(), "application/x-myapp".to_string()),
+            ("txt".to_string(), "text/x-notes".to_string()),
+        ]);
+        let file = file_with_mime("board.MyApp", "");
+        assert_eq!(
+            resolve_mime_type(&file, &overrides).unwrap(),
+            "application/x-myapp"
+        );
+        let file = file_with_mime("notes.txt", "application/octet-stream");
+        assert_eq!(
+            resolve_mime_type(&file, &overrides).unwrap(),
+            "text/x-notes"
+        );
+        let file = file_with_mime("photo.jpg", "");
+        assert_eq!(resolve_mime_type(&file, &overrides).unwrap(), "image/jpeg");
+        let file = file_with_mime("notes.txt", "text/markdown");
+        assert_eq!(
+            resolve_mime_type(&file, &overrides).unwrap(),
+            "text/markdown"
+        );
+    }
+
+    #[test]
+    fn print_mime_type_accepts_documents_and_images() {
+        let file = file_with_mime("invoice.pdf", "");
+        assert_eq!(
+            print_mime_type(&file, &HashMap::new()).unwrap(),
+            "application/pdf"
+        );
+        let file = file_with_mime("photo.jpg", "application/octet-stream");
+        assert_eq!(
+            print_mime_type(&file, &HashMap::new()).unwrap(),
+            "image/jpeg"
+        );
+        let file = file_with_mime("notes.txt", "");
+        assert!(matches!(
+            print_mime_type(&file, &HashMap::new()),
+            Err(Error::InvalidArgs(_))
+        ));
+    }
+
+    #[test]
+    fn share_options_take_precedence_over_the_defaults() {
+        let defaults = ShareOptions {
+            title: Some("My App".to_string()),
+            application_name: Some("My App".to_string()),
+            text_items: Some(vec!["#myapp".to_string()]),
+            url_provider_id: Some("upload".to_string()),
+            files: Some(vec![file_with_data("aGk=")]),
+            ..Default::default()
+        };
+        let options = ShareOptions {
+            title: Some("Report".to_string()),
+            text_items: Some(Vec::new()),
+            ..Default::default()
+        };
+
+        let options = apply_default_options(options, Some(&defaults));
+        assert_eq!(options.title.as_deref(), Some("Report"));
+        assert_eq!(options.application_name.as_deref(), Some("My App"));
+        assert_eq!(options.text_items, Some(Vec::new()));
+        assert!(options.url_provider_id.is_none());
+        assert!(options.files.is_none());
+
+        let options = apply_default_options(ShareOptions::default(), None);
+        assert!(options.title.is_none());
+    }
+
+    #[test]
+    fn custom_formats_are_decoded_and_checked() {
+        let with_formats = |formats: &[(&str, &str)]| ShareOptions {
+            custom_formats: Some(
+                formats
+                    .iter()
+                    .map(|(id, data)| (id.to_string(), data.to_string()))
+                    .collect(),
+            ),
+            ..Default::default()
+        };
+
+        let decoded =
+            decode_custom_formats(&with_formats(&[("com.example.board", "aGk=")])).unwrap();
+        assert_eq!(decoded, [("com.example.board".to_string(), b"hi".to_vec())]);
+        assert!(decode_custom_formats(&ShareOptions::default())
+            .unwrap()
+            .is_empty());
+
+        let too_large = general_purpose::STANDARD.encode(vec![0; MAX_CUSTOM_FORMAT_BYTES + 1]);
+        for formats in [
+            [(" ", "aGk=")],
+            [("text", "aGk=")],
+            [("com.example.board", "a!b")],
+            [("com.example.board", too_large.as_str())],
+        ] {
+            assert!(matches!(
+                decode_custom_formats(&with_formats(&formats)),
+                Err(Error::InvalidArgs(_))
+            ));
+        }
+
+        // Too long to hold an allowed payload, so rejected before being decoded.
+        let too_long = "!".repeat(MAX_CUSTOM_FORMAT_BYTES.div_ceil(3) * 4 + 1);
+        match decode_custom_formats(&with_formats(&[("com.example.board", &too_long)])) {
+            Err(Error::InvalidArgs(message)) => assert!(message.contains("bytes allowed")),
+            other => panic!("expected an oversized payload error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn dedupe_files_keeps_the_first_of_identical_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("copy.txt");
+        std::fs::write(&path, b"hi").unwrap();
+        let named = |name: &str, data: &str| SharedFile {
+            name: name.to_string(),
+            ..file_with_data(data)
+        };
+        let mut options = ShareOptions {
+            files: Some(vec![
+                named("first.txt", "aGk="),
+                named("other.txt", "aGV5"),
+                named("second.txt", "aGk"),
+                SharedFile::from_path(&path, ""),
+                SharedFile::from_reader("streamed.txt", "", &b"hi"[..]),
+            ]),
+            ..Default::default()
+        };
+
+        assert_eq!(dedupe_files(&mut options).unwrap().0, 0);
+        assert_eq!(options.files.as_ref().unwrap().len(), 5);
+
+        options.dedupe_files = Some(true);
+        assert_eq!(dedupe_files(&mut options).unwrap().0, 2);
+        let names: Vec<_> = options
+            .files
+            .unwrap()
+            .into_iter()
+            .map(|file| file.name)
+            .collect();
+        assert_eq!(names, ["first.txt", "other.txt", "streamed.txt"]);
+    }
+
+    #[test]
+    fn dedupe_files_returns_the_handed_off_duplicates_left_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let handed_off = |name: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, b"hi").unwrap();
+            SharedFile {
+                handoff: Some(true),
+                ..SharedFile::from_path(&path, "")
+            }
+        };
+        let kept = handed_off("kept.txt");
+        let mut options = ShareOptions {
+            files: Some(vec![kept.clone(), kept.clone(), handed_off("copy.txt")]),
+            dedupe_files: Some(true),
+            ..Default::default()
+        };
+
+        let (count, handoffs) = dedupe_files(&mut options).unwrap();
+
+        assert_eq!(count, 2);
+        let paths: Vec<_> = handoffs.iter().map(|file| file.path.as_deref()).collect();
+        assert_eq!(paths, [dir.path().join("copy.txt").to_str()]);
+        // Releasing them is up to the caller.
+        assert!(dir.path().join("copy.txt").exists());
+    }
+
+    #[test]
+    fn normalize_share_options_checks_the_preview_image() {
+        let with_preview = |preview: SharedFile| ShareOptions {
+            url: Some("https://example.com".to_string()),
+            preview_image: Some(preview),
+            ..Default::default()
+        };
+
+        let options = normalize_share_options(
+            with_preview(file_with_mime("card.png", "")),
+            &HashMap::new(),
+        )
+        .unwrap();
+        assert_eq!(options.preview_image.unwrap().mime_type, "image/png");
+
+        let text = file_with_mime("card.txt", "");
+        assert!(normalize_share_options(with_preview(text), &HashMap::new()).is_err());
+
+        let mut by_path = file_with_mime("card.png", "");
+        by_path.path = Some("/tmp/card.png".to_string());
+        assert!(normalize_share_options(with_preview(by_path), &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn blocked_mime_types_reject_matching_files() {
+        let blocked = ["application/x-msdownload".to_string(), "text/*".to_string()];
+        let overrides = HashMap::from([("ps1".to_string(), "text/x-powershell".to_string())]);
+        let share = |file: SharedFile| ShareOptions {
+            files: Some(vec![file_with_mime("report.pdf", ""), file]),
+            ..Default::default()
+        };
+        let check = |file| check_blocked_mime_types(&share(file), &overrides, &blocked);
+
+        assert!(check(file_with_mime("photo.png", "")).is_ok());
+        assert!(check(file_with_mime("setup.exe", "application/x-msdownload")).is_err());
+        assert!(check(file_with_mime("notes", "text/plain; charset=utf-8")).is_err());
+        // Types inferred from the name, including the app's overrides, are blocked too.
+        assert!(check(file_with_mime("notes.txt", "")).is_err());
+        assert!(check(file_with_mime("install.ps1", "")).is_err());
+        assert!(
+            check_blocked_mime_types(&share(file_with_mime("notes.txt", "")), &overrides, &[])
+                .is_ok()
+        );
+        assert!(matches!(
+            check(file_with_mime("setup.exe", "Application/X-MSDownload")),
+            Err(Error::InvalidArgs(message)) if message.contains("setup.exe")
+        ));
+    }
+
+    #[test]
+    fn blocked_mime_types_reject_a_matching_preview_image() {
+        let blocked = ["image/*".to_string()];
+        let options = ShareOptions {
+            preview_image: Some(file_with_mime("card.png", "")),
+            ..Default::default()
+        };
+        assert!(check_blocked_mime_types(&options, &HashMap::new(), &blocked).is_err());
+        assert!(
+            check_blocked_mime_type(&file_with_mime("invoice.pdf", ""), &HashMap::new(), &blocked)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn normalize_share_options_rejects_empty_addresses() {
+        let with_addresses = |cc: &[&str], reply_to: Option<&str>| ShareOptions {
+            cc: Some(cc.iter().map(|address| address.to_string()).collect()),
+            reply_to: reply_to.map(str::to_string),
+            ..Default::default()
+        };
+
+        let options = with_addresses(&["a@example.com", "not an address"], Some("b@example.com"));
+        assert!(normalize_share_options(options, &HashMap::new()).is_ok());
+        for options in [
+            with_addresses(&["a@example.com", ""], None),
+            with_addresses(&[], Some(" ")),
+        ] {
+            assert!(matches!(
+                normalize_share_options(options, &HashMap::new()),
+                Err(Error::InvalidArgs(_))
+            ));
+        }
+    }
+
+    fn text_url(text: Option<&str>, url: Option<&str>) -> ShareOptions {
+        ShareOptions {
+            text: text.map(str::to_string),
+            url: url.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn merge_text_url_joins_both_with_a_newline() {
+        assert_eq!(
+            merge_text_url(&text_url(Some("Look"), Some("https://example.com"))),
+            "Look\nhttps://example.com"
+        );
+    }
+
+    #[test]
+    fn merge_text_url_keeps_a_single_part() {
+        assert_eq!(merge_text_url(&text_url(Some("Look"), None)), "Look");
+        assert_eq!(
+            merge_text_url(&text_url(None, Some("https://example.com"))),
+            "https://example.com"
+        );
+    }
+
+    #[test]
+    fn merge_text_url_is_empty_without_either() {
+        assert_eq!(merge_text_url(&text_url(None, None)), "");
+    }
+
+    #[test]
+    fn merge_text_url_applies_the_separator_and_order() {
+        let options = ShareOptions {
+            text_url_separator: Some(" ".to_string()),
+            ..text_url(Some("Look"), Some("https://example.com"))
+        };
+        assert_eq!(merge_text_url(&options), "Look https://example.com");
+
+        let options = ShareOptions {
+            text_url_separator: Some(String::new()),
+            url_first: Some(true),
+            ..options
+        };
+        assert_eq!(merge_text_url(&options), "https://example.comLook");
+
+        let options = ShareOptions {
+            url_first: Some(true),
+            ..text_url(Some("Look"), None)
+        };
+        assert_eq!(merge_text_url(&options), "Look");
+    }
+
+    #[test]
+    fn fold_text_items_appends_them_to_the_text() {
+        let mut options = ShareOptions {
+            text: Some("Groceries".to_string()),
+            text_items: Some(vec!["Milk".to_string(), String::new(), "Eggs".to_string()]),
+            ..Default::default()
+        };
+        fold_text_items(&mut options);
+        assert_eq!(options.text.as_deref(), Some("Groceries\n\nMilk\n\nEggs"));
+        assert_eq!(options.text_items, None);
+
+        let mut options = ShareOptions {
+            text_items: Some(vec!["Milk".to_string(), "Eggs".to_string()]),
+            text_items_separator: Some(", ".to_string()),
+            ..Default::default()
+        };
+        fold_text_items(&mut options);
+        assert_eq!(options.text.as_deref(), Some("Milk, Eggs"));
+    }
+
+    #[test]
+    fn link_url_only_applies_without_text() {
+        let options = |text: Option<&str>, url: Option<&str>| ShareOptions {
+            text: text.map(str::to_string),
+            url: url.map(str::to_string),
+            ..Default::default()
+        };
+
+        let url = link_url(&options(None, Some("https://example.com/a?b=c"))).unwrap();
+        assert_eq!(url.unwrap().as_str(), "https://example.com/a?b=c");
+        assert!(link_url(&options(Some(""), Some("https://example.com")))
+            .unwrap()
+            .is_some());
+        assert!(link_url(&options(Some("text"), Some("not a url")))
+            .unwrap()
+            .is_none());
+        assert!(link_url(&options(Some("text"), None)).unwrap().is_none());
+        assert!(matches!(
+            link_url(&options(None, Some("not a url"))),
+            Err(Error::InvalidArgs(_))
+        ));
+
+        let plain = ShareOptions {
+            text_is_plain: Some(true),
+            ..options(None, Some("not a url"))
+        };
+        assert!(link_url(&plain).unwrap().is_none());
+    }
+
+    #[test]
+    fn extension_for_mime_type_reverses_infer_mime_type() {
+        assert_eq!(extension_for_mime_type("application/pdf"), Some("pdf"));
+        assert_eq!(extension_for_mime_type("image/jpeg"), Some("jpg"));
+        assert_eq!(extension_for_mime_type("text/HTML"), Some("html"));
+        assert_eq!(extension_for_mime_type(DEFAULT_MIME_TYPE), None);
+    }
+
+    #[test]
+    fn estimate_disk_usage_matches_the_decoded_sizes() {
+        let with_data = |data: &str| SharedFile {
+            data: data.to_string(),
+            ..file_with_mime("a.bin", "")
+        };
+        let options = ShareOptions {
+            files: Some(vec![
+                with_data("aGk="),
+                with_data("aGVsbG8"),
+                with_data(&general_purpose::STANDARD.encode([0u8; 1000])),
+                SharedFile::from_path("/tmp/large.iso", ""),
+                SharedFile::from_reader("stream.bin", "", &b"unknown"[..]),
+            ]),
+            ..Default::default()
+        };
+        assert_eq!(estimate_disk_usage(&options), 2 + 5 + 1000);
+        assert_eq!(estimate_disk_usage(&ShareOptions::default()), 0);
+    }
+
+    #[test]
+    fn title_placeholders_are_filled_in() {
+        let files = vec![file_with_mime("a.txt", ""), file_with_mime("b.txt", "")];
+        let options = ShareOptions {
+            title: Some("{fileName} and {fileCount} {other}".to_string()),
+            files: Some(files.clone()),
+            ..Default::default()
+        };
+        assert_eq!(resolve_title(&options).unwrap(), "a.txt and 2 {other}");
+        assert_eq!(resolve_title(&ShareOptions::default()), None);
+
+        assert_eq!(default_title(&options).unwrap(), "2 files");
+        let single = ShareOptions {
+            files: Some(files[..1].to_vec()),
+            ..Default::default()
+        };
+        assert_eq!(default_title(&single).unwrap(), "a.txt");
+        let link = ShareOptions {
+            url: Some("https://example.com/post/1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(default_title(&link).unwrap(), "example.com");
+        assert_eq!(default_title(&ShareOptions::default()), None);
+    }
+
+    #[test]
+    fn percent_decode_reverses_encode_uri_component() {
+        assert_eq!(percent_decode("report.pdf").unwrap(), "report.pdf");
+        assert_eq!(
+            percent_decode("r%C3%A9sum%C3%A9%20(1).pdf").unwrap(),
+            "résumé (1).pdf"
+        );
+        for invalid in ["100%", "%zz", "%+1", "%C3"] {
+            assert!(
+                matches!(percent_decode(invalid), Err(Error::InvalidArgs(_))),
+                "expected '{}' to be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn check_shared_path_accepts_files_and_directories() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), b"hello").unwrap();
+        let path = file.path().to_str().unwrap();
+        assert_eq!(check_shared_path(path).unwrap(), SharedPath::File(5));
+
+        let dir = file.path().parent().unwrap().to_str().unwrap();
+        assert_eq!(check_shared_path(dir).unwrap(), SharedPath::Directory);
+
+        for invalid in ["relative/file.txt", &format!("{}.missing", path)] {
+            assert!(
+                matches!(check_shared_path(invalid), Err(Error::InvalidArgs(_))),
+                "expected '{}' to be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn is_within_dir_resolves_the_path_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let inner = dir.path().join("inner");
+        std::fs::create_dir(&inner).unwrap();
+        let file = inner.join("large.bin");
+        std::fs::write(&file, b"data").unwrap();
+        std::fs::write(dir.path().join("outside.bin"), b"data").unwrap();
+
+        assert!(is_within_dir(&file, &inner));
+        assert!(is_within_dir(&inner.join("../inner/large.bin"), &inner));
+        assert!(!is_within_dir(&inner.join("../outside.bin"), &inner));
+        assert!(!is_within_dir(&inner.join("missing.bin"), &inner));
+        assert!(!is_within_dir(&file, &dir.path().join("missing")));
+    }
+
+    #[test]
+    fn sanitize_file_name_keeps_only_the_last_component() {
+        assert_eq!(sanitize_file_name("report.pdf").unwrap(), "report.pdf");
+        assert_eq!(sanitize_file_name("../../etc/passwd").unwrap(), "passwd");
+        assert_eq!(sanitize_file_name("/etc/passwd").unwrap(), "passwd");
+        assert_eq!(sanitize_file_name("photos/../cat.png").unwrap(), "cat.png");
+        assert_eq!(sanitize_file_name("..\\..\\boot.ini").unwrap(), "boot.ini");
+        assert_eq!(
+            sanitize_file_name("C:\\Users\\me\\cat.png").unwrap(),
+            "cat.png"
+        );
+        assert_eq!(sanitize_file_name("photos/./").unwrap(), "photos");
+    }
+
+    #[test]
+    fn sanitize_file_name_replaces_characters_windows_rejects() {
+        assert_eq!(
+            sanitize_file_name("a<b>c:d\"e|f?g*h").unwrap(),
+            "a_b_c_d_e_f_g_h"
+        );
+        assert_eq!(
+            sanitize_file_name("tab\there\u{7f}.txt").unwrap(),
+            "tab_here_.txt"
+        );
+        assert_eq!(sanitize_file_name("C:cat.png").unwrap(), "C_cat.png");
+    }
+
+    #[test]
+    fn sanitize_file_name_drops_trailing_dots_and_spaces() {
+        assert_eq!(sanitize_file_name("report.pdf. . ").unwrap(), "report.pdf");
+        assert_eq!(sanitize_file_name("notes ").unwrap(), "notes");
+        for invalid in ["...", " . ", "photos/. ."] {
+            assert!(
+                matches!(sanitize_file_name(invalid), Err(Error::InvalidArgs(_))),
+                "expected '{}' to be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn sanitize_file_name_rejects_names_without_a_file_name() {
+        for invalid in ["", ".", "..", "/", "\\", "photos/..", "photos\\.."] {
+            assert!(
+                matches!(sanitize_file_name(invalid), Err(Error::InvalidArgs(_))),
+                "expected '{}' to be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn sanitize_file_name_rejects_reserved_names() {
+        for reserved in ["CON", "nul.txt", "Com1.tar.gz", "LPT9 .log"] {
+            assert!(
+                matches!(sanitize_file_name(reserved), Err(Error::InvalidArgs(_))),
+                "expected '{}' to be rejected",
+                reserved
+            );
+        }
+        assert_eq!(sanitize_file_name("console.txt").unwrap(), "console.txt");
+    }
+
+    /// Checks that a name accepted by [`sanitize_file_name`] is a single relative
+    /// path component, which stays inside the directory it is joined to.
+    fn assert_stays_in_dir(name: &str) {
+        let Ok(sanitized) = sanitize_file_name(name) else {
+            return;
+        };
+        let path = Path::new(&sanitized);
+        assert!(
+            !path.is_absolute() && !path.has_root(),
+            "{:?} is rooted",
+            name
+        );
+        assert!(
+            matches!(
+                path.components().collect::<Vec<_>>()[..],
+                [std::path::Component::Normal(component)] if component == sanitized.as_str()
+            ),
+            "{:?} became {:?}, which is not a single component",
+            name,
+            sanitized
+        );
+        let dir = Path::new("plugin-temp-dir");
+        assert_eq!(dir.join(path).parent(), Some(dir), "{:?} escapes", name);
+        // Checked explicitly, since `Path` only treats `\` as a separator on Windows.
+        assert!(
+            !sanitized.contains(['/', '\\'])
+                && !sanitized.contains(INVALID_FILE_NAME_CHARS)
+                && !sanitized.contains(char::is_control),
+            "{:?} became {:?}, which Windows cannot hold",
+            name,
+            sanitized
+        );
+        assert!(
+            !sanitized.ends_with(['.', ' ']),
+            "{:?} became {:?}, which ends with a dot or space",
+            name,
+            sanitized
+        );
+    }
+
+    #[test]
+    fn validate_share_reports_each_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.pdf");
+        std::fs::write(&path, b"%PDF-1.7").unwrap();
+        let photos = dir.path().join("photos");
+        std::fs::create_dir(&photos).unwrap();
+        let options = ShareOptions {
+            title: Some("{fileCount} files".to_string()),
+            text: Some("Hello".to_string()),
+            url: Some("https://example.com".to_string()),
+            files: Some(vec![
+                SharedFile {
+                    data: "aGk=".to_string(),
+                    ..file_with_mime("../notes.txt", "")
+                },
+                SharedFile::from_path(&path, "application/pdf"),
+                SharedFile::from_path(&photos, "inode/directory"),
+                SharedFile::from_reader("stream.bin", "", &b"streamed"[..]),
+                file_with_data("not base64!"),
+                file_with_mime("CON", "text/plain"),
+            ]),
+            ..Default::default()
+        };
+
+        let validated = validate_share(&options, &Config::default());
+        assert_eq!(validated.title.as_deref(), Some("6 files"));
+        assert_eq!(
+            validated.text.as_deref(),
+            Some("Hello\nhttps://example.com")
+        );
+        let files: Vec<_> = validated
+            .files
+            .iter()
+            .map(|file| {
+                (
+                    file.resolved_name.as_deref(),
+                    file.resolved_mime.as_deref(),
+                    file.byte_length,
+                    file.error.is_some(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            files,
+            [
+                (Some("notes.txt"), Some("text/plain"), Some(2), false),
+                (Some("report.pdf"), Some("application/pdf"), Some(8), false),
+                (Some("photos"), Some("inode/directory"), None, false),
+                (
+                    Some("stream.bin"),
+                    Some("application/octet-stream"),
+                    None,
+                    false
+                ),
+                (None, None, None, true),
+                (None, None, None, true),
+            ]
+        );
+        assert_eq!(validated.files[0].name, "../notes.txt");
+    }
+
+    #[test]
+    fn validate_share_reports_blocked_types() {
+        let options = ShareOptions {
+            files: Some(vec![
+                file_with_mime("run.sh", "application/x-sh"),
+                file_with_mime("notes.txt", "text/plain"),
+            ]),
+            ..Default::default()
+        };
+        let config = Config {
+            blocked_mime_types: vec!["application/x-sh".to_string()],
+            ..Default::default()
+        };
+
+        let validated = validate_share(&options, &config);
+        let error = validated.files[0].error.as_deref().unwrap();
+        assert!(error.contains("blocked"), "{}", error);
+        assert_eq!(validated.files[0].resolved_mime, None);
+        assert_eq!(validated.files[1].error, None);
+    }
+
+    #[test]
+    fn validate_share_without_text_or_files_is_empty() {
+        let validated = validate_share(&ShareOptions::default(), &Config::default());
+        assert_eq!(validated.title, None);
+        assert_eq!(validated.text, None);
+        assert!(validated.files.is_empty());
+    }
+
+    #[test]
+    fn validate_share_applies_the_default_options() {
+        let config = Config {
+            default_options: Some(ShareOptions {
+                title: Some("Shared from Example".to_string()),
+                url: Some("https://example.com".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let options = ShareOptions {
+            text: Some("Hello".to_string()),
+            ..Default::default()
+        };
+
+        let validated = validate_share(&options, &config);
+        assert_eq!(validated.title.as_deref(), Some("Shared from Example"));
+        assert_eq!(
+            validated.text.as_deref(),
+            Some("Hello\nhttps://example.com")
+        );
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn sanitized_names_stay_in_the_temp_dir(name in proptest::prelude::any::<String>()) {
+            assert_stays_in_dir(&name);
+        }
+
+        #[test]
+        fn sanitized_path_like_names_stay_in_the_temp_dir(name in "[a-zA-Z0-9./\\\\:~ <>\"|?*\\x00-\\x1f]{0,24}") {
+            assert_stays_in_dir(&name);
+        }
+    }
+}